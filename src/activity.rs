@@ -0,0 +1,43 @@
+use crate::models::view::{View, ViewEvent};
+use mongodb::bson::{doc, DateTime};
+use mongodb::Collection;
+
+/// Where playback starts are recorded: per-user history for recommendations
+/// and anonymous, regional events for the activity charts.
+#[derive(Clone)]
+pub struct ViewLog {
+    pub views: Collection<View>,
+    pub events: Collection<ViewEvent>,
+}
+
+impl ViewLog {
+    /// Records that `user` started watching `movie_id` from `region`.
+    pub async fn record(
+        &self,
+        user: &str,
+        movie_id: &str,
+        region: Option<String>,
+    ) -> mongodb::error::Result<()> {
+        let now = DateTime::now();
+        self.views
+            .update_one(
+                doc! { "user": user, "movie_id": movie_id },
+                doc! {
+                    "$inc": { "plays": 1_i64 },
+                    "$set": { "last_watched_at": now },
+                    "$setOnInsert": { "first_watched_at": now },
+                },
+            )
+            .upsert(true)
+            .await?;
+
+        let event = ViewEvent {
+            id: None,
+            movie_id: movie_id.to_string(),
+            region,
+            at: now,
+        };
+        self.events.insert_one(event).await?;
+        Ok(())
+    }
+}
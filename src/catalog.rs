@@ -0,0 +1,118 @@
+use mongodb::bson::{doc, DateTime, Document};
+
+// ── User-facing visibility ────────────────────────────────────────────────────
+//
+// Every read path that serves regular users combines its own filter with one
+// of these. Admin-only views skip them so scheduled items stay manageable.
+
+/// Matches documents whose `[from, until)` window contains `now`.
+/// Missing bounds are open, so unscheduled documents always match.
+fn window(from_field: &str, until_field: &str, now: DateTime) -> Document {
+    doc! {
+        "$and": [
+            { "$or": [{ from_field: null }, { from_field: { "$lte": now } }] },
+            { "$or": [{ until_field: null }, { until_field: { "$gt": now } }] },
+        ]
+    }
+}
+
+/// Matches documents that are not in the trash. Applies to admin views too;
+/// trashed documents are only reachable through the trash endpoints.
+pub fn not_deleted() -> Document {
+    doc! { "deleted_at": null }
+}
+
+/// Matches published documents. Those without a status predate the editorial
+/// workflow and were live already, so they count as published.
+pub fn published() -> Document {
+    doc! { "$or": [{ "status": null }, { "status": "published" }] }
+}
+
+/// Movies that may be shown to users right now.
+pub fn movie_visible(now: DateTime) -> Document {
+    doc! {
+        "$and": [not_deleted(), published(), window("available_from", "available_until", now)]
+    }
+}
+
+/// Lists that may be shown to users right now.
+pub fn list_visible(now: DateTime) -> Document {
+    doc! {
+        "$and": [not_deleted(), published(), window("visible_from", "visible_until", now)]
+    }
+}
+
+/// Movies licensed in `region`. Titles without an allow-list play everywhere
+/// except their blocked regions; an unknown region only sees unrestricted titles.
+pub fn movie_in_region(region: Option<&str>) -> Document {
+    let unrestricted = doc! {
+        "$or": [{ "allowed_regions": null }, { "allowed_regions": { "$size": 0 } }]
+    };
+    match region {
+        Some(region) => doc! {
+            "$and": [
+                { "$or": [unrestricted, { "allowed_regions": region }] },
+                { "blocked_regions": { "$ne": region } },
+            ]
+        },
+        None => unrestricted,
+    }
+}
+
+/// Lists for `region`: every curated list, the global activity charts and
+/// the region's own charts.
+pub fn list_in_region(region: Option<&str>) -> Document {
+    match region {
+        Some(region) => doc! {
+            "$or": [{ "chart.region": null }, { "chart.region": region }]
+        },
+        None => doc! { "chart.region": null },
+    }
+}
+
+/// Combines a handler's filter with a visibility filter.
+pub fn and(filter: Document, visibility: Document) -> Document {
+    match (filter.is_empty(), visibility.is_empty()) {
+        (true, _) => visibility,
+        (_, true) => filter,
+        _ => doc! { "$and": [filter, visibility] },
+    }
+}
+
+// ── Viewer ────────────────────────────────────────────────────────────────────
+
+/// Who a read is being served to, deciding which catalog filters apply.
+pub struct Viewer {
+    /// Resolved (or admin-previewed) region, upper-case ISO 3166-1 alpha-2.
+    pub region: Option<String>,
+
+    /// Admin view without a region preview: no availability filters at all,
+    /// so drafts and titles in review can be previewed.
+    pub unrestricted: bool,
+}
+
+impl Viewer {
+    /// Conditions a movie must meet to be shown to this viewer
+    /// (only "not in the trash" when unrestricted).
+    pub fn movie_filter(&self) -> Document {
+        if self.unrestricted {
+            return not_deleted();
+        }
+        and(
+            movie_visible(DateTime::now()),
+            movie_in_region(self.region.as_deref()),
+        )
+    }
+
+    /// Conditions a list must meet to be shown to this viewer
+    /// (only "not in the trash" when unrestricted).
+    pub fn list_filter(&self) -> Document {
+        if self.unrestricted {
+            return not_deleted();
+        }
+        and(
+            list_visible(DateTime::now()),
+            list_in_region(self.region.as_deref()),
+        )
+    }
+}
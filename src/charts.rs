@@ -0,0 +1,598 @@
+use crate::catalog::{and, movie_visible};
+use crate::models::chart::{ChartOverride, OverrideAction};
+use crate::models::list::{ChartKind, List};
+use crate::models::movie::Movie;
+use crate::models::view::ViewEvent;
+use futures_util::future::select;
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime, Document};
+use mongodb::Collection;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::env;
+use std::ops::AddAssign;
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+// ── Chart definitions ─────────────────────────────────────────────────────────
+
+/// Days view events are kept; covers the longest chart window.
+pub const VIEW_EVENT_RETENTION_DAYS: u64 = 7;
+
+/// How a chart scores titles from their view events.
+enum Measure {
+    /// Views over the last `window_hours`, each weighing `2^(-age / half-life)`.
+    Decayed {
+        window_hours: i64,
+        half_life_hours: f64,
+    },
+    /// How far views over the last `recent_hours` exceed the rate of the
+    /// `baseline_hours` before them; see `velocity`.
+    Velocity {
+        recent_hours: i64,
+        baseline_hours: i64,
+    },
+}
+
+/// How a chart is scored and how many titles the row holds.
+struct ChartSpec {
+    measure: Measure,
+    size: usize,
+}
+
+fn spec(kind: ChartKind) -> ChartSpec {
+    match kind {
+        ChartKind::TopTen => ChartSpec {
+            measure: Measure::Decayed {
+                window_hours: 24,
+                half_life_hours: 12.0,
+            },
+            size: 10,
+        },
+        ChartKind::Trending => ChartSpec {
+            measure: Measure::Velocity {
+                recent_hours: 24,
+                baseline_hours: 24 * (VIEW_EVENT_RETENTION_DAYS as i64 - 1),
+            },
+            size: 20,
+        },
+    }
+}
+
+/// Media types each chart is computed for; `None` mixes movies and series.
+const TYPES: [Option<&str>; 3] = [None, Some("movie"), Some("series")];
+
+/// How often the charts are recomputed (`CHART_REFRESH_MINS`, default 15).
+fn chart_refresh_mins() -> u64 {
+    env::var("CHART_REFRESH_MINS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15)
+}
+
+fn chart_title(kind: ChartKind, type_list: Option<&str>, region: Option<&str>) -> String {
+    let noun = match type_list {
+        Some("movie") => " Movies",
+        Some("series") => " Series",
+        _ => "",
+    };
+    let place = region.map(|r| format!(" in {}", r)).unwrap_or_default();
+    match kind {
+        ChartKind::TopTen => format!("Top 10{}{} Today", noun, place),
+        ChartKind::Trending if noun.is_empty() => format!("Trending Now{}", place),
+        ChartKind::Trending => format!("Trending{}{}", noun, place),
+    }
+}
+
+// ── Scoring ───────────────────────────────────────────────────────────────────
+
+/// Scores per movie, globally (`None`) and per region.
+type Tallies = HashMap<Option<String>, HashMap<String, f64>>;
+
+/// Sums `value` of each `{ _id: { movie_id, region }, ... }` group per movie,
+/// globally and for the group's region.
+fn by_region<T: Default + Copy + AddAssign>(
+    groups: &[Document],
+    value: impl Fn(&Document) -> Option<T>,
+) -> HashMap<Option<String>, HashMap<String, T>> {
+    let mut tallies: HashMap<Option<String>, HashMap<String, T>> = HashMap::new();
+    for group in groups {
+        let (Ok(key), Some(value)) = (group.get_document("_id"), value(group)) else {
+            continue;
+        };
+        let Ok(movie_id) = key.get_str("movie_id") else {
+            continue;
+        };
+        let region = key.get_str("region").ok().map(str::to_string);
+        *tallies
+            .entry(None)
+            .or_default()
+            .entry(movie_id.to_string())
+            .or_default() += value;
+        if region.is_some() {
+            *tallies
+                .entry(region)
+                .or_default()
+                .entry(movie_id.to_string())
+                .or_default() += value;
+        }
+    }
+    tallies
+}
+
+/// Sums `2^(-age / half-life)` over the window's views per movie and region,
+/// so a view from one half-life ago counts half as much as one just now.
+async fn decayed_counts(
+    events: &Collection<ViewEvent>,
+    window_hours: i64,
+    half_life_hours: f64,
+    now: DateTime,
+) -> mongodb::error::Result<Tallies> {
+    let since = DateTime::from_millis(now.timestamp_millis() - window_hours * 3_600_000);
+    let rate = std::f64::consts::LN_2 / (half_life_hours * 3_600_000.0);
+
+    let groups: Vec<Document> = events
+        .clone_with_type::<Document>()
+        .aggregate(vec![
+            doc! { "$match": { "at": { "$gte": since } } },
+            doc! { "$group": {
+                "_id": { "movie_id": "$movie_id", "region": "$region" },
+                "score": { "$sum": {
+                    "$exp": { "$multiply": [{ "$subtract": ["$at", now] }, rate] }
+                } },
+            } },
+        ])
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(by_region(&groups, |group| group.get_f64("score").ok()))
+}
+
+/// Views in the recent window and in the baseline before it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Windows {
+    recent: f64,
+    baseline: f64,
+}
+
+impl AddAssign for Windows {
+    fn add_assign(&mut self, other: Windows) {
+        self.recent += other.recent;
+        self.baseline += other.baseline;
+    }
+}
+
+/// Growth of a title's views: recent views minus those expected at the
+/// baseline rate, over the square root of the expectation. The root keeps a
+/// jump from 1 to 5 views from outranking one from 1,000 to 3,000, and a
+/// title with no baseline still needs a few views to chart. Only positive
+/// scores mean the title is trending.
+fn velocity(views: Windows, recent_hours: i64, baseline_hours: i64) -> f64 {
+    let expected = views.baseline * recent_hours as f64 / baseline_hours.max(1) as f64;
+    (views.recent - expected) / (expected + 1.0).sqrt()
+}
+
+/// `velocity` of every title viewed in either window, per movie and region;
+/// titles that are not speeding up are left out.
+async fn velocity_scores(
+    events: &Collection<ViewEvent>,
+    recent_hours: i64,
+    baseline_hours: i64,
+    now: DateTime,
+) -> mongodb::error::Result<Tallies> {
+    let recent_since = DateTime::from_millis(now.timestamp_millis() - recent_hours * 3_600_000);
+    let since =
+        DateTime::from_millis(now.timestamp_millis() - (recent_hours + baseline_hours) * 3_600_000);
+
+    let groups: Vec<Document> = events
+        .clone_with_type::<Document>()
+        .aggregate(vec![
+            doc! { "$match": { "at": { "$gte": since } } },
+            doc! { "$group": {
+                "_id": { "movie_id": "$movie_id", "region": "$region" },
+                "recent": { "$sum": { "$cond": [{ "$gte": ["$at", recent_since] }, 1.0, 0.0] } },
+                "baseline": { "$sum": { "$cond": [{ "$lt": ["$at", recent_since] }, 1.0, 0.0] } },
+            } },
+        ])
+        .await?
+        .try_collect()
+        .await?;
+
+    let windows = by_region(&groups, |group| {
+        Some(Windows {
+            recent: group.get_f64("recent").ok()?,
+            baseline: group.get_f64("baseline").ok()?,
+        })
+    });
+    Ok(windows
+        .into_iter()
+        .map(|(region, counts)| {
+            let scores = counts
+                .into_iter()
+                .map(|(movie_id, views)| (movie_id, velocity(views, recent_hours, baseline_hours)))
+                .filter(|(_, score)| *score > 0.0)
+                .collect();
+            (region, scores)
+        })
+        .collect())
+}
+
+async fn scores(
+    events: &Collection<ViewEvent>,
+    measure: &Measure,
+    now: DateTime,
+) -> mongodb::error::Result<Tallies> {
+    match *measure {
+        Measure::Decayed {
+            window_hours,
+            half_life_hours,
+        } => decayed_counts(events, window_hours, half_life_hours, now).await,
+        Measure::Velocity {
+            recent_hours,
+            baseline_hours,
+        } => velocity_scores(events, recent_hours, baseline_hours, now).await,
+    }
+}
+
+/// `is_series` of every title in `ids` that users may see right now.
+async fn visible_types(
+    movies: &Collection<Document>,
+    ids: &HashSet<&str>,
+    now: DateTime,
+) -> mongodb::error::Result<HashMap<String, bool>> {
+    let oids: Vec<ObjectId> = ids
+        .iter()
+        .filter_map(|id| ObjectId::parse_str(id).ok())
+        .collect();
+    let found: Vec<Document> = movies
+        .find(and(doc! { "_id": { "$in": oids } }, movie_visible(now)))
+        .projection(doc! { "is_series": 1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(found
+        .iter()
+        .filter_map(|movie| {
+            let id = movie.get_object_id("_id").ok()?.to_hex();
+            Some((id, movie.get_bool("is_series").unwrap_or(false)))
+        })
+        .collect())
+}
+
+fn matches_type(type_list: Option<&str>, is_series: bool) -> bool {
+    match type_list {
+        Some("movie") => !is_series,
+        Some("series") => is_series,
+        _ => true,
+    }
+}
+
+/// Applies excludes, then places pins: unpositioned ones first in creation
+/// order, positioned ones at their slot. The result holds at most `size` titles.
+fn apply_overrides(ranked: Vec<String>, overrides: &[&ChartOverride], size: usize) -> Vec<String> {
+    let excluded: HashSet<&str> = overrides
+        .iter()
+        .filter(|o| o.action == OverrideAction::Exclude)
+        .map(|o| o.movie_id.as_str())
+        .collect();
+    let pins: Vec<&ChartOverride> = overrides
+        .iter()
+        .filter(|o| o.action == OverrideAction::Pin && !excluded.contains(o.movie_id.as_str()))
+        .copied()
+        .collect();
+    let pinned: HashSet<&str> = pins.iter().map(|o| o.movie_id.as_str()).collect();
+
+    let mut content: Vec<String> = pins
+        .iter()
+        .filter(|o| o.position.is_none())
+        .map(|o| o.movie_id.clone())
+        .collect();
+    content.extend(
+        ranked
+            .into_iter()
+            .filter(|id| !excluded.contains(id.as_str()) && !pinned.contains(id.as_str())),
+    );
+
+    let mut positioned: Vec<&&ChartOverride> =
+        pins.iter().filter(|o| o.position.is_some()).collect();
+    positioned.sort_by_key(|o| o.position);
+    for pin in positioned {
+        let slot = pin.position.unwrap_or(1).saturating_sub(1) as usize;
+        content.insert(slot.min(content.len()), pin.movie_id.clone());
+    }
+
+    let mut seen = HashSet::new();
+    content.retain(|id| seen.insert(id.clone()));
+    content.truncate(size);
+    content
+}
+
+// ── Materialization ───────────────────────────────────────────────────────────
+
+/// Recomputes every chart and writes it as a list, one per kind, region and
+/// media type, so `get_lists` serves them like any other row. Regional rows
+/// exist for regions with recent views or a regional override; rows no
+/// longer produced are deleted.
+///
+/// A row an admin moved to the trash stays there untouched: it is neither
+/// rewritten nor deleted here, restoring it brings it back at the next
+/// refresh, and once the trash is purged the row is computed afresh.
+pub async fn refresh_charts(
+    events: &Collection<ViewEvent>,
+    movie_collection: &Collection<Movie>,
+    list_collection: &Collection<List>,
+    override_collection: &Collection<ChartOverride>,
+) -> mongodb::error::Result<()> {
+    let movies = movie_collection.clone_with_type::<Document>();
+    let lists = list_collection.clone_with_type::<Document>();
+    let now = DateTime::now();
+
+    let trashed: HashSet<(String, Option<String>, Option<String>)> = lists
+        .find(doc! { "chart": { "$ne": null }, "deleted_at": { "$ne": null } })
+        .projection(doc! { "chart": 1, "type_list": 1 })
+        .await?
+        .try_collect::<Vec<Document>>()
+        .await?
+        .iter()
+        .filter_map(|list| {
+            let chart = list.get_document("chart").ok()?;
+            Some((
+                chart.get_str("kind").ok()?.to_string(),
+                chart.get_str("region").ok().map(str::to_string),
+                list.get_str("type_list").ok().map(str::to_string),
+            ))
+        })
+        .collect();
+
+    let overrides: Vec<ChartOverride> = override_collection
+        .find(doc! {})
+        .sort(doc! { "created_at": 1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    for kind in ChartKind::ALL {
+        let spec = spec(kind);
+        let tallies = scores(events, &spec.measure, now).await?;
+        let kind_overrides: Vec<&ChartOverride> =
+            overrides.iter().filter(|o| o.kind == kind).collect();
+
+        let mut ids: HashSet<&str> = tallies
+            .values()
+            .flat_map(|counts| counts.keys().map(String::as_str))
+            .collect();
+        ids.extend(kind_overrides.iter().map(|o| o.movie_id.as_str()));
+        let types = visible_types(&movies, &ids, now).await?;
+
+        let mut regions: BTreeSet<Option<String>> = tallies.keys().cloned().collect();
+        regions.insert(None);
+        regions.extend(
+            kind_overrides
+                .iter()
+                .filter_map(|o| o.region.clone())
+                .map(Some),
+        );
+
+        for region in &regions {
+            for type_list in TYPES {
+                let key = (
+                    kind.name().to_string(),
+                    region.clone(),
+                    type_list.map(str::to_string),
+                );
+                if trashed.contains(&key) {
+                    continue;
+                }
+
+                let mut ranked: Vec<(&String, f64)> = tallies
+                    .get(region)
+                    .into_iter()
+                    .flatten()
+                    .filter(|(id, _)| {
+                        types
+                            .get(*id)
+                            .is_some_and(|&series| matches_type(type_list, series))
+                    })
+                    .map(|(id, score)| (id, *score))
+                    .collect();
+                ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+                let ranked: Vec<String> = ranked.into_iter().map(|(id, _)| id.clone()).collect();
+
+                // Pins must be watchable and of the row's type like any ranked title.
+                let applicable: Vec<&ChartOverride> = kind_overrides
+                    .iter()
+                    .filter(|o| o.region.is_none() || o.region == *region)
+                    .filter(|o| o.type_list.is_none() || o.type_list.as_deref() == type_list)
+                    .filter(|o| {
+                        o.action == OverrideAction::Exclude
+                            || types
+                                .get(&o.movie_id)
+                                .is_some_and(|&series| matches_type(type_list, series))
+                    })
+                    .copied()
+                    .collect();
+                let content = apply_overrides(ranked, &applicable, spec.size);
+
+                let region_key = region.clone().map_or(Bson::Null, Bson::String);
+                let type_key = type_list.map_or(Bson::Null, |t| Bson::String(t.to_string()));
+                lists
+                    .update_one(
+                        doc! {
+                            "chart.kind": kind.name(),
+                            "chart.region": region_key,
+                            "type_list": type_key,
+                            "deleted_at": null,
+                        },
+                        doc! {
+                            "$set": {
+                                "title": chart_title(kind, type_list, region.as_deref()),
+                                "content": content,
+                                "resolved_at": now,
+                                "updated_at": now,
+                            },
+                            "$setOnInsert": { "created_at": now },
+                            "$inc": { "version": 1_i64 },
+                        },
+                    )
+                    .upsert(true)
+                    .await?;
+            }
+        }
+    }
+
+    lists
+        .delete_many(doc! {
+            "chart": { "$ne": null },
+            "resolved_at": { "$lt": now },
+            "deleted_at": null,
+        })
+        .await?;
+    Ok(())
+}
+
+/// Asks the chart builder for a refresh ahead of schedule. Requests made
+/// while one is pending or running are folded into a single extra refresh.
+#[derive(Default)]
+pub struct ChartRefresh {
+    requested: Notify,
+}
+
+impl ChartRefresh {
+    pub fn request(&self) {
+        self.requested.notify_one();
+    }
+}
+
+/// Spawns a background task that recomputes the charts at startup, then
+/// every `CHART_REFRESH_MINS` and whenever a refresh is requested.
+pub fn spawn_chart_builder(
+    events: Collection<ViewEvent>,
+    movie_collection: Collection<Movie>,
+    list_collection: Collection<List>,
+    override_collection: Collection<ChartOverride>,
+    refresh: Arc<ChartRefresh>,
+) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(chart_refresh_mins() * 60));
+
+        loop {
+            select(pin!(interval.tick()), pin!(refresh.requested.notified())).await;
+            if let Err(e) = refresh_charts(
+                &events,
+                &movie_collection,
+                &list_collection,
+                &override_collection,
+            )
+            .await
+            {
+                log::warn!("Chart refresh failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn override_of(movie_id: &str, action: OverrideAction, position: Option<u32>) -> ChartOverride {
+        ChartOverride {
+            id: None,
+            kind: ChartKind::TopTen,
+            region: None,
+            type_list: None,
+            movie_id: movie_id.to_string(),
+            action,
+            position,
+            created_by: String::new(),
+            created_at: DateTime::now(),
+        }
+    }
+
+    fn apply(ranked: &[&str], overrides: &[ChartOverride], size: usize) -> Vec<String> {
+        let overrides: Vec<&ChartOverride> = overrides.iter().collect();
+        apply_overrides(ids(ranked), &overrides, size)
+    }
+
+    #[test]
+    fn without_overrides_keeps_the_ranking_up_to_size() {
+        assert_eq!(apply(&["a", "b", "c"], &[], 2), ids(&["a", "b"]));
+    }
+
+    #[test]
+    fn excludes_win_over_ranking_and_pins() {
+        let overrides = [
+            override_of("b", OverrideAction::Exclude, None),
+            override_of("c", OverrideAction::Pin, Some(1)),
+            override_of("c", OverrideAction::Exclude, None),
+        ];
+        assert_eq!(
+            apply(&["a", "b", "c", "d"], &overrides, 10),
+            ids(&["a", "d"])
+        );
+    }
+
+    #[test]
+    fn unpositioned_pins_go_first_in_creation_order() {
+        let overrides = [
+            override_of("x", OverrideAction::Pin, None),
+            override_of("y", OverrideAction::Pin, None),
+        ];
+        assert_eq!(
+            apply(&["a", "y", "b"], &overrides, 3),
+            ids(&["x", "y", "a"])
+        );
+    }
+
+    #[test]
+    fn positioned_pins_take_their_slot() {
+        let overrides = [
+            override_of("z", OverrideAction::Pin, Some(3)),
+            override_of("x", OverrideAction::Pin, Some(1)),
+            override_of("late", OverrideAction::Pin, Some(50)),
+        ];
+        assert_eq!(
+            apply(&["a", "b", "z", "c"], &overrides, 10),
+            ids(&["x", "a", "z", "b", "c", "late"])
+        );
+        assert_eq!(apply(&["a", "b"], &overrides, 2), ids(&["x", "a"]));
+    }
+
+    #[test]
+    fn velocity_rewards_growth_over_volume() {
+        let steady = Windows {
+            recent: 1000.0,
+            baseline: 6000.0,
+        };
+        assert_eq!(velocity(steady, 24, 144), 0.0);
+
+        let fading = Windows {
+            recent: 10.0,
+            baseline: 600.0,
+        };
+        assert!(velocity(fading, 24, 144) < 0.0);
+
+        let surging = Windows {
+            recent: 3000.0,
+            baseline: 6000.0,
+        };
+        let blip = Windows {
+            recent: 5.0,
+            baseline: 6.0,
+        };
+        let new_title = Windows {
+            recent: 1.0,
+            baseline: 0.0,
+        };
+        assert!(velocity(surging, 24, 144) > velocity(blip, 24, 144));
+        assert!(velocity(blip, 24, 144) > velocity(new_title, 24, 144));
+        assert!(velocity(new_title, 24, 144) > 0.0);
+    }
+}
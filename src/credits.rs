@@ -0,0 +1,210 @@
+use crate::models::person::{CastMember, Credit, Person};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use mongodb::Collection;
+use std::collections::{HashMap, HashSet};
+
+// ── Credits on a title ────────────────────────────────────────────────────────
+
+/// Canonical episode key `sNNeNN` for input such as `S1E2` or `s01e02`;
+/// `None` if it is not a season and episode number.
+pub fn normalize_episode(raw: &str) -> Option<String> {
+    let key = raw.trim().to_ascii_lowercase();
+    let (season, episode) = key.strip_prefix('s')?.split_once('e')?;
+    let number = |digits: &str| -> Option<u32> {
+        if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    Some(format!("s{:02}e{:02}", number(season)?, number(episode)?))
+}
+
+/// Keeps only the newest credit set of each episode (and of the whole
+/// title). A replace inserts its set before deleting older ones, so for a
+/// moment, or after a lost race, more than one set may be stored.
+pub fn current_sets(credits: Vec<Credit>) -> Vec<Credit> {
+    let mut newest: HashMap<Option<String>, Option<ObjectId>> = HashMap::new();
+    for credit in &credits {
+        let set = newest.entry(credit.episode.clone()).or_default();
+        *set = (*set).max(credit.set_id);
+    }
+    credits
+        .into_iter()
+        .filter(|credit| newest.get(&credit.episode) == Some(&credit.set_id))
+        .collect()
+}
+
+/// Everyone credited on `movie_id`, whole-title credits first and then each
+/// episode's, by role and billing order. Credits whose person is gone are dropped.
+pub async fn movie_credits(
+    credit_collection: &Collection<Credit>,
+    person_collection: &Collection<Person>,
+    movie_id: &str,
+) -> mongodb::error::Result<Vec<CastMember>> {
+    let credits: Vec<Credit> = credit_collection
+        .find(doc! { "movie_id": movie_id })
+        .await?
+        .try_collect()
+        .await?;
+    let mut credits = current_sets(credits);
+    credits.sort_by(|a, b| (&a.episode, a.role, a.order).cmp(&(&b.episode, b.role, b.order)));
+
+    let people = people_by_id(
+        person_collection,
+        credits.iter().map(|c| c.person_id.as_str()),
+    )
+    .await?;
+    Ok(credits
+        .into_iter()
+        .filter_map(|credit| {
+            let person = people.get(&credit.person_id)?;
+            Some(CastMember {
+                person_id: credit.person_id,
+                name: person.name.clone(),
+                photo: person.photo.clone(),
+                role: credit.role,
+                character: credit.character,
+                episode: credit.episode,
+                order: credit.order,
+            })
+        })
+        .collect())
+}
+
+/// The people among `ids`, keyed by hex id.
+pub async fn people_by_id<'a>(
+    person_collection: &Collection<Person>,
+    ids: impl Iterator<Item = &'a str>,
+) -> mongodb::error::Result<HashMap<String, Person>> {
+    let oids: Vec<ObjectId> = ids
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .filter_map(|id| ObjectId::parse_str(id).ok())
+        .collect();
+    if oids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(person_collection
+        .find(doc! { "_id": { "$in": oids } })
+        .await?
+        .try_collect::<Vec<Person>>()
+        .await?
+        .into_iter()
+        .filter_map(|person| Some((person.id?.to_hex(), person)))
+        .collect())
+}
+
+// ── Credits of a person ───────────────────────────────────────────────────────
+
+/// The credits of `person_id` in the current set of each title. Which set is
+/// current depends on the whole cast, so every credit of those titles is read.
+pub async fn person_credits(
+    credit_collection: &Collection<Credit>,
+    person_id: &str,
+) -> mongodb::error::Result<Vec<Credit>> {
+    let movie_ids = credit_collection
+        .distinct("movie_id", doc! { "person_id": person_id })
+        .await?;
+    if movie_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut by_title: HashMap<String, Vec<Credit>> = HashMap::new();
+    let mut cursor = credit_collection
+        .find(doc! { "movie_id": { "$in": movie_ids } })
+        .await?;
+    while let Some(credit) = cursor.try_next().await? {
+        by_title
+            .entry(credit.movie_id.clone())
+            .or_default()
+            .push(credit);
+    }
+    Ok(by_title
+        .into_values()
+        .flat_map(current_sets)
+        .filter(|credit| credit.person_id == person_id)
+        .collect())
+}
+
+// ── Similarity input ──────────────────────────────────────────────────────────
+
+/// The people credited on each title, episodes included, keyed by movie id.
+pub async fn people_by_title(
+    credits: &Collection<Document>,
+) -> mongodb::error::Result<HashMap<String, HashSet<String>>> {
+    let rows: Vec<Document> = credits
+        .find(doc! {})
+        .projection(doc! { "movie_id": 1, "person_id": 1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut people: HashMap<String, HashSet<String>> = HashMap::new();
+    for row in rows {
+        let (Ok(movie_id), Ok(person_id)) = (row.get_str("movie_id"), row.get_str("person_id"))
+        else {
+            continue;
+        };
+        people
+            .entry(movie_id.to_string())
+            .or_default()
+            .insert(person_id.to_string());
+    }
+    Ok(people)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::person::CreditRole;
+    use mongodb::bson::DateTime;
+
+    fn credit(person_id: &str, episode: Option<&str>, set_id: Option<ObjectId>) -> Credit {
+        Credit {
+            id: None,
+            person_id: person_id.to_string(),
+            movie_id: "m".to_string(),
+            episode: episode.map(str::to_string),
+            role: CreditRole::Actor,
+            character: None,
+            order: 1,
+            set_id,
+            created_at: DateTime::now(),
+        }
+    }
+
+    #[test]
+    fn normalizes_episode_keys() {
+        assert_eq!(normalize_episode("s01e02").as_deref(), Some("s01e02"));
+        assert_eq!(normalize_episode(" S1E2 ").as_deref(), Some("s01e02"));
+        assert_eq!(normalize_episode("s2e110").as_deref(), Some("s02e110"));
+        assert_eq!(normalize_episode("S10E05").as_deref(), Some("s10e05"));
+    }
+
+    #[test]
+    fn rejects_anything_else_as_an_episode() {
+        for raw in [
+            "", "e01", "s01", "s01e", "se02", "1x02", "s01e02x", "s-1e02", "s01 e02",
+        ] {
+            assert_eq!(normalize_episode(raw), None, "{raw}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_newest_set_per_episode() {
+        let (older, newer) = (ObjectId::new(), ObjectId::new());
+        let credits = vec![
+            credit("legacy", None, None),
+            credit("a", None, Some(older)),
+            credit("b", None, Some(newer)),
+            credit("c", Some("s01e01"), None),
+            credit("d", Some("s01e02"), Some(older)),
+        ];
+        let people: Vec<String> = current_sets(credits)
+            .into_iter()
+            .map(|credit| credit.person_id)
+            .collect();
+        assert_eq!(people, vec!["b", "c", "d"]);
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt;
+use tokio::sync::broadcast;
+
+/// Catalog changes other parts of the service may want to react to
+/// (cache invalidation, notifications). Ids are hex ObjectId strings.
+#[derive(Debug, Clone)]
+pub enum CatalogEvent {
+    MovieLive(String),
+    MovieExpired(String),
+    ListVisible(String),
+    ListHidden(String),
+}
+
+impl fmt::Display for CatalogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogEvent::MovieLive(id) => write!(f, "movie {} went live", id),
+            CatalogEvent::MovieExpired(id) => write!(f, "movie {} expired", id),
+            CatalogEvent::ListVisible(id) => write!(f, "list {} became visible", id),
+            CatalogEvent::ListHidden(id) => write!(f, "list {} was hidden", id),
+        }
+    }
+}
+
+/// Fan-out channel for catalog events. Cloning shares the same channel;
+/// call `subscribe` once per listener.
+#[derive(Clone)]
+pub struct CatalogEvents {
+    sender: broadcast::Sender<CatalogEvent>,
+}
+
+impl Default for CatalogEvents {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        CatalogEvents { sender }
+    }
+}
+
+impl CatalogEvents {
+    /// Publishes an event. Having no listeners is not an error.
+    pub fn emit(&self, event: CatalogEvent) {
+        log::info!("Catalog event: {}", event);
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<CatalogEvent> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,275 @@
+use actix_web::web::Bytes;
+use mongodb::bson::{oid::ObjectId, Bson, Document};
+use serde_json::Value;
+use thiserror::Error;
+
+// ── Exportable collections ────────────────────────────────────────────────────
+
+/// Fields that may be exported (and filtered on) per collection, in CSV column
+/// order. Anything sensitive, like password hashes, is simply not listed.
+pub fn exportable_fields(collection: &str) -> Option<&'static [&'static str]> {
+    match collection {
+        "movies" => Some(&[
+            "_id",
+            "external_id",
+            "title",
+            "translations",
+            "desc",
+            "img",
+            "img_title",
+            "img_sm",
+            "blurhash",
+            "trailer",
+            "video",
+            "year",
+            "limit",
+            "genre",
+            "genres",
+            "is_series",
+            "featured_boost",
+            "ratings",
+            "status",
+            "available_from",
+            "available_until",
+            "allowed_regions",
+            "blocked_regions",
+            "subtitles",
+            "audio_tracks",
+            "revision",
+            "created_at",
+            "updated_at",
+        ]),
+        "lists" => Some(&[
+            "_id",
+            "title",
+            "translations",
+            "type_list",
+            "genre",
+            "content",
+            "rule",
+            "status",
+            "visible_from",
+            "visible_until",
+            "version",
+            "created_at",
+            "updated_at",
+        ]),
+        "users" => Some(&[
+            "_id",
+            "username",
+            "email",
+            "profile_pic",
+            "is_admin",
+            "plan",
+            "role",
+            "language",
+            "created_at",
+            "updated_at",
+        ]),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(ExportFormat::Csv),
+            "ndjson" | "jsonl" => Some(ExportFormat::Ndjson),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Unknown field: {0}")]
+    UnknownField(String),
+
+    #[error("filter must be a JSON object of field: value pairs")]
+    InvalidFilter,
+
+    #[error("Filter values must be strings, numbers, booleans or null: {0}")]
+    NonScalarFilter(String),
+}
+
+// ── Query building ────────────────────────────────────────────────────────────
+
+/// Resolves `?fields=a,b` against the collection's exportable fields;
+/// no selection means all of them.
+pub fn select_fields(
+    allowed: &'static [&'static str],
+    selection: Option<&str>,
+) -> Result<Vec<&'static str>, ExportError> {
+    let Some(selection) = selection.filter(|s| !s.trim().is_empty()) else {
+        return Ok(allowed.to_vec());
+    };
+
+    selection
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            allowed
+                .iter()
+                .find(|allowed| **allowed == field)
+                .copied()
+                .ok_or_else(|| ExportError::UnknownField(field.to_string()))
+        })
+        .collect()
+}
+
+/// Parses `?filter={"genre":"Action","is_series":true}` into equality
+/// conditions on exportable fields. Operators are not accepted.
+pub fn parse_filter(
+    allowed: &'static [&'static str],
+    raw: Option<&str>,
+) -> Result<Document, ExportError> {
+    let Some(raw) = raw else {
+        return Ok(Document::new());
+    };
+    let Ok(Value::Object(conditions)) = serde_json::from_str::<Value>(raw) else {
+        return Err(ExportError::InvalidFilter);
+    };
+
+    let mut filter = Document::new();
+    for (field, value) in conditions {
+        if !allowed.contains(&field.as_str()) {
+            return Err(ExportError::UnknownField(field));
+        }
+        if value.is_object() || value.is_array() {
+            return Err(ExportError::NonScalarFilter(field));
+        }
+        let value = match (field.as_str(), &value) {
+            ("_id", Value::String(hex)) => ObjectId::parse_str(hex)
+                .map(Bson::ObjectId)
+                .unwrap_or(Bson::String(hex.clone())),
+            _ => Bson::try_from(value).map_err(|_| ExportError::InvalidFilter)?,
+        };
+        filter.insert(field, value);
+    }
+    Ok(filter)
+}
+
+/// Projection returning exactly `fields` (excluding `_id` unless asked for).
+pub fn projection(fields: &[&str]) -> Document {
+    let mut projection = Document::new();
+    if !fields.contains(&"_id") {
+        projection.insert("_id", 0);
+    }
+    for field in fields {
+        projection.insert(*field, 1);
+    }
+    projection
+}
+
+// ── Encoding ──────────────────────────────────────────────────────────────────
+
+/// Plain JSON for analytics tools: ids as hex strings and dates as RFC 3339
+/// instead of extended-JSON wrappers.
+fn plain_json(value: Bson) -> Value {
+    match value {
+        Bson::ObjectId(oid) => Value::String(oid.to_hex()),
+        Bson::DateTime(date) => date
+            .try_to_rfc3339_string()
+            .map(Value::String)
+            .unwrap_or(Value::Null),
+        Bson::Array(items) => Value::Array(items.into_iter().map(plain_json).collect()),
+        Bson::Document(document) => Value::Object(
+            document
+                .into_iter()
+                .map(|(key, value)| (key, plain_json(value)))
+                .collect(),
+        ),
+        other => other.into_relaxed_extjson(),
+    }
+}
+
+/// One CSV cell: scalars as text, lists of scalars joined with `;` (as the
+/// importer reads them), anything nested as JSON.
+fn csv_cell(value: Option<Bson>) -> String {
+    match value.map(plain_json) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s,
+        Some(Value::Array(items)) if items.iter().all(|i| !i.is_object() && !i.is_array()) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => s,
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(";"),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn csv_line<I, S>(cells: I) -> Bytes
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    // Writing to a Vec cannot fail.
+    let _ = writer.write_record(cells);
+    Bytes::from(writer.into_inner().unwrap_or_default())
+}
+
+/// Bytes sent before the first document.
+pub fn preamble(format: ExportFormat, fields: &[&str]) -> Bytes {
+    match format {
+        ExportFormat::Csv => csv_line(fields),
+        ExportFormat::Ndjson => Bytes::new(),
+        ExportFormat::Json => Bytes::from_static(b"["),
+    }
+}
+
+/// The encoding of the `index`-th document.
+pub fn encode(format: ExportFormat, fields: &[&str], index: usize, document: Document) -> Bytes {
+    match format {
+        ExportFormat::Csv => {
+            let mut document = document;
+            csv_line(fields.iter().map(|field| csv_cell(document.remove(*field))))
+        }
+        ExportFormat::Ndjson => {
+            let mut line = plain_json(Bson::Document(document)).to_string();
+            line.push('\n');
+            Bytes::from(line)
+        }
+        ExportFormat::Json => {
+            let item = plain_json(Bson::Document(document)).to_string();
+            let separator = if index == 0 { "" } else { "," };
+            Bytes::from(format!("{}{}", separator, item))
+        }
+    }
+}
+
+/// Bytes sent after the last document.
+pub fn epilogue(format: ExportFormat) -> Bytes {
+    match format {
+        ExportFormat::Json => Bytes::from_static(b"]"),
+        _ => Bytes::new(),
+    }
+}
@@ -0,0 +1,188 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use rand::Rng;
+use std::env;
+
+// ── Featured policy ───────────────────────────────────────────────────────────
+
+/// Weight every title keeps regardless of its signals, so the whole catalog
+/// can still reach the billboard now and then.
+const BASE_WEIGHT: f64 = 0.05;
+
+/// A title eligible for the billboard and the signals it is weighted by.
+pub struct Candidate {
+    pub id: ObjectId,
+    /// When the title became available: `available_from`, else `created_at`.
+    pub released_at: Option<DateTime>,
+    /// Recent weighted views and ratings from the recommendation model.
+    pub popularity: f64,
+    /// Editorial `featured_boost`.
+    pub boost: f64,
+}
+
+/// How the featured billboard weighs titles, configured from the environment:
+/// - `FEATURED_RECENCY_WEIGHT`, `FEATURED_POPULARITY_WEIGHT` and
+///   `FEATURED_BOOST_WEIGHT`: how much each signal counts (default 1 each).
+/// - `FEATURED_RECENCY_HALF_LIFE_DAYS`: age at which a title's recency counts half (default 90).
+/// - `FEATURED_REPEAT_HOURS`: how long a title shown to a user is held back for them (default 24).
+pub struct FeaturedPolicy {
+    recency_weight: f64,
+    popularity_weight: f64,
+    boost_weight: f64,
+    half_life_days: f64,
+    pub repeat_hours: u64,
+}
+
+fn env_f64(name: &str, default: f64) -> f64 {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+impl FeaturedPolicy {
+    pub fn from_env() -> Self {
+        FeaturedPolicy {
+            recency_weight: env_f64("FEATURED_RECENCY_WEIGHT", 1.0),
+            popularity_weight: env_f64("FEATURED_POPULARITY_WEIGHT", 1.0),
+            boost_weight: env_f64("FEATURED_BOOST_WEIGHT", 1.0),
+            half_life_days: env_f64("FEATURED_RECENCY_HALF_LIFE_DAYS", 90.0).max(1.0),
+            repeat_hours: env::var("FEATURED_REPEAT_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(24),
+        }
+    }
+
+    /// Recency decays by half every half-life; popularity is log-scaled
+    /// against the most popular candidate so one hit does not drown the rest.
+    fn weight(&self, candidate: &Candidate, max_popularity: f64, now: DateTime) -> f64 {
+        let recency = candidate.released_at.map_or(0.0, |at| {
+            let age_days = (now.timestamp_millis() - at.timestamp_millis()).max(0) as f64
+                / (24.0 * 60.0 * 60.0 * 1000.0);
+            0.5_f64.powf(age_days / self.half_life_days)
+        });
+        let popularity = if max_popularity > 0.0 {
+            candidate.popularity.ln_1p() / max_popularity.ln_1p()
+        } else {
+            0.0
+        };
+
+        BASE_WEIGHT
+            + self.recency_weight.max(0.0) * recency
+            + self.popularity_weight.max(0.0) * popularity
+            + self.boost_weight.max(0.0) * candidate.boost.max(0.0)
+    }
+
+    /// Draws up to `count` distinct candidates, each with probability in
+    /// proportion to its weight (weighted sampling without replacement: the
+    /// largest `u^(1/w)` keys win).
+    pub fn pick(&self, candidates: &[Candidate], count: usize) -> Vec<ObjectId> {
+        let now = DateTime::now();
+        let max_popularity = candidates
+            .iter()
+            .map(|candidate| candidate.popularity)
+            .fold(0.0, f64::max);
+
+        let mut rng = rand::thread_rng();
+        let mut keyed: Vec<(f64, ObjectId)> = candidates
+            .iter()
+            .map(|candidate| {
+                let weight = self.weight(candidate, max_popularity, now);
+                let u: f64 = rng.gen_range(f64::EPSILON..1.0);
+                (u.powf(1.0 / weight), candidate.id)
+            })
+            .collect();
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+        keyed.into_iter().take(count).map(|(_, id)| id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+    fn policy() -> FeaturedPolicy {
+        FeaturedPolicy {
+            recency_weight: 1.0,
+            popularity_weight: 1.0,
+            boost_weight: 1.0,
+            half_life_days: 90.0,
+            repeat_hours: 24,
+        }
+    }
+
+    fn candidate(released_days_ago: Option<i64>, popularity: f64, boost: f64) -> Candidate {
+        let now = DateTime::now().timestamp_millis();
+        Candidate {
+            id: ObjectId::new(),
+            released_at: released_days_ago
+                .map(|days| DateTime::from_millis(now - days * DAY_MILLIS)),
+            popularity,
+            boost,
+        }
+    }
+
+    #[test]
+    fn recency_halves_every_half_life() {
+        let now = DateTime::now();
+        let fresh = policy().weight(&candidate(Some(0), 0.0, 0.0), 0.0, now);
+        let aged = policy().weight(&candidate(Some(90), 0.0, 0.0), 0.0, now);
+        let undated = policy().weight(&candidate(None, 0.0, 0.0), 0.0, now);
+        assert!((fresh - (BASE_WEIGHT + 1.0)).abs() < 1e-6);
+        assert!((aged - (BASE_WEIGHT + 0.5)).abs() < 1e-6);
+        assert_eq!(undated, BASE_WEIGHT);
+    }
+
+    #[test]
+    fn popularity_is_log_scaled_against_the_top() {
+        let now = DateTime::now();
+        let top = policy().weight(&candidate(None, 99.0, 0.0), 99.0, now);
+        let tenth = policy().weight(&candidate(None, 9.0, 0.0), 99.0, now);
+        assert!((top - (BASE_WEIGHT + 1.0)).abs() < 1e-9);
+        assert!((tenth - (BASE_WEIGHT + 0.5)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn negative_weights_and_boosts_count_as_zero() {
+        let policy = FeaturedPolicy {
+            boost_weight: -1.0,
+            ..policy()
+        };
+        let now = DateTime::now();
+        assert_eq!(
+            policy.weight(&candidate(None, 0.0, 5.0), 0.0, now),
+            BASE_WEIGHT
+        );
+        assert_eq!(
+            self::policy().weight(&candidate(None, 0.0, -5.0), 0.0, now),
+            BASE_WEIGHT
+        );
+    }
+
+    #[test]
+    fn picks_distinct_titles_up_to_count() {
+        let candidates: Vec<Candidate> = (0..5).map(|_| candidate(Some(10), 1.0, 0.0)).collect();
+        let picks = policy().pick(&candidates, 3);
+        assert_eq!(picks.len(), 3);
+        assert_eq!(
+            picks.iter().collect::<std::collections::HashSet<_>>().len(),
+            3
+        );
+
+        assert_eq!(policy().pick(&candidates, 10).len(), 5);
+        assert!(policy().pick(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn heavier_titles_are_picked_more_often() {
+        let candidates = vec![candidate(None, 0.0, 5.0), candidate(None, 0.0, 0.0)];
+        let boosted = candidates[0].id;
+        let wins = (0..1000)
+            .filter(|_| policy().pick(&candidates, 1) == vec![boosted])
+            .count();
+        // Weights 5.05 to 0.05: the boosted title should win about 99% of draws.
+        assert!(wins > 950, "{wins}");
+    }
+}
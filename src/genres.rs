@@ -0,0 +1,391 @@
+use crate::locale::DEFAULT_LOCALE;
+use crate::models::genre::{Genre, GenreMigration, MigrationStatus};
+use crate::models::revision::MovieRevision;
+use crate::revisions::{write_movie, RevisionError};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, to_bson, DateTime, Document};
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// ── Slugs ─────────────────────────────────────────────────────────────────────
+
+/// Lower-case words joined by dashes: `"Action & Adventure "` becomes
+/// `action-adventure`. Letters and digits of any script are kept, so
+/// `"Ação"` becomes `ação`. Empty when the text has no letters or digits.
+pub fn slugify(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Legacy free-text genres hold several genres separated by commas, slashes
+/// or pipes. `&` is kept: "Action & Adventure" is one genre.
+pub fn split_genres(text: &str) -> Vec<&str> {
+    text.split([',', '/', '|'])
+        .map(str::trim)
+        .filter(|part| !slugify(part).is_empty())
+        .collect()
+}
+
+/// The slugs of the genres named in legacy text, in order, without repeats.
+pub fn genre_slugs(text: &str) -> Vec<String> {
+    let mut slugs: Vec<String> = Vec::new();
+    for slug in split_genres(text).into_iter().map(slugify) {
+        if !slugs.contains(&slug) {
+            slugs.push(slug);
+        }
+    }
+    slugs
+}
+
+/// A display name for a genre found in legacy text: whitespace collapsed,
+/// and words capitalized if it was written all lower-case.
+fn tidy_name(part: &str) -> String {
+    let words: Vec<&str> = part.split_whitespace().collect();
+    if part.chars().any(char::is_uppercase) {
+        return words.join(" ");
+    }
+    words
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// ── Filters ───────────────────────────────────────────────────────────────────
+
+/// Movies in genre `genre`, given as a slug or as legacy text.
+pub fn movie_genre_filter(genre: &str) -> Document {
+    doc! { "$or": [{ "genres": slugify(genre) }, { "genre": genre }] }
+}
+
+/// Lists for genre `genre`; migrated lists store the slug.
+pub fn list_genre_filter(genre: &str) -> Document {
+    doc! { "genre": { "$in": [genre, slugify(genre)] } }
+}
+
+// ── Validation ────────────────────────────────────────────────────────────────
+
+/// The slugs in `slugs` that are not in the taxonomy.
+pub async fn unknown_genres(
+    genres: &Collection<Genre>,
+    slugs: &[String],
+) -> mongodb::error::Result<Vec<String>> {
+    if slugs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let known: HashSet<String> = genres
+        .find(doc! { "slug": { "$in": slugs } })
+        .await?
+        .try_collect::<Vec<Genre>>()
+        .await?
+        .into_iter()
+        .map(|genre| genre.slug)
+        .collect();
+    Ok(slugs
+        .iter()
+        .filter(|slug| !known.contains(*slug))
+        .cloned()
+        .collect())
+}
+
+/// True if making `parent` the parent of `slug` would put `slug` among its
+/// own ancestors.
+pub async fn creates_cycle(
+    genres: &Collection<Genre>,
+    slug: &str,
+    parent: &str,
+) -> mongodb::error::Result<bool> {
+    let mut seen = HashSet::new();
+    let mut current = Some(parent.to_string());
+    while let Some(ancestor) = current {
+        if ancestor == slug || !seen.insert(ancestor.clone()) {
+            return Ok(true);
+        }
+        current = genres
+            .find_one(doc! { "slug": &ancestor })
+            .await?
+            .and_then(|genre| genre.parent);
+    }
+    Ok(false)
+}
+
+// ── Migration ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MigrationReport {
+    /// Slugs of genres created from legacy text.
+    pub genres_created: Vec<String>,
+    pub movies_updated: u64,
+    pub lists_updated: u64,
+    /// Movies that kept changing while being migrated; run the migration again.
+    pub movies_skipped: Vec<String>,
+}
+
+/// The taxonomy keyed by slug.
+pub async fn known_genres(
+    genre_collection: &Collection<Genre>,
+) -> mongodb::error::Result<HashMap<String, Genre>> {
+    Ok(genre_collection
+        .find(doc! {})
+        .await?
+        .try_collect::<Vec<Genre>>()
+        .await?
+        .into_iter()
+        .map(|genre| (genre.slug.clone(), genre))
+        .collect())
+}
+
+/// The slug for each genre in `text`, creating missing genres on the way;
+/// the slugs of created genres are added to `created`.
+pub async fn resolve_genres(
+    genre_collection: &Collection<Genre>,
+    known: &mut HashMap<String, Genre>,
+    created: &mut Vec<String>,
+    text: &str,
+) -> mongodb::error::Result<Vec<String>> {
+    let mut slugs = Vec::new();
+    for part in split_genres(text) {
+        let slug = slugify(part);
+        if !known.contains_key(&slug) {
+            let now = DateTime::now();
+            let genre = Genre {
+                id: None,
+                slug: slug.clone(),
+                names: BTreeMap::from([(DEFAULT_LOCALE.to_string(), tidy_name(part))]),
+                parent: None,
+                created_at: Some(now),
+                updated_at: Some(now),
+            };
+            genre_collection.insert_one(&genre).await?;
+            created.push(slug.clone());
+            known.insert(slug.clone(), genre);
+        }
+        if !slugs.contains(&slug) {
+            slugs.push(slug);
+        }
+    }
+    Ok(slugs)
+}
+
+/// Turns legacy genre text into taxonomy references. Each movie gains the
+/// genres named in its `genre` text and that text is rewritten to the
+/// genres' English names; each list's `genre` becomes a slug. Genres missing
+/// from the taxonomy are created. Running it again changes nothing.
+pub async fn migrate_genres(
+    genre_collection: &Collection<Genre>,
+    movies: &Collection<Document>,
+    lists: &Collection<Document>,
+    revisions: &Collection<MovieRevision>,
+    actor: &str,
+) -> mongodb::error::Result<MigrationReport> {
+    let mut report = MigrationReport::default();
+    let mut known = known_genres(genre_collection).await?;
+
+    let legacy: Vec<Document> = movies
+        .find(doc! { "genre": { "$type": "string" } })
+        .projection(doc! { "genre": 1, "genres": 1 })
+        .await?
+        .try_collect()
+        .await?;
+    for movie in legacy {
+        let Ok(oid) = movie.get_object_id("_id") else {
+            continue;
+        };
+        let mut genres: Vec<String> = movie
+            .get_array("genres")
+            .map(|slugs| {
+                slugs
+                    .iter()
+                    .filter_map(|slug| slug.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let text = movie.get_str("genre").unwrap_or_default();
+        for slug in resolve_genres(
+            genre_collection,
+            &mut known,
+            &mut report.genres_created,
+            text,
+        )
+        .await?
+        {
+            if !genres.contains(&slug) {
+                genres.push(slug);
+            }
+        }
+
+        let names: Vec<&str> = genres
+            .iter()
+            .map(|slug| {
+                known
+                    .get(slug)
+                    .map_or(slug.as_str(), |g| g.display_name(&[]))
+            })
+            .collect();
+        let normalized = names.join(", ");
+        let unchanged_genres = movie.get_array("genres").is_ok_and(|current| {
+            current
+                .iter()
+                .map(|slug| slug.as_str())
+                .eq(genres.iter().map(|slug| Some(slug.as_str())))
+        });
+        if unchanged_genres && normalized == text {
+            continue;
+        }
+
+        let update = doc! { "$set": { "genres": &genres, "genre": normalized } };
+        match write_movie(movies, revisions, doc! { "_id": oid }, update, actor).await {
+            Ok(Some(_)) => report.movies_updated += 1,
+            Ok(None) => {}
+            Err(RevisionError::Conflict) => report.movies_skipped.push(oid.to_hex()),
+            Err(RevisionError::Db(e)) => return Err(e),
+        }
+    }
+
+    let legacy_lists: Vec<Document> = lists
+        .find(doc! { "genre": { "$type": "string" } })
+        .projection(doc! { "genre": 1 })
+        .await?
+        .try_collect()
+        .await?;
+    for list in legacy_lists {
+        let (Ok(oid), Ok(text)) = (list.get_object_id("_id"), list.get_str("genre")) else {
+            continue;
+        };
+        // A list row has a single genre; the first one named wins.
+        let slugs = resolve_genres(
+            genre_collection,
+            &mut known,
+            &mut report.genres_created,
+            text,
+        )
+        .await?;
+        let Some(slug) = slugs.first() else {
+            continue;
+        };
+        if slug == text {
+            continue;
+        }
+        lists
+            .update_one(
+                doc! { "_id": oid },
+                doc! {
+                    "$set": { "genre": slug, "updated_at": DateTime::now() },
+                    "$inc": { "version": 1_i64 },
+                },
+            )
+            .await?;
+        report.lists_updated += 1;
+    }
+
+    Ok(report)
+}
+
+/// Runs the migration recorded as `job_id`, then stores its report or why
+/// it failed.
+pub async fn run_migration(
+    genre_collection: Collection<Genre>,
+    movies: Collection<Document>,
+    lists: Collection<Document>,
+    revisions: Collection<MovieRevision>,
+    migrations: Collection<GenreMigration>,
+    job_id: ObjectId,
+    actor: String,
+) {
+    let outcome = migrate_genres(&genre_collection, &movies, &lists, &revisions, &actor).await;
+    let now = DateTime::now();
+    let update = match outcome.map(|report| to_bson(&report)) {
+        Ok(Ok(report)) => doc! { "$set": {
+            "status": MigrationStatus::Completed.name(),
+            "report": report,
+            "updated_at": now,
+            "finished_at": now,
+        } },
+        Ok(Err(e)) => failed(e.to_string(), now),
+        Err(e) => failed(e.to_string(), now),
+    };
+    if let Err(e) = migrations.update_one(doc! { "_id": job_id }, update).await {
+        log::error!(
+            "Failed to record the end of genre migration {}: {}",
+            job_id,
+            e
+        );
+    }
+}
+
+fn failed(failure: String, now: DateTime) -> Document {
+    doc! { "$set": {
+        "status": MigrationStatus::Failed.name(),
+        "failure": failure,
+        "updated_at": now,
+        "finished_at": now,
+    } }
+}
+
+/// Marks migrations left running by a previous process as failed, so a new
+/// one can start.
+pub async fn fail_interrupted_migrations(
+    migrations: &Collection<GenreMigration>,
+) -> mongodb::error::Result<()> {
+    migrations
+        .update_many(
+            doc! { "status": MigrationStatus::Running.name() },
+            failed(
+                "Interrupted by a restart; run it again".to_string(),
+                DateTime::now(),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugifies_words_of_any_script() {
+        assert_eq!(slugify("Action & Adventure "), "action-adventure");
+        assert_eq!(slugify("Sci-Fi"), "sci-fi");
+        assert_eq!(slugify("  Ação / Aventura"), "ação-aventura");
+        assert_eq!(slugify("Film-Noir 2"), "film-noir-2");
+        assert_eq!(slugify(" & - "), "");
+    }
+
+    #[test]
+    fn splits_legacy_text_but_keeps_ampersands() {
+        assert_eq!(
+            split_genres("Action & Adventure, Drama/ Sci-Fi | ,  "),
+            vec!["Action & Adventure", "Drama", "Sci-Fi"]
+        );
+        assert!(split_genres("").is_empty());
+        assert!(split_genres(" / & ").is_empty());
+    }
+
+    #[test]
+    fn genre_slugs_keep_order_without_repeats() {
+        assert_eq!(
+            genre_slugs("Drama, comedy, DRAMA | Comedy"),
+            vec!["drama".to_string(), "comedy".to_string()]
+        );
+        assert!(genre_slugs(" , ").is_empty());
+    }
+
+    #[test]
+    fn tidies_names_capitalizing_only_lower_case_text() {
+        assert_eq!(tidy_name("  science   fiction "), "Science Fiction");
+        assert_eq!(tidy_name("film-noir"), "Film-noir");
+        assert_eq!(tidy_name("TV  Movie"), "TV Movie");
+    }
+}
@@ -0,0 +1,304 @@
+use crate::catalog::Viewer;
+use actix_web::HttpRequest;
+use maxminddb::{geoip2, Reader};
+use std::env;
+use std::net::IpAddr;
+
+// ── Region resolution ─────────────────────────────────────────────────────────
+
+/// Resolves the country a request comes from, for licensing filters.
+///
+/// Configured from the environment:
+/// - `REGION_HEADER`: a header set by a trusted proxy or CDN (e.g. `CF-IPCountry`).
+/// - `GEOIP_DB_PATH`: a MaxMind-format country database, looked up by client address.
+/// - `TRUSTED_PROXIES`: comma-separated addresses or CIDR ranges of load balancers
+///   whose `X-Forwarded-For` is believed when looking up the client address.
+///
+/// The header wins when both are present and the header is set on the request.
+pub struct RegionResolver {
+    header: Option<String>,
+    reader: Option<Reader<Vec<u8>>>,
+    trusted_proxies: Vec<Cidr>,
+}
+
+impl RegionResolver {
+    pub fn from_env() -> Self {
+        let header = env::var("REGION_HEADER").ok().filter(|h| !h.is_empty());
+
+        let reader = env::var("GEOIP_DB_PATH").ok().and_then(open_geoip_db);
+
+        let trusted_proxies = env::var("TRUSTED_PROXIES")
+            .map(|list| parse_trusted_proxies(&list))
+            .unwrap_or_default();
+
+        if header.is_none() && reader.is_none() {
+            log::warn!("No region source configured; region-restricted titles will be hidden");
+        }
+
+        RegionResolver {
+            header,
+            reader,
+            trusted_proxies,
+        }
+    }
+
+    /// The request's region as an upper-case ISO 3166-1 alpha-2 code, if known.
+    pub fn resolve(&self, req: &HttpRequest) -> Option<String> {
+        let from_header = self.header.as_ref().and_then(|name| {
+            req.headers()
+                .get(name.as_str())
+                .and_then(|value| value.to_str().ok())
+                .and_then(normalize_region)
+        });
+
+        from_header.or_else(|| {
+            let reader = self.reader.as_ref()?;
+            let forwarded_for = req
+                .headers()
+                .get("X-Forwarded-For")
+                .and_then(|value| value.to_str().ok());
+            let ip = client_ip(req.peer_addr()?.ip(), forwarded_for, &self.trusted_proxies);
+            let country: geoip2::Country = reader.lookup(ip).ok()?;
+            country.country?.iso_code.and_then(normalize_region)
+        })
+    }
+}
+
+// ── Client address ────────────────────────────────────────────────────────────
+
+/// An address range such as `10.0.0.0/8`; a bare address is a single-host range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    fn parse(raw: &str) -> Option<Cidr> {
+        let (addr, prefix) = match raw.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+            None => (raw.trim(), None),
+        };
+        let network: IpAddr = addr.parse().ok()?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Cidr { network, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let bits = |ip: IpAddr| -> Option<(u128, u32)> {
+            match (self.network, ip) {
+                (IpAddr::V4(_), IpAddr::V4(ip)) => Some((u32::from(ip) as u128, 32)),
+                (IpAddr::V6(_), IpAddr::V6(ip)) => Some((u128::from(ip), 128)),
+                (IpAddr::V6(_), IpAddr::V4(ip)) => Some((u128::from(ip.to_ipv6_mapped()), 128)),
+                (IpAddr::V4(_), IpAddr::V6(ip)) => {
+                    ip.to_ipv4_mapped().map(|ip| (u32::from(ip) as u128, 32))
+                }
+            }
+        };
+        let (Some((network, width)), Some((ip, _))) = (bits(self.network), bits(ip)) else {
+            return false;
+        };
+        let host_bits = width - self.prefix as u32;
+        host_bits == width || network >> host_bits == ip >> host_bits
+    }
+}
+
+/// Parses `TRUSTED_PROXIES`; malformed entries are logged and skipped.
+fn parse_trusted_proxies(list: &str) -> Vec<Cidr> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let cidr = Cidr::parse(entry);
+            if cidr.is_none() {
+                log::warn!("Ignoring invalid TRUSTED_PROXIES entry {:?}", entry);
+            }
+            cidr
+        })
+        .collect()
+}
+
+/// The address of the client behind any trusted proxies.
+///
+/// A request straight from an untrusted peer is taken at its peer address;
+/// from a trusted one, `X-Forwarded-For` is walked right to left and the
+/// first address not belonging to a trusted proxy is the client. Entries
+/// further left were written by the client itself and are never believed.
+fn client_ip(peer: IpAddr, forwarded_for: Option<&str>, trusted: &[Cidr]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|cidr| cidr.contains(ip));
+    if !is_trusted(peer) {
+        return peer;
+    }
+    let mut client = peer;
+    for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) => {
+                client = ip;
+                if !is_trusted(ip) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    client
+}
+
+fn open_geoip_db(path: String) -> Option<Reader<Vec<u8>>> {
+    match Reader::open_readfile(&path) {
+        Ok(reader) => Some(reader),
+        Err(e) => {
+            log::warn!("Failed to open GeoIP database {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Accepts two-letter country codes only; anything else (`XX`, `T1`, empty) is unknown.
+pub fn normalize_region(raw: &str) -> Option<String> {
+    let code = raw.trim().to_ascii_uppercase();
+    let valid = code.len() == 2 && code.chars().all(|c| c.is_ascii_uppercase()) && code != "XX";
+    valid.then_some(code)
+}
+
+/// Normalizes a list of region codes, naming the first invalid one.
+pub fn normalize_regions(codes: Vec<String>) -> Result<Vec<String>, String> {
+    codes
+        .into_iter()
+        .map(|code| normalize_region(&code).ok_or(format!("Invalid region code: {:?}", code)))
+        .collect()
+}
+
+/// Builds a regular-user viewer: availability and region filters always apply.
+/// `preview_region`, when given, replaces the resolved region (admin previews).
+pub fn user_viewer(
+    req: &HttpRequest,
+    resolver: &RegionResolver,
+    preview_region: Option<&str>,
+) -> Viewer {
+    Viewer {
+        region: preview_region
+            .and_then(normalize_region)
+            .or_else(|| resolver.resolve(req)),
+        unrestricted: false,
+    }
+}
+
+/// Builds the viewer for a read that admins may see unfiltered.
+///
+/// Regular users get their resolved region. Admins see the whole catalog,
+/// unless they pass `?region=` to preview it as a user from that region.
+pub fn viewer(
+    req: &HttpRequest,
+    resolver: &RegionResolver,
+    is_admin: bool,
+    preview_region: Option<&str>,
+) -> Viewer {
+    match (is_admin, preview_region.and_then(normalize_region)) {
+        (true, Some(region)) => Viewer {
+            region: Some(region),
+            unrestricted: false,
+        },
+        (true, None) => Viewer {
+            region: None,
+            unrestricted: true,
+        },
+        (false, _) => user_viewer(req, resolver, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn normalize_region_accepts_two_letter_codes() {
+        assert_eq!(normalize_region("de").as_deref(), Some("DE"));
+        assert_eq!(normalize_region(" Us ").as_deref(), Some("US"));
+        for bad in ["", "X", "USA", "XX", "xx", "T1", "1A", "É1"] {
+            assert_eq!(normalize_region(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn normalize_regions_rejects_any_invalid_code() {
+        let codes = |list: &[&str]| list.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            normalize_regions(codes(&["us", "GB"])),
+            Ok(codes(&["US", "GB"]))
+        );
+        assert_eq!(
+            normalize_regions(codes(&["us", "usa"])),
+            Err("Invalid region code: \"usa\"".to_string())
+        );
+        assert_eq!(normalize_regions(Vec::new()), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn cidr_matching() {
+        let range = Cidr::parse("10.0.0.0/8").unwrap();
+        assert!(range.contains(ip("10.1.2.3")));
+        assert!(range.contains(ip("::ffff:10.1.2.3")));
+        assert!(!range.contains(ip("11.0.0.1")));
+
+        let host = Cidr::parse("192.168.1.5").unwrap();
+        assert!(host.contains(ip("192.168.1.5")));
+        assert!(!host.contains(ip("192.168.1.6")));
+
+        let v6 = Cidr::parse("fd00::/8").unwrap();
+        assert!(v6.contains(ip("fd12::1")));
+        assert!(!v6.contains(ip("fe80::1")));
+
+        assert!(Cidr::parse("0.0.0.0/0").unwrap().contains(ip("8.8.8.8")));
+        assert_eq!(Cidr::parse("10.0.0.0/33"), None);
+        assert_eq!(Cidr::parse("not-an-ip"), None);
+    }
+
+    #[test]
+    fn parse_trusted_proxies_skips_bad_entries() {
+        let list = parse_trusted_proxies(" 10.0.0.0/8, ,bogus,::1 ");
+        assert_eq!(list.len(), 2);
+        assert!(list[1].contains(ip("::1")));
+    }
+
+    #[test]
+    fn client_ip_ignores_forwarded_for_from_untrusted_peers() {
+        let trusted = parse_trusted_proxies("10.0.0.0/8");
+        assert_eq!(
+            client_ip(ip("203.0.113.9"), Some("198.51.100.1"), &trusted),
+            ip("203.0.113.9")
+        );
+        assert_eq!(
+            client_ip(ip("10.0.0.2"), Some("198.51.100.1"), &[]),
+            ip("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn client_ip_takes_the_right_most_untrusted_hop() {
+        let trusted = parse_trusted_proxies("10.0.0.0/8");
+        // The client spoofed the first entry; the LB appended the real one.
+        assert_eq!(
+            client_ip(
+                ip("10.0.0.2"),
+                Some("1.2.3.4, 198.51.100.1, 10.0.0.7"),
+                &trusted
+            ),
+            ip("198.51.100.1")
+        );
+        assert_eq!(
+            client_ip(ip("10.0.0.2"), Some("10.0.0.9, 10.0.0.7"), &trusted),
+            ip("10.0.0.9")
+        );
+        assert_eq!(client_ip(ip("10.0.0.2"), None, &trusted), ip("10.0.0.2"));
+        assert_eq!(
+            client_ip(ip("10.0.0.2"), Some("garbage, 10.0.0.7"), &trusted),
+            ip("10.0.0.7")
+        );
+    }
+}
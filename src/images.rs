@@ -0,0 +1,305 @@
+use crate::storage::ImageType;
+use actix_web::web::{self, Bytes};
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageError, ImageReader, Limits};
+use mongodb::bson::oid::ObjectId;
+use std::env;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+#[derive(Error, Debug)]
+pub enum ImageProcessingError {
+    #[error("Image could not be processed: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("Placeholder could not be computed: {0}")]
+    Blurhash(#[from] blurhash::Error),
+
+    #[error("Image could not be read: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+// ── Decoding limits ───────────────────────────────────────────────────────────
+
+/// Largest width or height decoded, from `IMAGE_MAX_DIMENSION` (default 8192).
+/// A few kilobytes of compressed data can claim a huge canvas; this keeps such
+/// files from being decoded at all.
+pub fn max_dimension() -> u32 {
+    env::var("IMAGE_MAX_DIMENSION")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8192)
+}
+
+/// Most memory a decoder may allocate, from `IMAGE_MAX_ALLOC_BYTES` (default 256 MiB).
+pub fn max_alloc_bytes() -> u64 {
+    env::var("IMAGE_MAX_ALLOC_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(256 * 1024 * 1024)
+}
+
+fn decode_limits() -> Limits {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_dimension());
+    limits.max_image_height = Some(max_dimension());
+    limits.max_alloc = Some(max_alloc_bytes());
+    limits
+}
+
+fn reader(original: &[u8]) -> Result<ImageReader<Cursor<&[u8]>>, ImageProcessingError> {
+    let mut reader = ImageReader::new(Cursor::new(original)).with_guessed_format()?;
+    reader.limits(decode_limits());
+    Ok(reader)
+}
+
+/// Decodes an image within the configured limits.
+fn decode(original: &[u8]) -> Result<DynamicImage, ImageProcessingError> {
+    Ok(reader(original)?.decode()?)
+}
+
+/// Whether the image header claims a canvas larger than the decoding limits.
+/// Unreadable input is not judged; uploads are sniffed before this.
+pub fn exceeds_limits(original: &[u8]) -> bool {
+    let max = max_dimension();
+    match reader(original).map(|reader| reader.into_dimensions()) {
+        Ok(Ok((width, height))) => width > max || height > max,
+        Ok(Err(ImageError::Limits(_))) => true,
+        _ => false,
+    }
+}
+
+// ── Variant configuration ─────────────────────────────────────────────────────
+
+/// Widths variants are rendered at, from `IMAGE_WIDTHS` (default `160,320,640,1280`).
+/// Requested widths snap to these so the cache holds a bounded set of files.
+pub fn variant_widths() -> Vec<u32> {
+    let mut widths: Vec<u32> = env::var("IMAGE_WIDTHS")
+        .ok()
+        .map(|v| {
+            v.split(',')
+                .filter_map(|w| w.trim().parse().ok())
+                .filter(|w| *w > 0)
+                .collect()
+        })
+        .filter(|widths: &Vec<u32>| !widths.is_empty())
+        .unwrap_or_else(|| vec![160, 320, 640, 1280]);
+    widths.sort_unstable();
+    widths
+}
+
+/// The smallest configured width at least as wide as requested, or the largest.
+pub fn snap_width(requested: u32, widths: &[u32]) -> u32 {
+    widths
+        .iter()
+        .copied()
+        .find(|w| *w >= requested)
+        .or_else(|| widths.last().copied())
+        .unwrap_or(requested)
+}
+
+/// Where rendered variants are cached, from `IMAGE_CACHE_DIR` (default `cache/images`).
+pub fn image_cache_dir() -> PathBuf {
+    PathBuf::from(env::var("IMAGE_CACHE_DIR").unwrap_or_else(|_| "cache/images".to_string()))
+}
+
+/// `<cache dir>/<asset key>/<width|full>.<ext>`
+pub fn variant_path(key: &str, width: Option<u32>, format: ImageType) -> PathBuf {
+    let size = width.map_or_else(|| "full".to_string(), |w| w.to_string());
+    image_cache_dir()
+        .join(key)
+        .join(format!("{}.{}", size, format.extension()))
+}
+
+/// Format names accepted by `?fmt=`.
+pub fn format_from_name(name: &str) -> Option<ImageType> {
+    match name {
+        "jpeg" | "jpg" => Some(ImageType::Jpeg),
+        "png" => Some(ImageType::Png),
+        "webp" => Some(ImageType::Webp),
+        "avif" => Some(ImageType::Avif),
+        _ => None,
+    }
+}
+
+// ── Rendering ─────────────────────────────────────────────────────────────────
+
+/// Re-encodes `original` as `format`, scaled down to `width` if given. Images
+/// are never scaled up. CPU-bound: run it on the blocking pool.
+pub fn render_variant(
+    original: &[u8],
+    width: Option<u32>,
+    format: ImageType,
+) -> Result<Vec<u8>, ImageProcessingError> {
+    let mut image = decode(original)?;
+    if let Some(width) = width.filter(|w| *w < image.width()) {
+        image = image.resize(width, image.height(), FilterType::Lanczos3);
+    }
+
+    let mut out = Vec::new();
+    match format {
+        ImageType::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut out, 82))?,
+        ImageType::Png => image.write_with_encoder(PngEncoder::new(&mut out))?,
+        ImageType::Webp => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut out))?,
+        ImageType::Avif => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(AvifEncoder::new_with_speed_quality(&mut out, 8, 70))?,
+    }
+    Ok(out)
+}
+
+/// A BlurHash placeholder clients paint while the real image loads.
+pub fn blurhash(original: &[u8]) -> Result<String, ImageProcessingError> {
+    let thumbnail = decode(original)?.thumbnail(32, 32).to_rgba8();
+    Ok(blurhash::encode(
+        4,
+        3,
+        thumbnail.width(),
+        thumbnail.height(),
+        thumbnail.as_raw(),
+    )?)
+}
+
+// ── Variant cache ─────────────────────────────────────────────────────────────
+
+/// Bounds how many variants render at once (`IMAGE_RENDER_CONCURRENCY`,
+/// default 2). Cache misses wait up to `IMAGE_RENDER_WAIT_SECS` (default 10)
+/// for a slot, so a flood of requests for uncached sizes is turned away
+/// instead of queueing unbounded CPU work.
+pub struct RenderSlots {
+    permits: Semaphore,
+    wait: Duration,
+}
+
+impl RenderSlots {
+    pub fn from_env() -> Self {
+        let concurrency = env::var("IMAGE_RENDER_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(2);
+        let wait_secs = env::var("IMAGE_RENDER_WAIT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        RenderSlots {
+            permits: Semaphore::new(concurrency),
+            wait: Duration::from_secs(wait_secs),
+        }
+    }
+
+    /// A render slot, or `None` if none freed up in time.
+    pub async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        actix_rt::time::timeout(self.wait, self.permits.acquire())
+            .await
+            .ok()?
+            .ok()
+    }
+}
+
+/// Writes a rendered variant under a temporary name and renames it into
+/// place, so readers never see a partial file. Concurrent writers of the same
+/// variant each use their own temporary file and the last rename wins whole.
+pub async fn cache_variant(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let partial = path.with_extension(format!("{}.partial", ObjectId::new().to_hex()));
+    tokio::fs::write(&partial, bytes).await?;
+    tokio::fs::rename(&partial, path).await
+}
+
+/// Renders and caches every configured width of a fresh upload in its own
+/// format, so the sizes clients ask for are ready before the first request.
+/// Runs in the background, one variant at a time within `slots`.
+pub async fn prerender_variants(
+    slots: Arc<RenderSlots>,
+    key: String,
+    original: Bytes,
+    format: ImageType,
+) {
+    for width in variant_widths() {
+        let path = variant_path(&key, Some(width), format);
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            continue;
+        }
+        let Ok(_permit) = slots.permits.acquire().await else {
+            return;
+        };
+        let original = original.clone();
+        let rendered =
+            match web::block(move || render_variant(&original, Some(width), format)).await {
+                Ok(Ok(bytes)) => bytes,
+                Ok(Err(e)) => {
+                    log::warn!("Failed to pre-render {} at {}px: {}", key, width, e);
+                    return;
+                }
+                Err(e) => {
+                    log::warn!("Failed to pre-render {} at {}px: {}", key, width, e);
+                    return;
+                }
+            };
+        if let Err(e) = cache_variant(&path, &rendered).await {
+            log::warn!("Failed to cache image variant {}: {}", path.display(), e);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_with_encoder(PngEncoder::new(&mut out))
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn widths_snap_up_to_the_configured_set() {
+        let widths = [160, 320, 640];
+        assert_eq!(snap_width(1, &widths), 160);
+        assert_eq!(snap_width(320, &widths), 320);
+        assert_eq!(snap_width(321, &widths), 640);
+        assert_eq!(snap_width(5000, &widths), 640);
+        assert_eq!(snap_width(500, &[]), 500);
+    }
+
+    #[test]
+    fn oversized_canvases_are_refused() {
+        assert!(!exceeds_limits(&png(64, 32)));
+        assert!(exceeds_limits(&png(max_dimension() + 1, 1)));
+        assert!(exceeds_limits(&png(1, max_dimension() + 1)));
+        assert!(matches!(
+            render_variant(&png(max_dimension() + 1, 1), Some(160), ImageType::Png),
+            Err(ImageProcessingError::Image(ImageError::Limits(_)))
+        ));
+        // Not an image this module can read: left to the upload's sniffing.
+        assert!(!exceeds_limits(b"not an image"));
+    }
+
+    #[test]
+    fn variants_scale_down_but_never_up() {
+        let original = png(400, 200);
+        let small = render_variant(&original, Some(100), ImageType::Jpeg).unwrap();
+        let small = image::load_from_memory(&small).unwrap();
+        assert_eq!((small.width(), small.height()), (100, 50));
+
+        let same = render_variant(&original, Some(1280), ImageType::Webp).unwrap();
+        let same = image::load_from_memory(&same).unwrap();
+        assert_eq!((same.width(), same.height()), (400, 200));
+    }
+}
@@ -0,0 +1,784 @@
+use crate::genres::{genre_slugs, known_genres, resolve_genres};
+use crate::geo::normalize_regions;
+use crate::models::genre::Genre;
+use crate::models::import::{ImportJob, ImportStatus, RowError};
+use crate::models::list::List;
+use crate::models::movie::{Movie, METADATA_FIELDS};
+use crate::models::rating::RatingSummary;
+use crate::models::revision::MovieRevision;
+use crate::models::workflow::ContentStatus;
+use crate::revisions::{diff, write_movie, RevisionError};
+use crate::search::movie_search_keys;
+use crate::smart_lists::invalidate_smart_lists;
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, to_document, Bson, DateTime, Document};
+use mongodb::error::ErrorKind;
+use mongodb::Collection;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use thiserror::Error;
+
+// ── Input formats ─────────────────────────────────────────────────────────────
+
+/// Rows written per bulk insert and per progress update.
+const CHUNK_SIZE: usize = 500;
+
+/// Row errors kept on a job; the rest are only counted.
+pub const MAX_REPORTED_ERRORS: usize = 1000;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ImportFormat {
+    Csv,
+    Ndjson,
+}
+
+impl ImportFormat {
+    /// From `?format=`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(ImportFormat::Csv),
+            "ndjson" | "jsonl" => Some(ImportFormat::Ndjson),
+            _ => None,
+        }
+    }
+
+    /// From the request's `Content-Type`, when no format is given.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type.split(';').next().unwrap_or("").trim() {
+            "text/csv" => Some(ImportFormat::Csv),
+            "application/x-ndjson" | "application/jsonl" | "application/json" => {
+                Some(ImportFormat::Ndjson)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "csv",
+            ImportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("The file is not valid UTF-8")]
+    Encoding,
+
+    #[error("The file has no rows")]
+    Empty,
+
+    #[error("Unknown CSV column: {0}")]
+    UnknownColumn(String),
+
+    #[error("Invalid CSV: {0}")]
+    Csv(#[from] csv::Error),
+}
+
+// ── Rows ──────────────────────────────────────────────────────────────────────
+
+/// Columns (CSV) or keys (NDJSON) an import row may have; same names as on `Movie`.
+const COLUMNS: &[&str] = &[
+    "external_id",
+    "title",
+    "desc",
+    "img",
+    "img_title",
+    "img_sm",
+    "trailer",
+    "video",
+    "year",
+    "limit",
+    "genre",
+    "is_series",
+    "available_from",
+    "available_until",
+    "allowed_regions",
+    "blocked_regions",
+];
+
+/// One input row before validation. Dates are RFC 3339 strings.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImportRow {
+    external_id: Option<String>,
+    title: Option<String>,
+    desc: Option<String>,
+    img: Option<String>,
+    img_title: Option<String>,
+    img_sm: Option<String>,
+    trailer: Option<String>,
+    video: Option<String>,
+    year: Option<String>,
+    limit: Option<String>,
+    genre: Option<String>,
+    is_series: Option<bool>,
+    available_from: Option<String>,
+    available_until: Option<String>,
+    #[serde(default)]
+    allowed_regions: Vec<String>,
+    #[serde(default)]
+    blocked_regions: Vec<String>,
+}
+
+/// A validated row, ready to write.
+pub struct ValidRow {
+    pub row: u64,
+
+    pub external_id: String,
+
+    /// The title as it is inserted when the external id is new.
+    pub movie: Movie,
+
+    /// What the row sets on an existing title: only the fields it provides.
+    pub set: Document,
+}
+
+pub type ParsedRow = Result<ValidRow, RowError>;
+
+fn row_error(row: u64, external_id: Option<String>, message: impl Into<String>) -> RowError {
+    RowError {
+        row,
+        external_id,
+        message: message.into(),
+    }
+}
+
+/// A row number and the row as read, or why it could not be read.
+type RawRow = (u64, Result<ImportRow, String>);
+
+/// Turns one CSV cell into the JSON value `ImportRow` expects for its column.
+/// Region lists are separated by `;` or `|`.
+fn csv_value(column: &str, cell: &str) -> Result<Value, String> {
+    match column {
+        "is_series" => match cell.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "1" => Ok(Value::Bool(true)),
+            "false" | "no" | "0" => Ok(Value::Bool(false)),
+            _ => Err(format!("is_series must be true or false, got {:?}", cell)),
+        },
+        "allowed_regions" | "blocked_regions" => Ok(Value::Array(
+            cell.split([';', '|'])
+                .map(str::trim)
+                .filter(|code| !code.is_empty())
+                .map(|code| Value::String(code.to_string()))
+                .collect(),
+        )),
+        _ => Ok(Value::String(cell.to_string())),
+    }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<RawRow>, ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+
+    let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    if let Some(unknown) = headers.iter().find(|h| !COLUMNS.contains(&h.as_str())) {
+        return Err(ImportError::UnknownColumn(unknown.clone()));
+    }
+
+    let rows = reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let row = i as u64 + 1;
+            let parsed = record.map_err(|e| e.to_string()).and_then(|record| {
+                let mut fields = Map::new();
+                for (column, cell) in headers.iter().zip(record.iter()) {
+                    if !cell.is_empty() {
+                        fields.insert(column.clone(), csv_value(column, cell)?);
+                    }
+                }
+                serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())
+            });
+            (row, parsed)
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+/// Rows are numbered by line, so blank lines still count.
+fn parse_ndjson(text: &str) -> Vec<RawRow> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let parsed = serde_json::from_str(line).map_err(|e| e.to_string());
+            (i as u64 + 1, parsed)
+        })
+        .collect()
+}
+
+fn parse_date(field: &str, value: Option<String>) -> Result<Option<DateTime>, String> {
+    value
+        .map(|raw| {
+            DateTime::parse_rfc3339_str(&raw)
+                .map_err(|_| format!("{} must be an RFC 3339 date, got {:?}", field, raw))
+        })
+        .transpose()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn validate(row: u64, input: ImportRow) -> ParsedRow {
+    let Some(external_id) = non_empty(input.external_id) else {
+        return Err(row_error(row, None, "external_id is required"));
+    };
+    let fail = |message: String| row_error(row, Some(external_id.clone()), message);
+
+    let Some(title) = non_empty(input.title) else {
+        return Err(fail("title is required".to_string()));
+    };
+
+    let available_from = parse_date("available_from", input.available_from).map_err(fail)?;
+    let available_until = parse_date("available_until", input.available_until).map_err(fail)?;
+    if let (Some(from), Some(until)) = (available_from, available_until) {
+        if from >= until {
+            return Err(fail(
+                "available_from must be before available_until".to_string(),
+            ));
+        }
+    }
+
+    let genre = non_empty(input.genre);
+    let genres = genre.as_deref().map(genre_slugs).unwrap_or_default();
+
+    let allowed_regions = normalize_regions(input.allowed_regions).map_err(fail)?;
+    let blocked_regions = normalize_regions(input.blocked_regions).map_err(fail)?;
+
+    let movie = Movie {
+        id: None,
+        external_id: Some(external_id.clone()),
+        title,
+        desc: non_empty(input.desc),
+        img: non_empty(input.img),
+        img_title: non_empty(input.img_title),
+        img_sm: non_empty(input.img_sm),
+        translations: BTreeMap::new(),
+        blurhash: BTreeMap::new(),
+        trailer: non_empty(input.trailer),
+        video: non_empty(input.video),
+        year: non_empty(input.year),
+        limit: non_empty(input.limit),
+        genre,
+        genres,
+        is_series: input.is_series.unwrap_or(false),
+        featured_boost: None,
+        ratings: RatingSummary::default(),
+        subtitles: Vec::new(),
+        audio_tracks: Vec::new(),
+        available_from,
+        available_until,
+        allowed_regions,
+        blocked_regions,
+        status: Some(ContentStatus::Draft),
+        revision: 1,
+        created_at: None,
+        updated_at: None,
+        deleted_at: None,
+        deleted_by: None,
+    };
+
+    let mut set: Document = to_document(&movie)
+        .map_err(|e| fail(e.to_string()))?
+        .into_iter()
+        .filter(|(field, _)| METADATA_FIELDS.contains(&field.as_str()))
+        .collect();
+    if input.is_series.is_none() {
+        set.remove("is_series");
+    }
+
+    Ok(ValidRow {
+        row,
+        external_id,
+        movie,
+        set,
+    })
+}
+
+/// Parses and validates every row of an import file. File-level problems
+/// (encoding, unknown columns) fail the whole import; anything else is
+/// reported per row.
+pub fn parse_rows(format: ImportFormat, body: &[u8]) -> Result<Vec<ParsedRow>, ImportError> {
+    let text = std::str::from_utf8(body).map_err(|_| ImportError::Encoding)?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+    let raw = match format {
+        ImportFormat::Csv => parse_csv(text)?,
+        ImportFormat::Ndjson => parse_ndjson(text),
+    };
+    if raw.is_empty() {
+        return Err(ImportError::Empty);
+    }
+
+    // An external id may appear only once, or the later row would silently win.
+    let mut first_seen: HashMap<String, u64> = HashMap::new();
+    let rows = raw
+        .into_iter()
+        .map(|(row, parsed)| {
+            let valid = validate(row, parsed.map_err(|e| row_error(row, None, e))?)?;
+            match first_seen.get(&valid.external_id) {
+                Some(first) => Err(row_error(
+                    row,
+                    Some(valid.external_id),
+                    format!("Duplicate external_id, first used on row {}", first),
+                )),
+                None => {
+                    first_seen.insert(valid.external_id.clone(), row);
+                    Ok(valid)
+                }
+            }
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+// ── Writing ───────────────────────────────────────────────────────────────────
+
+/// External ids among `ids` that already exist (including titles in the trash).
+pub async fn existing_ids(
+    movie_collection: &Collection<Movie>,
+    ids: &[&str],
+) -> mongodb::error::Result<HashSet<String>> {
+    let found: Vec<Document> = movie_collection
+        .clone_with_type::<Document>()
+        .find(doc! { "external_id": { "$in": ids } })
+        .projection(doc! { "external_id": 1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(found
+        .iter()
+        .filter_map(|d| d.get_str("external_id").ok())
+        .map(str::to_string)
+        .collect())
+}
+
+#[derive(Default)]
+struct ChunkResult {
+    inserted: u64,
+    updated: u64,
+    errors: Vec<RowError>,
+    genres_created: Vec<String>,
+}
+
+/// Creates the genres the chunk's rows name that are not in the taxonomy yet.
+async fn create_genres(
+    genre_collection: &Collection<Genre>,
+    known: &mut HashMap<String, Genre>,
+    rows: &[&ValidRow],
+    created: &mut Vec<String>,
+) -> mongodb::error::Result<()> {
+    for row in rows {
+        if row.movie.genres.iter().all(|slug| known.contains_key(slug)) {
+            continue;
+        }
+        if let Some(text) = &row.movie.genre {
+            resolve_genres(genre_collection, known, created, text).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Inserts the chunk's new titles with one unordered `insert_many`, so a
+/// rejected row does not stop the rest, and updates the existing ones in
+/// place, recording a revision for each.
+async fn write_chunk(
+    c: &ImportCollections,
+    actor: &str,
+    known: &mut HashMap<String, Genre>,
+    rows: &[&ValidRow],
+) -> mongodb::error::Result<ChunkResult> {
+    let ImportCollections {
+        movies: movie_collection,
+        revisions: revision_collection,
+        genres: genre_collection,
+        ..
+    } = c;
+
+    let mut result = ChunkResult::default();
+    create_genres(genre_collection, known, rows, &mut result.genres_created).await?;
+
+    let ids: Vec<&str> = rows.iter().map(|r| r.external_id.as_str()).collect();
+    let existing = existing_ids(movie_collection, &ids).await?;
+    let (updates, inserts): (Vec<&ValidRow>, Vec<&ValidRow>) =
+        rows.iter().partition(|r| existing.contains(&r.external_id));
+
+    let now = DateTime::now();
+
+    if !inserts.is_empty() {
+        // Ids are assigned here: the driver does not say which rows made it
+        // when some of an unordered insert fail.
+        let movies: Vec<Movie> = inserts
+            .iter()
+            .map(|r| Movie {
+                id: Some(ObjectId::new()),
+                created_at: Some(now),
+                ..r.movie.clone()
+            })
+            .collect();
+        let documents = movies
+            .iter()
+            .map(|movie| {
+                let mut document = to_document(movie)?;
+                document.insert("search_keys", movie_search_keys(&document));
+                Ok(document)
+            })
+            .collect::<mongodb::error::Result<Vec<Document>>>()?;
+        let failed = match movie_collection
+            .clone_with_type::<Document>()
+            .insert_many(documents)
+            .ordered(false)
+            .await
+        {
+            Ok(_) => HashMap::new(),
+            Err(e) => rejected_inserts(e)?,
+        };
+
+        let mut revisions = Vec::new();
+        let mut created_rows = Vec::new();
+        for (index, (row, movie)) in inserts.iter().zip(&movies).enumerate() {
+            if let Some(message) = failed.get(&index) {
+                result.errors.push(row_error(
+                    row.row,
+                    Some(row.external_id.clone()),
+                    message.clone(),
+                ));
+                continue;
+            }
+            let (Some(oid), Ok(created)) = (movie.id, to_document(movie)) else {
+                continue;
+            };
+            revisions.push(MovieRevision {
+                id: None,
+                movie_id: oid.to_hex(),
+                rev: 1,
+                changes: diff(&Document::new(), &created),
+                actor: actor.to_string(),
+                at: now,
+            });
+            created_rows.push((row, oid));
+        }
+
+        // Without transactions, a title whose first revision cannot be recorded
+        // is taken back out so the history never misses its creation.
+        let unrecorded = if revisions.is_empty() {
+            HashMap::new()
+        } else {
+            match revision_collection
+                .insert_many(revisions)
+                .ordered(false)
+                .await
+            {
+                Ok(_) => HashMap::new(),
+                Err(e) => rejected_inserts(e).unwrap_or_else(|e| {
+                    (0..created_rows.len())
+                        .map(|index| (index, e.to_string()))
+                        .collect()
+                }),
+            }
+        };
+        if !unrecorded.is_empty() {
+            let oids: Vec<ObjectId> = unrecorded
+                .keys()
+                .map(|&index| created_rows[index].1)
+                .collect();
+            movie_collection
+                .delete_many(doc! { "_id": { "$in": oids } })
+                .await?;
+        }
+        for (index, (row, _)) in created_rows.iter().enumerate() {
+            match unrecorded.get(&index) {
+                Some(message) => result.errors.push(row_error(
+                    row.row,
+                    Some(row.external_id.clone()),
+                    format!("Failed to record the title's revision: {}", message),
+                )),
+                None => result.inserted += 1,
+            }
+        }
+    }
+
+    let movies = movie_collection.clone_with_type::<Document>();
+    for row in updates {
+        let filter = doc! { "external_id": &row.external_id };
+        let update = doc! { "$set": row.set.clone() };
+        match write_movie(&movies, revision_collection, filter, update, actor).await {
+            Ok(Some(_)) => result.updated += 1,
+            Ok(None) => result.errors.push(row_error(
+                row.row,
+                Some(row.external_id.clone()),
+                "The title was deleted during the import",
+            )),
+            Err(e @ RevisionError::Conflict) => result.errors.push(row_error(
+                row.row,
+                Some(row.external_id.clone()),
+                e.to_string(),
+            )),
+            Err(RevisionError::Db(e)) => return Err(e),
+        }
+    }
+
+    Ok(result)
+}
+
+/// The documents an unordered `insert_many` rejected, by index in the batch.
+/// Any other failure, where the outcome of each document is unknown, is
+/// passed back.
+fn rejected_inserts(e: mongodb::error::Error) -> mongodb::error::Result<HashMap<usize, String>> {
+    match *e.kind {
+        ErrorKind::InsertMany(ref insert)
+            if insert.write_concern_error.is_none() && insert.write_errors.is_some() =>
+        {
+            Ok(insert
+                .write_errors
+                .iter()
+                .flatten()
+                .map(|error| (error.index, error.message.clone()))
+                .collect())
+        }
+        _ => Err(e),
+    }
+}
+
+/// Everything an import reads and writes. Built once at startup and shared
+/// with the import handler, which hands a copy to each background job.
+#[derive(Clone)]
+pub struct ImportCollections {
+    pub movies: Collection<Movie>,
+    pub lists: Collection<List>,
+    pub revisions: Collection<MovieRevision>,
+    pub genres: Collection<Genre>,
+    pub jobs: Collection<ImportJob>,
+}
+
+/// Marks the job failed with the database error that stopped it.
+async fn fail_job(
+    job_collection: &Collection<ImportJob>,
+    job_id: ObjectId,
+    e: mongodb::error::Error,
+) {
+    log::warn!("Import job {} failed: {}", job_id, e);
+    let failed = doc! {
+        "$set": {
+            "status": "failed",
+            "failure": e.to_string(),
+            "updated_at": DateTime::now(),
+        }
+    };
+    if let Err(e) = job_collection
+        .update_one(doc! { "_id": job_id }, failed)
+        .await
+    {
+        log::warn!("Failed to update import job {}: {}", job_id, e);
+    }
+}
+
+/// Writes `rows[start..]` chunk by chunk, recording progress on the job after
+/// each one. Stops and marks the job failed on the first database error.
+/// Genres named in the rows that are not in the taxonomy are created.
+///
+/// Rows are upserted by external id, so re-running a chunk whose progress
+/// update was lost is harmless.
+///
+/// `actor` is the email of the admin running the import, recorded on revisions.
+pub async fn run_import(
+    c: ImportCollections,
+    actor: String,
+    job_id: ObjectId,
+    rows: Vec<ParsedRow>,
+    start: usize,
+) {
+    let job_collection = &c.jobs;
+    let mut known = match known_genres(&c.genres).await {
+        Ok(known) => known,
+        Err(e) => return fail_job(job_collection, job_id, e).await,
+    };
+    let mut offset = start;
+
+    while offset < rows.len() {
+        let end = (offset + CHUNK_SIZE).min(rows.len());
+        let chunk: Vec<&ValidRow> = rows[offset..end]
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .collect();
+
+        let progress = match write_chunk(&c, &actor, &mut known, &chunk).await {
+            Ok(written) => {
+                let errors = mongodb::bson::to_bson(&written.errors).unwrap_or(Bson::Array(vec![]));
+                doc! {
+                    "$set": { "processed_rows": end as i64, "updated_at": DateTime::now() },
+                    "$inc": {
+                        "inserted": written.inserted as i64,
+                        "updated": written.updated as i64,
+                        "error_count": written.errors.len() as i64,
+                    },
+                    "$push": { "errors": { "$each": errors, "$slice": MAX_REPORTED_ERRORS as i64 } },
+                    "$addToSet": { "genres_created": { "$each": written.genres_created } },
+                }
+            }
+            Err(e) => return fail_job(job_collection, job_id, e).await,
+        };
+
+        if let Err(e) = job_collection
+            .update_one(doc! { "_id": job_id }, progress)
+            .await
+        {
+            log::warn!("Failed to update import job {}: {}", job_id, e);
+        }
+        offset = end;
+    }
+
+    let now = DateTime::now();
+    let completed = doc! {
+        "$set": { "status": "completed", "updated_at": now, "finished_at": now }
+    };
+    if let Err(e) = job_collection
+        .update_one(doc! { "_id": job_id }, completed)
+        .await
+    {
+        log::warn!("Failed to update import job {}: {}", job_id, e);
+    }
+
+    if let Err(e) = invalidate_smart_lists(&c.lists).await {
+        log::warn!("Failed to invalidate smart lists: {}", e);
+    }
+}
+
+/// Whether a job marked running has stopped reporting progress, e.g. because
+/// the server restarted mid-import, and may be resumed.
+pub fn is_stalled(job: &ImportJob) -> bool {
+    const STALL_MILLIS: i64 = 5 * 60 * 1000;
+    job.status == ImportStatus::Running
+        && DateTime::now().timestamp_millis() - job.updated_at.timestamp_millis() > STALL_MILLIS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid(rows: &[ParsedRow]) -> Vec<&ValidRow> {
+        rows.iter().filter_map(|r| r.as_ref().ok()).collect()
+    }
+
+    fn errors(rows: &[ParsedRow]) -> Vec<(u64, String)> {
+        rows.iter()
+            .filter_map(|r| r.as_ref().err())
+            .map(|e| (e.row, e.message.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn csv_rows_become_draft_movies() {
+        let body = "\u{feff}external_id,title,genre,is_series,allowed_regions,year\n\
+                    tt1, The Film ,Action & Adventure / drama,yes,us;gb,1999\n";
+        let rows = parse_rows(ImportFormat::Csv, body.as_bytes()).unwrap();
+        let rows = valid(&rows);
+        assert_eq!(rows.len(), 1);
+
+        let movie = &rows[0].movie;
+        assert_eq!(rows[0].row, 1);
+        assert_eq!(movie.external_id.as_deref(), Some("tt1"));
+        assert_eq!(movie.title, "The Film");
+        assert_eq!(movie.genres, vec!["action-adventure", "drama"]);
+        assert!(movie.is_series);
+        assert_eq!(movie.allowed_regions, vec!["US", "GB"]);
+        assert_eq!(movie.status, Some(ContentStatus::Draft));
+    }
+
+    #[test]
+    fn update_sets_only_the_fields_the_row_gives() {
+        let body = "{\"external_id\":\"tt1\",\"title\":\"T\",\"desc\":\"D\"}\n";
+        let rows = parse_rows(ImportFormat::Ndjson, body.as_bytes()).unwrap();
+        let set = &valid(&rows)[0].set;
+        assert_eq!(set.get_str("title"), Ok("T"));
+        assert_eq!(set.get_str("desc"), Ok("D"));
+        for absent in ["genre", "genres", "is_series", "year", "status", "revision"] {
+            assert!(!set.contains_key(absent), "{} should not be set", absent);
+        }
+    }
+
+    #[test]
+    fn invalid_rows_are_reported_one_by_one() {
+        let body = [
+            r#"{"external_id":"a","title":"A"}"#,
+            r#"{"title":"No id"}"#,
+            "",
+            r#"{"external_id":"b"}"#,
+            r#"{"external_id":"c","title":"C","allowed_regions":["USA"]}"#,
+            r#"{"external_id":"d","title":"D","available_from":"2024-02-01T00:00:00Z","available_until":"2024-01-01T00:00:00Z"}"#,
+            r#"{"external_id":"e","title":"E","available_from":"yesterday"}"#,
+            r#"{"external_id":"a","title":"Again"}"#,
+            r#"{"external_id":"f","title":"F","rating":5}"#,
+            "not json",
+        ]
+        .join("\n");
+        let rows = parse_rows(ImportFormat::Ndjson, body.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 9);
+        assert_eq!(valid(&rows).len(), 1);
+
+        let errors = errors(&rows);
+        let rows_in_error: Vec<u64> = errors.iter().map(|(row, _)| *row).collect();
+        assert_eq!(rows_in_error, vec![2, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(errors[0].1, "external_id is required");
+        assert_eq!(errors[1].1, "title is required");
+        assert_eq!(errors[2].1, "Invalid region code: \"USA\"");
+        assert_eq!(errors[3].1, "available_from must be before available_until");
+        assert!(errors[4]
+            .1
+            .starts_with("available_from must be an RFC 3339 date"));
+        assert_eq!(errors[5].1, "Duplicate external_id, first used on row 1");
+        assert!(errors[6].1.contains("unknown field"));
+    }
+
+    #[test]
+    fn file_level_problems_fail_the_whole_import() {
+        assert!(matches!(
+            parse_rows(ImportFormat::Csv, b"external_id,rating\nx,5\n"),
+            Err(ImportError::UnknownColumn(column)) if column == "rating"
+        ));
+        assert!(matches!(
+            parse_rows(ImportFormat::Ndjson, b"\n  \n"),
+            Err(ImportError::Empty)
+        ));
+        assert!(matches!(
+            parse_rows(ImportFormat::Ndjson, &[0xff, 0xfe]),
+            Err(ImportError::Encoding)
+        ));
+    }
+
+    #[test]
+    fn csv_is_series_must_be_boolean() {
+        let body = "external_id,title,is_series\nx,X,maybe\n";
+        let rows = parse_rows(ImportFormat::Csv, body.as_bytes()).unwrap();
+        assert_eq!(
+            errors(&rows),
+            vec![(
+                1,
+                "is_series must be true or false, got \"maybe\"".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn format_detection() {
+        assert!(matches!(
+            ImportFormat::from_content_type("text/csv; charset=utf-8"),
+            Some(ImportFormat::Csv)
+        ));
+        assert!(matches!(
+            ImportFormat::from_name("jsonl"),
+            Some(ImportFormat::Ndjson)
+        ));
+        assert!(ImportFormat::from_content_type("text/plain").is_none());
+    }
+}
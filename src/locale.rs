@@ -0,0 +1,229 @@
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::HttpRequest;
+use std::collections::{BTreeMap, HashMap};
+
+// ── Locale tags ───────────────────────────────────────────────────────────────
+
+/// The language of untranslated catalog text, and the end of every fallback chain.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Canonical form of a BCP 47 tag: language lower-case, script title-case and
+/// region upper-case (`pt-br` becomes `pt-BR`, `zh_hant_tw` becomes `zh-Hant-TW`).
+/// Variants, extensions and private-use subtags are dropped, so `de-CH-1996`
+/// becomes `de-CH`. `None` for anything that is not a language tag, including `*`.
+pub fn normalize_locale(raw: &str) -> Option<String> {
+    let mut parts = raw.trim().split(['-', '_']);
+    let language = parts.next()?;
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut tag = language.to_ascii_lowercase();
+    let (mut script, mut region) = (false, false);
+    for part in parts {
+        if !(1..=8).contains(&part.len()) || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        let alphabetic = part.chars().all(|c| c.is_ascii_alphabetic());
+        let subtag = match part.len() {
+            // A singleton starts an extension or private use, which runs to the end.
+            1 => break,
+            4 if alphabetic && !script && !region => {
+                script = true;
+                let lower = part.to_ascii_lowercase();
+                lower[..1].to_ascii_uppercase() + &lower[1..]
+            }
+            2 if alphabetic && !region => {
+                region = true;
+                part.to_ascii_uppercase()
+            }
+            3 if part.chars().all(|c| c.is_ascii_digit()) && !region => {
+                region = true;
+                part.to_string()
+            }
+            _ => continue,
+        };
+        tag.push('-');
+        tag.push_str(&subtag);
+    }
+    Some(tag)
+}
+
+/// `tag` followed by ever less specific tags: `zh-Hant-TW`, `zh-Hant`, `zh`.
+pub fn fallback_chain(tag: &str) -> Vec<String> {
+    let mut chain = vec![tag.to_string()];
+    let mut current = tag;
+    while let Some((parent, _)) = current.rsplit_once('-') {
+        chain.push(parent.to_string());
+        current = parent;
+    }
+    chain
+}
+
+// ── Negotiation ───────────────────────────────────────────────────────────────
+
+/// The request's `Accept-Language` tags, most preferred first. Wildcards,
+/// malformed entries and `q=0` are dropped; equal weights keep header order.
+fn accept_language(req: &HttpRequest) -> Vec<String> {
+    let Some(header) = req
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut ranked: Vec<(f32, String)> = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut params = entry.split(';');
+            let tag = normalize_locale(params.next()?)?;
+            let q = match params.find_map(|param| param.trim().strip_prefix("q=")) {
+                Some(q) => q.trim().parse().ok()?,
+                None => 1.0,
+            };
+            (q > 0.0).then_some((q, tag))
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    ranked.into_iter().map(|(_, tag)| tag).collect()
+}
+
+/// The locales to localize a response in, most preferred first: `explicit`
+/// (a `?lang=` parameter) or else the profile language from the token, then
+/// the `Accept-Language` tags. Each is followed by its fallbacks and English
+/// always comes last, so `pt-BR` resolves as `pt-BR`, `pt`, `en`.
+pub fn preferred_locales(
+    req: &HttpRequest,
+    claims: &HashMap<String, String>,
+    explicit: Option<&str>,
+) -> Vec<String> {
+    let chosen = explicit
+        .and_then(normalize_locale)
+        .or_else(|| claims.get("language").and_then(|tag| normalize_locale(tag)));
+
+    let mut locales: Vec<String> = Vec::new();
+    for tag in chosen.into_iter().chain(accept_language(req)) {
+        for fallback in fallback_chain(&tag) {
+            if !locales.contains(&fallback) {
+                locales.push(fallback);
+            }
+        }
+    }
+    if !locales.iter().any(|tag| tag == DEFAULT_LOCALE) {
+        locales.push(DEFAULT_LOCALE.to_string());
+    }
+    locales
+}
+
+/// The first translation of a field along `locales`. The untranslated text is
+/// English, so the search stops at `en`: a Portuguese title never wins over
+/// the original for a viewer who prefers English.
+pub fn translated<'a, T>(
+    translations: &'a BTreeMap<String, T>,
+    locales: &[String],
+    field: impl Fn(&'a T) -> &'a Option<String>,
+) -> Option<&'a str> {
+    locales
+        .iter()
+        .take_while(|tag| tag.as_str() != DEFAULT_LOCALE)
+        .find_map(|tag| field(translations.get(tag)?).as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn accepting(header: &str) -> HttpRequest {
+        TestRequest::default()
+            .insert_header((ACCEPT_LANGUAGE, header))
+            .to_http_request()
+    }
+
+    #[test]
+    fn normalize_canonicalizes_case_and_separators() {
+        assert_eq!(normalize_locale("pt-br").as_deref(), Some("pt-BR"));
+        assert_eq!(
+            normalize_locale(" zh_hant_tw ").as_deref(),
+            Some("zh-Hant-TW")
+        );
+        assert_eq!(normalize_locale("es-419").as_deref(), Some("es-419"));
+        assert_eq!(normalize_locale("*"), None);
+        assert_eq!(normalize_locale("english"), None);
+        assert_eq!(normalize_locale("en-"), None);
+        assert_eq!(normalize_locale("en-U$"), None);
+    }
+
+    #[test]
+    fn normalize_keeps_the_tag_without_unknown_subtags() {
+        assert_eq!(normalize_locale("de-CH-1996").as_deref(), Some("de-CH"));
+        assert_eq!(normalize_locale("sl-rozaj-biske").as_deref(), Some("sl"));
+        assert_eq!(
+            normalize_locale("en-US-u-ca-gregory").as_deref(),
+            Some("en-US")
+        );
+        assert_eq!(normalize_locale("en-x-Latn").as_deref(), Some("en"));
+        assert_eq!(normalize_locale("pt-BR-PT").as_deref(), Some("pt-BR"));
+    }
+
+    #[test]
+    fn fallback_chain_drops_one_subtag_at_a_time() {
+        assert_eq!(
+            fallback_chain("zh-Hant-TW"),
+            ["zh-Hant-TW", "zh-Hant", "zh"]
+        );
+        assert_eq!(fallback_chain("en"), ["en"]);
+    }
+
+    #[test]
+    fn accept_language_orders_by_q_value() {
+        let req = accepting("de;q=0.5, fr-CA, en;q=0.8");
+        assert_eq!(accept_language(&req), ["fr-CA", "en", "de"]);
+    }
+
+    #[test]
+    fn accept_language_keeps_header_order_on_ties() {
+        let req = accepting("it;q=0.7, es, ja;q=0.7, fr");
+        assert_eq!(accept_language(&req), ["es", "fr", "it", "ja"]);
+    }
+
+    #[test]
+    fn accept_language_drops_wildcards_and_refused_tags() {
+        let req = accepting("*, de;q=0, fr;q=0.0, nl;q=abc, sv;q=0.1");
+        assert_eq!(accept_language(&req), ["sv"]);
+        assert!(accept_language(&TestRequest::default().to_http_request()).is_empty());
+    }
+
+    #[test]
+    fn preferred_locales_fall_back_to_english() {
+        let req = accepting("pt-BR");
+        assert_eq!(
+            preferred_locales(&req, &HashMap::new(), None),
+            ["pt-BR", "pt", "en"]
+        );
+
+        let claims = HashMap::from([("language".to_string(), "de-at".to_string())]);
+        let req = accepting("en-GB, pt;q=0.5");
+        assert_eq!(
+            preferred_locales(&req, &claims, Some("pt-br")),
+            ["pt-BR", "pt", "en-GB", "en"]
+        );
+        assert_eq!(
+            preferred_locales(&req, &claims, None),
+            ["de-AT", "de", "en-GB", "en", "pt"]
+        );
+    }
+
+    #[test]
+    fn translated_stops_at_the_default_locale() {
+        let translations = BTreeMap::from([
+            ("pt".to_string(), Some("Olá".to_string())),
+            ("de".to_string(), None),
+        ]);
+        let locales = ["pt-BR", "pt", "en"].map(String::from);
+        assert_eq!(translated(&translations, &locales, |t| t), Some("Olá"));
+        let locales = ["de", "en", "pt"].map(String::from);
+        assert_eq!(translated(&translations, &locales, |t| t), None);
+    }
+}
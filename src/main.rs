@@ -1,5 +1,30 @@
+mod activity;
+mod catalog;
+mod charts;
+mod credits;
+mod events;
+mod export;
+mod featured;
+mod genres;
+mod geo;
+mod images;
+mod import;
+mod locale;
+mod ratings;
+mod recommendations;
 mod models;
+mod reviews;
+mod revisions;
 mod routes;
+mod scheduler;
+mod search;
+mod similar;
+mod smart_lists;
+mod storage;
+mod subtitles;
+#[cfg(test)]
+mod test_support;
+mod translations;
 mod utils;
 mod verify_token;
 
@@ -8,15 +33,63 @@ use actix_web::middleware::Logger;
 use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use dotenv::dotenv;
 use env_logger::Env;
-use mongodb::{options::ClientOptions, Client};
+use mongodb::{
+    bson::doc,
+    options::{ClientOptions, IndexOptions},
+    Client, IndexModel,
+};
 use std::env;
 use std::net::TcpListener;
+use std::time::Duration;
 
-use crate::models::{list, movie, user, users}; 
-use routes::auth::{login_user, register_user};
-use routes::lists::{create_list, delete_list, get_lists};
-use routes::movies::{create_movie, get_all_movies, get_movie, get_random_movie};
-use routes::users::{get_all_users, get_user};
+use crate::events::CatalogEvents;
+use crate::models::{
+    chart, featured as featured_impression, genre, import as import_job, list, movie, person,
+    rating, recommendation, review, revision, stream, subtitle, user, users, view, watchlist,
+    workflow,
+};
+use routes::assets::{get_asset, upload_avatar, upload_movie_image};
+use routes::auth::{login_user, register_user, set_language};
+use routes::charts::{add_chart_override, delete_chart_override, get_chart_overrides};
+use routes::credits::set_movie_credits;
+use routes::export::export_collection;
+use routes::genres::{
+    create_genre, delete_genre, get_genre_migration, get_genres, migrate_legacy_genres,
+    update_genre,
+};
+use routes::images::get_image;
+use routes::import::{get_import_job, import_movies, MAX_IMPORT_BYTES};
+use routes::lists::{
+    add_list_item, create_list, delete_list, get_lists, move_list_item, patch_list,
+    preview_smart_list, remove_list_item, replace_list, replace_list_item,
+};
+use routes::movies::{
+    create_movie, delete_movie, get_all_movies, get_movie, get_random_movie, update_movie,
+};
+use routes::people::{create_person, delete_person, get_person, update_person};
+use routes::ratings::{clear_rating, get_my_ratings, rate_movie};
+use routes::recommendations::get_recommendations;
+use routes::reviews::{
+    create_review, delete_review, get_review_queue, get_reviews, mark_helpful, moderate_review,
+    unmark_helpful, update_review,
+};
+use routes::revisions::{get_movie_revisions, restore_movie_revision};
+use routes::search::search;
+use routes::similar::get_similar_movies;
+use routes::streams::{get_streams, heartbeat_stream, lease_ttl_secs, start_stream, stop_stream};
+use routes::subtitles::{
+    delete_subtitle, get_master_playlist, get_subtitle, get_subtitle_playlist, set_audio_tracks,
+    upload_subtitle,
+};
+use routes::trash::{get_trash, restore_from_trash};
+use routes::translations::{
+    delete_translation, get_translation_coverage, get_translations, set_translation,
+};
+use routes::users::{assign_initial_roles, delete_user, get_all_users, get_user, set_user_role};
+use routes::watchlist::{add_to_watchlist, get_watchlist, remove_from_watchlist};
+use routes::workflow::{
+    get_list_status_history, get_movie_status_history, set_list_status, set_movie_status,
+};
 
 // ── Health check ──────────────────────────────────────────────────────────────
 
@@ -51,9 +124,435 @@ async fn main() -> std::io::Result<()> {
     let list_collection = db.collection::<list::List>("lists");
     // user management routes use the richer Users model (get / list)
     let users_collection = db.collection::<users::Users>("users");
+    let stream_collection = db.collection::<stream::StreamLease>("streams");
+    let subtitle_collection = db.collection::<subtitle::Subtitle>("subtitles");
+
+    // Every authenticated request checks that the token's account is still live.
+    let user_email_index = IndexModel::builder().keys(doc! { "email": 1 }).build();
+    if let Err(e) = auth_collection.create_index(user_email_index).await {
+        log::warn!("Failed to create user email index: {}", e);
+    }
+    if let Err(e) = assign_initial_roles(&users_collection).await {
+        log::warn!("Failed to assign roles to existing admins: {}", e);
+    }
+
+    // Stale stream leases are dropped by MongoDB once their heartbeat is older than the TTL.
+    let lease_ttl_index = IndexModel::builder()
+        .keys(doc! { "heartbeat_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(lease_ttl_secs()))
+                .build(),
+        )
+        .build();
+    // One live lease per stream slot; leases from before slots existed have none.
+    let lease_slot_index = IndexModel::builder()
+        .keys(doc! { "user": 1, "slot": 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "slot": { "$exists": true } })
+                .build(),
+        )
+        .build();
+    if let Err(e) = stream_collection
+        .create_indexes([lease_ttl_index, lease_slot_index])
+        .await
+    {
+        log::warn!("Failed to create stream lease indexes: {}", e);
+    }
+
+    let watchlist_collection = db.collection::<watchlist::WatchlistEntry>("watchlist");
+
+    // One entry per user and title.
+    let watchlist_index = IndexModel::builder()
+        .keys(doc! { "user": 1, "movie_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    // Serves the per-user listing, most recently added first.
+    let watchlist_added_index = IndexModel::builder()
+        .keys(doc! { "user": 1, "added_at": -1 })
+        .build();
+    if let Err(e) = watchlist_collection
+        .create_indexes([watchlist_index, watchlist_added_index])
+        .await
+    {
+        log::warn!("Failed to create watchlist indexes: {}", e);
+    }
+
+    let status_collection = db.collection::<workflow::StatusChange>("status_changes");
+
+    // Serves the per-entry history, newest first.
+    let status_index = IndexModel::builder()
+        .keys(doc! { "entity": 1, "entity_id": 1, "at": -1 })
+        .build();
+    if let Err(e) = status_collection.create_index(status_index).await {
+        log::warn!("Failed to create status history index: {}", e);
+    }
+
+    let revision_collection = db.collection::<revision::MovieRevision>("movie_revisions");
+
+    // One document per movie and revision number; serves the newest-first history.
+    let revision_index = IndexModel::builder()
+        .keys(doc! { "movie_id": 1, "rev": -1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    if let Err(e) = revision_collection.create_index(revision_index).await {
+        log::warn!("Failed to create movie revision index: {}", e);
+    }
+
+    // Imports upsert on the partner's id; titles created by hand have none.
+    let external_id_index = IndexModel::builder()
+        .keys(doc! { "external_id": 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "external_id": { "$type": "string" } })
+                .build(),
+        )
+        .build();
+    if let Err(e) = movie_collection.create_index(external_id_index).await {
+        log::warn!("Failed to create movie external id index: {}", e);
+    }
+    // The similarity index checks the newest edit to notice catalog changes.
+    let movie_updated_index = IndexModel::builder()
+        .keys(doc! { "updated_at": 1 })
+        .build();
+    if let Err(e) = movie_collection.create_index(movie_updated_index).await {
+        log::warn!("Failed to create movie updated_at index: {}", e);
+    }
+
+    let import_job_collection = db.collection::<import_job::ImportJob>("import_jobs");
+
+    let rating_collection = db.collection::<rating::Rating>("ratings");
+
+    // One rating per user and title.
+    let rating_index = IndexModel::builder()
+        .keys(doc! { "user": 1, "movie_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    // Serves the per-user listing, most recent first.
+    let rating_user_index = IndexModel::builder()
+        .keys(doc! { "user": 1, "rated_at": -1 })
+        .build();
+    // Lets a purged movie's ratings be found without a collection scan.
+    let rating_movie_index = IndexModel::builder()
+        .keys(doc! { "movie_id": 1 })
+        .build();
+    if let Err(e) = rating_collection
+        .create_indexes([rating_index, rating_user_index, rating_movie_index])
+        .await
+    {
+        log::warn!("Failed to create rating indexes: {}", e);
+    }
+
+    let review_collection = db.collection::<review::Review>("reviews");
+
+    // One review per user and title; serves the per-title listings.
+    let review_index = IndexModel::builder()
+        .keys(doc! { "movie_id": 1, "user": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    // Serves the moderation queue and the purge of a user's reviews.
+    let review_status_index = IndexModel::builder()
+        .keys(doc! { "status": 1, "updated_at": 1 })
+        .build();
+    let review_user_index = IndexModel::builder()
+        .keys(doc! { "user": 1, "updated_at": 1 })
+        .build();
+    if let Err(e) = review_collection
+        .create_indexes([review_index, review_status_index, review_user_index])
+        .await
+    {
+        log::warn!("Failed to create review indexes: {}", e);
+    }
+
+    let review_attempt_collection = db.collection::<review::ReviewAttempt>("review_attempts");
+
+    // Serves the per-user rate limit; attempts expire with their window.
+    let review_attempt_index = IndexModel::builder()
+        .keys(doc! { "user": 1, "at": 1 })
+        .build();
+    let review_attempt_ttl_index = IndexModel::builder()
+        .keys(doc! { "expires_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(0))
+                .build(),
+        )
+        .build();
+    if let Err(e) = review_attempt_collection
+        .create_indexes([review_attempt_index, review_attempt_ttl_index])
+        .await
+    {
+        log::warn!("Failed to create review attempt indexes: {}", e);
+    }
+
+    let review_vote_collection = db.collection::<review::ReviewVote>("review_votes");
+
+    // One helpful mark per user and review.
+    let review_vote_index = IndexModel::builder()
+        .keys(doc! { "review_id": 1, "user": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    if let Err(e) = review_vote_collection.create_index(review_vote_index).await {
+        log::warn!("Failed to create review vote index: {}", e);
+    }
+
+    let view_collection = db.collection::<view::View>("views");
+
+    // One document per user and title; the movie index serves purges.
+    let view_index = IndexModel::builder()
+        .keys(doc! { "user": 1, "movie_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    let view_movie_index = IndexModel::builder()
+        .keys(doc! { "movie_id": 1 })
+        .build();
+    if let Err(e) = view_collection
+        .create_indexes([view_index, view_movie_index])
+        .await
+    {
+        log::warn!("Failed to create view indexes: {}", e);
+    }
+
+    let view_event_collection = db.collection::<view::ViewEvent>("view_events");
+
+    // Views only matter for the charts' windows; MongoDB drops them afterwards.
+    let view_event_ttl_index = IndexModel::builder()
+        .keys(doc! { "at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(
+                    charts::VIEW_EVENT_RETENTION_DAYS * 24 * 60 * 60,
+                ))
+                .build(),
+        )
+        .build();
+    if let Err(e) = view_event_collection.create_index(view_event_ttl_index).await {
+        log::warn!("Failed to create view event TTL index: {}", e);
+    }
+
+    let chart_override_collection = db.collection::<chart::ChartOverride>("chart_overrides");
+
+    // One computed row per chart kind, region and media type.
+    let chart_list_index = IndexModel::builder()
+        .keys(doc! { "chart.kind": 1, "chart.region": 1, "type_list": 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "chart.kind": { "$exists": true } })
+                .build(),
+        )
+        .build();
+    if let Err(e) = list_collection.create_index(chart_list_index).await {
+        log::warn!("Failed to create chart list index: {}", e);
+    }
+
+    let impression_collection =
+        db.collection::<featured_impression::FeaturedImpression>("featured_impressions");
+    let featured_policy = web::Data::new(featured::FeaturedPolicy::from_env());
+
+    // One showing per user and title, forgotten once the repeat window has passed.
+    let impression_index = IndexModel::builder()
+        .keys(doc! { "user": 1, "movie_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    if let Err(e) = impression_collection.create_index(impression_index).await {
+        log::warn!("Failed to create featured impression index: {}", e);
+    }
+    let impression_ttl_secs = featured_policy.repeat_hours * 60 * 60;
+    let impression_ttl_index = IndexModel::builder()
+        .keys(doc! { "shown_at": 1 })
+        .options(
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(impression_ttl_secs))
+                .build(),
+        )
+        .build();
+    // An index left by an earlier FEATURED_REPEAT_HOURS conflicts with the new
+    // TTL; MongoDB can change it in place.
+    if impression_collection
+        .create_index(impression_ttl_index)
+        .await
+        .is_err()
+    {
+        let resize = doc! {
+            "collMod": impression_collection.name(),
+            "index": {
+                "keyPattern": { "shown_at": 1 },
+                "expireAfterSeconds": impression_ttl_secs as i64,
+            },
+        };
+        if let Err(e) = db.run_command(resize).await {
+            log::warn!("Failed to update featured impression TTL index: {}", e);
+        }
+    }
+
+    let recommendation_model_collection =
+        db.collection::<recommendation::ItemModel>("recommendation_model");
+
+    // Serves the popularity fallback for users without history.
+    let popularity_index = IndexModel::builder()
+        .keys(doc! { "popularity": -1 })
+        .build();
+    if let Err(e) = recommendation_model_collection
+        .create_index(popularity_index)
+        .await
+    {
+        log::warn!("Failed to create recommendation model index: {}", e);
+    }
+
+    let genre_collection = db.collection::<genre::Genre>("genres");
+
+    // Movies and lists reference genres by slug.
+    let genre_slug_index = IndexModel::builder()
+        .keys(doc! { "slug": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    if let Err(e) = genre_collection.create_index(genre_slug_index).await {
+        log::warn!("Failed to create genre index: {}", e);
+    }
+    let movie_genres_index = IndexModel::builder().keys(doc! { "genres": 1 }).build();
+    if let Err(e) = movie_collection.create_index(movie_genres_index).await {
+        log::warn!("Failed to create movie genres index: {}", e);
+    }
+
+    let genre_migration_collection = db.collection::<genre::GenreMigration>("genre_migrations");
+
+    // At most one legacy genre migration runs at a time.
+    let genre_migration_index = IndexModel::builder()
+        .keys(doc! { "status": 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "status": "running" })
+                .build(),
+        )
+        .build();
+    if let Err(e) = genre_migration_collection
+        .create_index(genre_migration_index)
+        .await
+    {
+        log::warn!("Failed to create genre migration index: {}", e);
+    }
+    if let Err(e) = genres::fail_interrupted_migrations(&genre_migration_collection).await {
+        log::warn!("Failed to close interrupted genre migrations: {}", e);
+    }
+
+    let person_collection = db.collection::<person::Person>("people");
+    let credit_collection = db.collection::<person::Credit>("credits");
+
+    // Serves the cast of a title in billing order, and a person's filmography.
+    let credit_movie_index = IndexModel::builder()
+        .keys(doc! { "movie_id": 1, "episode": 1, "role": 1, "order": 1 })
+        .build();
+    let credit_person_index = IndexModel::builder()
+        .keys(doc! { "person_id": 1 })
+        .build();
+    // A person is credited at most once per role within a set.
+    let credit_set_index = IndexModel::builder()
+        .keys(doc! { "set_id": 1, "role": 1, "person_id": 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "set_id": { "$exists": true } })
+                .build(),
+        )
+        .build();
+    // The similarity index checks the newest credit to notice replaced casts.
+    let credit_created_index = IndexModel::builder()
+        .keys(doc! { "created_at": 1 })
+        .build();
+    if let Err(e) = credit_collection
+        .create_indexes([
+            credit_movie_index,
+            credit_person_index,
+            credit_set_index,
+            credit_created_index,
+        ])
+        .await
+    {
+        log::warn!("Failed to create credit indexes: {}", e);
+    }
+
+    // Search matches anchored prefixes of these derived keys.
+    let movie_search_index = IndexModel::builder()
+        .keys(doc! { "search_keys": 1 })
+        .build();
+    if let Err(e) = movie_collection.create_index(movie_search_index).await {
+        log::warn!("Failed to create movie search index: {}", e);
+    }
+    let person_search_index = IndexModel::builder()
+        .keys(doc! { "search_keys": 1 })
+        .build();
+    if let Err(e) = person_collection.create_index(person_search_index).await {
+        log::warn!("Failed to create person search index: {}", e);
+    }
+    search::spawn_search_backfill(movie_collection.clone(), person_collection.clone());
 
     log::info!("MongoDB connected!");
 
+    // ── Background tasks ──────────────────────────────────────────────────────
+
+    let catalog_events = CatalogEvents::default();
+    smart_lists::spawn_invalidation_listener(list_collection.clone(), &catalog_events);
+    smart_lists::spawn_smart_list_refresher(list_collection.clone(), movie_collection.clone());
+    scheduler::spawn_availability_watcher(
+        movie_collection.clone(),
+        list_collection.clone(),
+        catalog_events.clone(),
+    );
+    // In-process TF-IDF corpus for "more like this", rebuilt as the catalog changes.
+    let similarity_index = web::Data::new(similar::SimilarityIndex::default());
+    similar::spawn_similarity_refresher(
+        movie_collection.clone(),
+        credit_collection.clone(),
+        similarity_index.clone().into_inner(),
+    );
+    ratings::spawn_rating_recounter(
+        movie_collection.clone_with_type(),
+        rating_collection.clone_with_type(),
+    );
+    recommendations::spawn_recommendation_builder(
+        view_collection.clone(),
+        rating_collection.clone(),
+        recommendation_model_collection.clone(),
+    );
+    // Override changes ask for an early refresh rather than running one inline.
+    let chart_refresh = web::Data::new(charts::ChartRefresh::default());
+    charts::spawn_chart_builder(
+        view_event_collection.clone(),
+        movie_collection.clone(),
+        list_collection.clone(),
+        chart_override_collection.clone(),
+        chart_refresh.clone().into_inner(),
+    );
+    scheduler::spawn_trash_purger(scheduler::PurgeCollections {
+        movies: movie_collection.clone_with_type(),
+        lists: list_collection.clone_with_type(),
+        users: users_collection.clone_with_type(),
+        watchlist: watchlist_collection.clone_with_type(),
+        revisions: revision_collection.clone_with_type(),
+        ratings: rating_collection.clone_with_type(),
+        reviews: review_collection.clone_with_type(),
+        review_votes: review_vote_collection.clone_with_type(),
+        views: view_collection.clone_with_type(),
+        view_events: view_event_collection.clone_with_type(),
+        chart_overrides: chart_override_collection.clone_with_type(),
+        credits: credit_collection.clone_with_type(),
+        streams: stream_collection.clone_with_type(),
+        subtitles: subtitle_collection.clone_with_type(),
+    });
+
+    let import_collections = import::ImportCollections {
+        movies: movie_collection.clone(),
+        lists: list_collection.clone(),
+        revisions: revision_collection.clone(),
+        genres: genre_collection.clone(),
+        jobs: import_job_collection.clone(),
+    };
+
     // ── Bind ──────────────────────────────────────────────────────────────────
 
     let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string());
@@ -62,6 +561,24 @@ async fn main() -> std::io::Result<()> {
 
     log::info!("Server listening on port {}", port);
 
+    // Opened once and shared: the GeoIP database is read fully into memory.
+    let region_resolver = web::Data::new(geo::RegionResolver::from_env());
+
+    // Content filters and limits for user reviews; the word list is read once.
+    let review_policy = web::Data::new(reviews::ReviewPolicy::from_env(review_attempt_collection));
+
+    // Playback starts feed both recommendations and the activity charts.
+    let view_log = web::Data::new(activity::ViewLog {
+        views: view_collection.clone(),
+        events: view_event_collection.clone(),
+    });
+
+    // Local directory or S3-compatible bucket for uploaded images.
+    let asset_storage: web::Data<dyn storage::AssetStorage> =
+        web::Data::from(storage::storage_from_env());
+    // Shared by on-demand image renders and the pre-rendering of uploads.
+    let render_slots = web::Data::new(images::RenderSlots::from_env());
+
     // ── Server ────────────────────────────────────────────────────────────────
 
     HttpServer::new(move || {
@@ -69,7 +586,7 @@ async fn main() -> std::io::Result<()> {
             .wrap(
                 Cors::default()
                     .allowed_origin("https://visionarynetflixclone.vercel.app")
-                    .allowed_methods(vec!["GET", "POST", "DELETE", "OPTIONS"])
+                    .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
                     .allowed_headers(vec!["Content-Type", "Authorization"])
                     .max_age(3600),
             )
@@ -80,6 +597,34 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(movie_collection.clone()))
             .app_data(web::Data::new(list_collection.clone()))
             .app_data(web::Data::new(users_collection.clone()))
+            .app_data(web::Data::new(stream_collection.clone()))
+            .app_data(web::Data::new(subtitle_collection.clone()))
+            .app_data(web::Data::new(watchlist_collection.clone()))
+            .app_data(web::Data::new(status_collection.clone()))
+            .app_data(web::Data::new(revision_collection.clone()))
+            .app_data(web::Data::new(import_job_collection.clone()))
+            .app_data(web::Data::new(import_collections.clone()))
+            .app_data(web::Data::new(rating_collection.clone()))
+            .app_data(web::Data::new(review_collection.clone()))
+            .app_data(web::Data::new(review_vote_collection.clone()))
+            .app_data(web::Data::new(view_collection.clone()))
+            .app_data(web::Data::new(view_event_collection.clone()))
+            .app_data(web::Data::new(chart_override_collection.clone()))
+            .app_data(web::Data::new(impression_collection.clone()))
+            .app_data(web::Data::new(recommendation_model_collection.clone()))
+            .app_data(web::Data::new(genre_collection.clone()))
+            .app_data(web::Data::new(genre_migration_collection.clone()))
+            .app_data(web::Data::new(person_collection.clone()))
+            .app_data(web::Data::new(credit_collection.clone()))
+            .app_data(web::Data::new(catalog_events.clone()))
+            .app_data(region_resolver.clone())
+            .app_data(asset_storage.clone())
+            .app_data(render_slots.clone())
+            .app_data(review_policy.clone())
+            .app_data(similarity_index.clone())
+            .app_data(chart_refresh.clone())
+            .app_data(view_log.clone())
+            .app_data(featured_policy.clone())
             .service(
                 web::scope("/api/auth")
                     .route("/register", web::post().to(register_user))
@@ -90,18 +635,145 @@ async fn main() -> std::io::Result<()> {
                     .route("/", web::post().to(create_movie))
                     .route("/", web::get().to(get_all_movies))
                     .route("/find/{id}", web::get().to(get_movie))
-                    .route("/random", web::get().to(get_random_movie)),
+                    .route("/random", web::get().to(get_random_movie))
+                    .route("/{id}", web::put().to(update_movie))
+                    .route("/{id}", web::delete().to(delete_movie))
+                    .route("/{id}/similar", web::get().to(get_similar_movies))
+                    .route("/{id}/credits", web::put().to(set_movie_credits))
+                    .route("/{id}/revisions", web::get().to(get_movie_revisions))
+                    .route(
+                        "/{id}/revisions/{rev}/restore",
+                        web::post().to(restore_movie_revision),
+                    )
+                    .route("/{id}/status", web::post().to(set_movie_status))
+                    .route("/{id}/status-history", web::get().to(get_movie_status_history))
+                    .route("/{id}/master.m3u8", web::get().to(get_master_playlist))
+                    .route("/{id}/subtitles/{lang}.vtt", web::get().to(get_subtitle))
+                    .route(
+                        "/{id}/subtitles/{lang}.m3u8",
+                        web::get().to(get_subtitle_playlist),
+                    )
+                    .service(
+                        // Subtitle files can exceed the default 256 KiB payload limit.
+                        web::resource("/{id}/subtitles/{lang}")
+                            .app_data(web::PayloadConfig::new(2 * 1024 * 1024))
+                            .route(web::put().to(upload_subtitle))
+                            .route(web::delete().to(delete_subtitle)),
+                    )
+                    .route("/{id}/audio-tracks", web::put().to(set_audio_tracks))
+                    .route("/{id}/images/{field}", web::post().to(upload_movie_image))
+                    .route("/{id}/reviews", web::post().to(create_review))
+                    .route("/{id}/reviews", web::get().to(get_reviews))
+                    .route("/{id}/reviews/{review_id}", web::put().to(update_review))
+                    .route("/{id}/reviews/{review_id}", web::delete().to(delete_review))
+                    .route("/{id}/reviews/{review_id}/helpful", web::post().to(mark_helpful))
+                    .route(
+                        "/{id}/reviews/{review_id}/helpful",
+                        web::delete().to(unmark_helpful),
+                    ),
             )
             .service(
                 web::scope("/api/lists")
                     .route("/", web::post().to(create_list))
+                    .route("/preview", web::post().to(preview_smart_list))
                     .route("/{id}", web::delete().to(delete_list))
+                    .route("/{id}", web::put().to(replace_list))
+                    .route("/{id}", web::patch().to(patch_list))
+                    .route("/{id}/status", web::post().to(set_list_status))
+                    .route("/{id}/status-history", web::get().to(get_list_status_history))
+                    .route("/{id}/items", web::post().to(add_list_item))
+                    .route("/{id}/items/move", web::post().to(move_list_item))
+                    .route("/{id}/items/{movie_id}", web::put().to(replace_list_item))
+                    .route("/{id}/items/{movie_id}", web::delete().to(remove_list_item))
                     .route("/", web::get().to(get_lists)),
             )
+            .service(
+                web::scope("/api/genres")
+                    .route("/", web::get().to(get_genres))
+                    .route("/", web::post().to(create_genre))
+                    .route("/{slug}", web::put().to(update_genre))
+                    .route("/{slug}", web::delete().to(delete_genre)),
+            )
+            .service(
+                web::scope("/api/people")
+                    .route("/", web::post().to(create_person))
+                    .route("/{id}", web::get().to(get_person))
+                    .route("/{id}", web::put().to(update_person))
+                    .route("/{id}", web::delete().to(delete_person)),
+            )
+            .service(
+                web::scope("/api/search")
+                    .route("/", web::get().to(search)),
+            )
             .service(
                 web::scope("/api/users")
                     .route("/", web::get().to(get_all_users))
-                    .route("/{id}", web::get().to(get_user)),
+                    .route("/{id}", web::get().to(get_user))
+                    .route("/{id}", web::delete().to(delete_user))
+                    .route("/{id}/role", web::put().to(set_user_role)),
+            )
+            .service(
+                web::scope("/api/trash")
+                    .route("/{kind}", web::get().to(get_trash))
+                    .route("/{kind}/{id}/restore", web::post().to(restore_from_trash)),
+            )
+            .service(
+                web::scope("/api/me")
+                    .route("/streams", web::get().to(get_streams))
+                    .route("/streams", web::post().to(start_stream))
+                    .route("/streams/{id}/heartbeat", web::post().to(heartbeat_stream))
+                    .route("/streams/{id}", web::delete().to(stop_stream))
+                    .route("/list", web::get().to(get_watchlist))
+                    .route("/list/{movie_id}", web::post().to(add_to_watchlist))
+                    .route("/list/{movie_id}", web::delete().to(remove_from_watchlist))
+                    .route("/ratings", web::get().to(get_my_ratings))
+                    .route("/ratings/{movie_id}", web::put().to(rate_movie))
+                    .route("/ratings/{movie_id}", web::delete().to(clear_rating))
+                    .route("/recommendations", web::get().to(get_recommendations))
+                    .route("/avatar", web::post().to(upload_avatar))
+                    .route("/language", web::put().to(set_language)),
+            )
+            .service(
+                web::scope("/api/assets")
+                    .route("/{key}", web::get().to(get_asset)),
+            )
+            .service(
+                web::scope("/api/images")
+                    .route("/{id}", web::get().to(get_image)),
+            )
+            .service(
+                web::scope("/api/admin")
+                    .service(
+                        // Partner catalog files are far larger than the default payload limit.
+                        web::resource("/import/movies")
+                            .app_data(web::PayloadConfig::new(MAX_IMPORT_BYTES))
+                            .route(web::post().to(import_movies)),
+                    )
+                    .route("/import/jobs/{id}", web::get().to(get_import_job))
+                    .route("/export/{collection}", web::get().to(export_collection))
+                    .route("/reviews", web::get().to(get_review_queue))
+                    .route("/reviews/{id}/moderate", web::post().to(moderate_review))
+                    .route("/charts/overrides", web::get().to(get_chart_overrides))
+                    .route("/charts/overrides", web::post().to(add_chart_override))
+                    .route(
+                        "/charts/overrides/{id}",
+                        web::delete().to(delete_chart_override),
+                    )
+                    .route("/genres/migrate", web::post().to(migrate_legacy_genres))
+                    .route("/genres/migrate/{id}", web::get().to(get_genre_migration))
+                    .route(
+                        "/translations/coverage",
+                        web::get().to(get_translation_coverage),
+                    )
+                    .route("/translations/{kind}/{id}", web::get().to(get_translations))
+                    .route(
+                        "/translations/{kind}/{id}/{locale}",
+                        web::put().to(set_translation),
+                    )
+                    .route(
+                        "/translations/{kind}/{id}/{locale}",
+                        web::delete().to(delete_translation),
+                    ),
             )
             .service(
                 web::scope("/api/health")
@@ -0,0 +1,43 @@
+use crate::models::list::ChartKind;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideAction {
+    /// Always show the title, at `position` when given.
+    Pin,
+    /// Never show the title.
+    Exclude,
+}
+
+/// An editorial correction to the computed charts. Unset `region` and
+/// `type_list` apply the override to every matching chart of that kind.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChartOverride {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub kind: ChartKind,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+
+    /// `movie` or `series`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_list: Option<String>,
+
+    pub movie_id: String,
+
+    pub action: OverrideAction,
+
+    /// 1-based slot for a pin; pins without one go first, in creation order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u32>,
+
+    /// Email of the admin who added it.
+    #[serde(default)]
+    pub created_by: String,
+
+    pub created_at: DateTime,
+}
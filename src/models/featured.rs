@@ -0,0 +1,18 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// A title shown to a user on the featured billboard. A unique index on
+/// `(user, movie_id)` keeps the latest showing; a TTL index on `shown_at`
+/// forgets it once the repeat window has passed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeaturedImpression {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    /// Email of the viewing account (the token `sub`).
+    pub user: String,
+
+    pub movie_id: String,
+
+    pub shown_at: DateTime,
+}
@@ -0,0 +1,110 @@
+use crate::genres::MigrationReport;
+use crate::locale::DEFAULT_LOCALE;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One node of the genre taxonomy. Movies reference genres by `slug`,
+/// which is unique and never changes once created.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Genre {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    /// e.g. `action-adventure`
+    pub slug: String,
+
+    /// Display name per locale, e.g. `{"en": "Action & Adventure", "pt-BR": "Ação e Aventura"}`.
+    /// Every genre has an English name.
+    pub names: BTreeMap<String, String>,
+
+    /// Slug of the broader genre this one belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime>,
+}
+
+impl Genre {
+    /// The name in the first of `locales` it has one in, falling back to
+    /// English, then to the slug.
+    pub fn display_name(&self, locales: &[String]) -> &str {
+        locales
+            .iter()
+            .find_map(|tag| self.names.get(tag))
+            .or_else(|| self.names.get(DEFAULT_LOCALE))
+            .map_or(self.slug.as_str(), String::as_str)
+    }
+}
+
+/// A genre as listed by `GET /genres`, with its localized name and how many
+/// titles the caller can see in it.
+#[derive(Debug, Serialize)]
+pub struct GenreEntry {
+    pub slug: String,
+
+    pub name: String,
+
+    pub names: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// Titles tagged with this genre itself.
+    pub count: u64,
+
+    /// Titles tagged with this genre or any genre below it.
+    pub total: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+impl MigrationStatus {
+    /// The stored name, as in `status`.
+    pub fn name(self) -> &'static str {
+        match self {
+            MigrationStatus::Running => "running",
+            MigrationStatus::Completed => "completed",
+            MigrationStatus::Failed => "failed",
+        }
+    }
+}
+
+/// A run of the legacy genre migration. It runs in the background; a unique
+/// partial index on `status: running` keeps a second one from starting
+/// alongside it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GenreMigration {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub status: MigrationStatus,
+
+    /// What changed, once `status` is `completed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<MigrationReport>,
+
+    /// Why the run stopped, when `status` is `failed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+
+    /// Email of the admin who started it.
+    pub actor: String,
+
+    pub created_at: DateTime,
+
+    pub updated_at: DateTime,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime>,
+}
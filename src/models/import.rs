@@ -0,0 +1,71 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Why one input row was not imported. `row` is 1-based, not counting a CSV header.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RowError {
+    pub row: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+
+    pub message: String,
+}
+
+/// Progress of a bulk movie import. Rows are written in chunks and
+/// `processed_rows` only advances once a chunk is stored, so an interrupted
+/// job can be resumed by posting the same file again with `?job=`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportJob {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub status: ImportStatus,
+
+    /// `csv` or `ndjson`.
+    pub format: String,
+
+    /// SHA-256 of the uploaded file, hex; a resume must send the same bytes.
+    #[serde(default)]
+    pub content_hash: String,
+
+    pub total_rows: u64,
+
+    pub processed_rows: u64,
+
+    pub inserted: u64,
+
+    pub updated: u64,
+
+    /// Total rows rejected; `errors` keeps only the first ones.
+    pub error_count: u64,
+
+    #[serde(default)]
+    pub errors: Vec<RowError>,
+
+    /// Slugs of genres the import added to the taxonomy.
+    #[serde(default)]
+    pub genres_created: Vec<String>,
+
+    /// Why the job stopped, when `status` is `failed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+
+    /// Email of the admin who started the import.
+    pub actor: String,
+
+    pub created_at: DateTime,
+
+    pub updated_at: DateTime,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime>,
+}
@@ -1,5 +1,9 @@
+use crate::locale::translated;
+use crate::models::movie::MovieSummary;
+use crate::models::workflow::ContentStatus;
 use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct List {
@@ -8,17 +12,161 @@ pub struct List {
 
     pub title: String,
 
+    /// Per-locale overrides of the title, e.g. `{"pt-BR": {"title": "Em alta"}}`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, ListTranslation>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub type_list: Option<String>,
 
+    /// Genre slug; lists created before the taxonomy may still hold free text.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub genre: Option<String>,
 
+    /// Movie ids in display order. For smart lists this is the last resolved
+    /// result of `rule`: set when the rule is saved, then refreshed in the
+    /// background once `resolved_at` goes stale.
+    #[serde(default)]
     pub content: Vec<String>,
 
+    /// Query definition for a smart list; `None` for a static, curated list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<ListRule>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<DateTime>,
+
+    /// Set on rows computed from viewing activity (Top 10, Trending); their
+    /// `content` is rewritten by the chart job and cannot be edited by hand.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chart: Option<Chart>,
+
+    /// Publishing window; unset bounds are open.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible_from: Option<DateTime>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible_until: Option<DateTime>,
+
+    /// Editorial status; unset on lists created before the workflow, which count as published.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ContentStatus>,
+
+    /// Optimistic-concurrency counter, bumped on every update.
+    /// Writers must send the version they read; a mismatch means someone else got there first.
+    #[serde(default)]
+    pub version: i64,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<DateTime>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<DateTime>,
-}
\ No newline at end of file
+
+    /// Set when moved to the trash; purged once older than the retention period.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime>,
+
+    /// Email of the admin who moved it to the trash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ListTranslation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl List {
+    /// Replaces the title with its translation for `locales` and drops the
+    /// translations from the list, ready to be served.
+    pub fn localize(&mut self, locales: &[String]) {
+        if let Some(title) = translated(&self.translations, locales, |t| &t.title) {
+            self.title = title.to_string();
+        }
+        self.translations.clear();
+    }
+}
+
+/// How a smart list orders its resolved titles.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleSort {
+    #[default]
+    Newest,
+    Oldest,
+    Title,
+    Year,
+}
+
+/// The query a smart list fills itself from, e.g. "Action series after 2015".
+/// Every criterion is optional; unset ones do not restrict the result.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ListRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year_from: Option<u16>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year_to: Option<u16>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_series: Option<bool>,
+
+    /// Only titles added within this many days.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub added_since_days: Option<u32>,
+
+    #[serde(default)]
+    pub sort: RuleSort,
+
+    /// Maximum titles in the list (default 20, at most 100).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+/// Which activity chart a computed row shows.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartKind {
+    /// The ten most watched titles of the last day.
+    TopTen,
+    /// Titles whose views over the last day outpace their daily average over
+    /// the week before.
+    Trending,
+}
+
+impl ChartKind {
+    pub const ALL: [ChartKind; 2] = [ChartKind::TopTen, ChartKind::Trending];
+
+    /// The stored name, as in `chart.kind`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChartKind::TopTen => "top_ten",
+            ChartKind::Trending => "trending",
+        }
+    }
+}
+
+/// Identifies a computed row; the media type is the list's `type_list`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Chart {
+    pub kind: ChartKind,
+
+    /// Region the views were counted in; unset for the global chart.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+/// A list returned with `?expand=content`: the stored list plus the
+/// movie summaries its `content` ids point at, in list order.
+#[derive(Debug, Serialize)]
+pub struct ExpandedList {
+    #[serde(flatten)]
+    pub list: List,
+
+    pub items: Vec<MovieSummary>,
+}
@@ -1,5 +1,19 @@
 // Define the models for the authentication, lists, movies and users
 pub mod user;
+pub mod chart;
+pub mod featured;
+pub mod genre;
+pub mod import;
 pub mod list;
 pub mod movie;
+pub mod person;
+pub mod rating;
+pub mod recommendation;
+pub mod review;
+pub mod revision;
+pub mod stream;
+pub mod subtitle;
 pub mod users;
+pub mod view;
+pub mod watchlist;
+pub mod workflow;
@@ -1,11 +1,20 @@
-use mongodb::bson::{oid::ObjectId, DateTime};
+use crate::models::rating::RatingSummary;
+use crate::models::subtitle::{AudioTrack, SubtitleTrack};
+use crate::models::workflow::ContentStatus;
+use crate::locale::translated;
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Movie {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
 
+    /// The content partner's id for the title; bulk imports upsert on it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+
     pub title: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -20,6 +29,15 @@ pub struct Movie {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub img_sm: Option<String>,
 
+    /// Per-locale overrides of `title`, `desc` and `img_title`, keyed by tag
+    /// such as `pt-BR`. Managed through `/admin/translations`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, MovieTranslation>,
+
+    /// BlurHash placeholders for uploaded images, keyed by image field (`img`, `img_sm`...).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub blurhash: BTreeMap<String, String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub trailer: Option<String>,
 
@@ -32,14 +50,246 @@ pub struct Movie {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<String>,
 
+    /// Legacy free-text genre; `genres` holds the taxonomy references.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub genre: Option<String>,
 
+    /// Slugs of the title's genres in the `genres` collection.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub genres: Vec<String>,
+
     pub is_series: bool,
 
+    /// Editorial weight for the featured billboard; 0 or unset means no boost.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub featured_boost: Option<f64>,
+
+    /// Thumbs counts and match percentage, maintained as users rate the title.
+    #[serde(default, skip_serializing_if = "RatingSummary::is_empty")]
+    pub ratings: RatingSummary,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtitles: Vec<SubtitleTrack>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audio_tracks: Vec<AudioTrack>,
+
+    /// Start of the licence window; unset means available immediately.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_from: Option<DateTime>,
+
+    /// End of the licence window; unset means no expiry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub available_until: Option<DateTime>,
+
+    /// ISO 3166-1 alpha-2 codes the title is licensed in; empty means everywhere.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_regions: Vec<String>,
+
+    /// Regions the title must never be shown in, even if otherwise allowed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_regions: Vec<String>,
+
+    /// Editorial status; unset on titles created before the workflow, which count as published.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<ContentStatus>,
+
+    /// Number of the latest recorded revision; see `GET /movies/{id}/revisions`.
+    #[serde(default)]
+    pub revision: i64,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<DateTime>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<DateTime>,
-}
\ No newline at end of file
+
+    /// Set when moved to the trash; purged once older than the retention period.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime>,
+
+    /// Email of the admin who moved it to the trash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MovieTranslation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub img_title: Option<String>,
+}
+
+impl Movie {
+    /// Replaces title, description and image title with their translations
+    /// for `locales`, field by field, and drops the translations from the
+    /// movie, ready to be served.
+    pub fn localize(&mut self, locales: &[String]) {
+        if let Some(title) = translated(&self.translations, locales, |t| &t.title) {
+            self.title = title.to_string();
+        }
+        if let Some(desc) = translated(&self.translations, locales, |t| &t.desc) {
+            self.desc = Some(desc.to_string());
+        }
+        if let Some(img_title) = translated(&self.translations, locales, |t| &t.img_title) {
+            self.img_title = Some(img_title.to_string());
+        }
+        self.translations.clear();
+    }
+}
+
+/// Fields an admin edits directly through `PUT /movies/{id}`; also what
+/// restoring a revision may roll back, along with translations. Subtitles, audio tracks, status and
+/// trash state have their own endpoints.
+pub const METADATA_FIELDS: &[&str] = &[
+    "title",
+    "desc",
+    "img",
+    "img_title",
+    "img_sm",
+    "blurhash",
+    "trailer",
+    "video",
+    "year",
+    "limit",
+    "genre",
+    "genres",
+    "is_series",
+    "featured_boost",
+    "available_from",
+    "available_until",
+    "allowed_regions",
+    "blocked_regions",
+];
+
+/// The card-sized view of a movie embedded in expanded lists.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MovieSummary {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub title: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub img: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub img_sm: Option<String>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub blurhash: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+
+    #[serde(default)]
+    pub is_series: bool,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, MovieTranslation>,
+}
+
+impl MovieSummary {
+    /// The fields a summary is read with.
+    pub fn projection() -> Document {
+        doc! {
+            "title": 1, "img": 1, "img_sm": 1, "blurhash": 1, "year": 1,
+            "limit": 1, "genre": 1, "is_series": 1, "translations": 1,
+        }
+    }
+
+    /// Replaces the title with its translation for `locales` and drops the
+    /// translations from the summary.
+    pub fn localize(&mut self, locales: &[String]) {
+        if let Some(title) = translated(&self.translations, locales, |t| &t.title) {
+            self.title = title.to_string();
+        }
+        self.translations.clear();
+    }
+}
+
+/// Minimum viewer age for a maturity rating: the MPA (`PG-13`, `R`) and US TV
+/// (`TV-Y7`, `TV-MA`) systems, the BBFC's `U`/`12A`, the Brazilian `L` and
+/// plain ages such as `16` or `16+`. `None` for anything else, including
+/// `NR`, which callers must treat as adults-only.
+pub fn maturity_age(limit: &str) -> Option<u32> {
+    let rating = limit.trim().to_ascii_uppercase();
+    let age = match rating.as_str() {
+        "G" | "U" | "L" | "TV-Y" | "TV-G" | "ALL" => 0,
+        "TV-Y7" | "TV-Y7-FV" | "PG" | "TV-PG" => 7,
+        "12A" => 12,
+        "PG-13" => 13,
+        "TV-14" => 14,
+        "R" | "TV-MA" => 17,
+        "NC-17" | "X" | "R18" => 18,
+        _ => {
+            let digits = rating.strip_suffix('+').unwrap_or(&rating);
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            digits.parse().ok()?
+        }
+    };
+    Some(age)
+}
+
+/// Whether a title rated `limit` may be shown under a `?max_age=` cap.
+/// Unrated titles and unknown ratings only pass when there is no cap.
+pub fn within_age(limit: Option<&str>, max_age: Option<u32>) -> bool {
+    match max_age {
+        Some(max) => limit.and_then(maturity_age).is_some_and(|age| age <= max),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_rating_systems_to_ages() {
+        assert_eq!(maturity_age("G"), Some(0));
+        assert_eq!(maturity_age("tv-y"), Some(0));
+        assert_eq!(maturity_age("TV-Y7-FV"), Some(7));
+        assert_eq!(maturity_age("PG"), Some(7));
+        assert_eq!(maturity_age("PG-13"), Some(13));
+        assert_eq!(maturity_age("TV-14"), Some(14));
+        assert_eq!(maturity_age("R"), Some(17));
+        assert_eq!(maturity_age("TV-MA"), Some(17));
+        assert_eq!(maturity_age("NC-17"), Some(18));
+        assert_eq!(maturity_age("X"), Some(18));
+        assert_eq!(maturity_age("12A"), Some(12));
+        assert_eq!(maturity_age(" 16+ "), Some(16));
+        assert_eq!(maturity_age("18"), Some(18));
+    }
+
+    #[test]
+    fn unknown_ratings_have_no_age() {
+        assert_eq!(maturity_age("NR"), None);
+        assert_eq!(maturity_age(""), None);
+        assert_eq!(maturity_age("+"), None);
+        assert_eq!(maturity_age("M/16"), None);
+    }
+
+    #[test]
+    fn caps_restrict_unknown_and_unrated_titles() {
+        assert!(within_age(Some("PG"), Some(7)));
+        assert!(!within_age(Some("R"), Some(13)));
+        assert!(!within_age(Some("TV-MA"), Some(16)));
+        assert!(!within_age(Some("NR"), Some(18)));
+        assert!(!within_age(None, Some(18)));
+        assert!(within_age(None, None));
+        assert!(within_age(Some("X"), None));
+    }
+}
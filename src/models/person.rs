@@ -0,0 +1,108 @@
+use crate::models::movie::MovieSummary;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// Someone credited on titles: cast or crew.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Person {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub photo: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CreditRole {
+    Actor,
+    Director,
+    Writer,
+}
+
+/// Links a person to a title, or to one episode of a series.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Credit {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub person_id: String,
+
+    pub movie_id: String,
+
+    /// Episode key such as `s01e02`; unset for credits on the whole title.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<String>,
+
+    pub role: CreditRole,
+
+    /// The part played, for actors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character: Option<String>,
+
+    /// Billing order within the role, starting at 1.
+    pub order: u32,
+
+    /// Shared by the credits written by one replace; the newest set of a
+    /// title or episode is the current one. Unset on credits written before
+    /// sets existed, which count as older than any set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_id: Option<ObjectId>,
+
+    pub created_at: DateTime,
+}
+
+/// A credit as embedded in the movie detail, with the person inlined.
+#[derive(Debug, Serialize, Clone)]
+pub struct CastMember {
+    pub person_id: String,
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub photo: Option<String>,
+
+    pub role: CreditRole,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<String>,
+
+    pub order: u32,
+}
+
+/// One credit in a person's filmography.
+#[derive(Debug, Serialize, Clone)]
+pub struct FilmographyEntry {
+    pub movie: MovieSummary,
+    pub role: CreditRole,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<String>,
+
+    pub order: u32,
+}
+
+/// `GET /people/{id}`: the person and the titles they are credited on.
+#[derive(Debug, Serialize)]
+pub struct PersonDetail {
+    #[serde(flatten)]
+    pub person: Person,
+
+    pub filmography: Vec<FilmographyEntry>,
+}
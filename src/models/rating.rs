@@ -0,0 +1,62 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Thumb {
+    Down,
+    Up,
+    Love,
+}
+
+impl Thumb {
+    /// The counter in `Movie.ratings` this thumb is tallied under.
+    pub fn counter(self) -> &'static str {
+        match self {
+            Thumb::Down => "down",
+            Thumb::Up => "up",
+            Thumb::Love => "love",
+        }
+    }
+}
+
+/// One user's feedback on a title. A unique index on `(user, movie_id)`
+/// keeps a single, replaceable rating per user and title.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Rating {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    /// Email of the rating account (the token `sub`).
+    pub user: String,
+
+    pub movie_id: String,
+
+    pub thumb: Thumb,
+
+    pub rated_at: DateTime,
+}
+
+/// Aggregate feedback stored on the movie and adjusted on every rating
+/// change, so reads never count ratings.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RatingSummary {
+    #[serde(default)]
+    pub up: i64,
+
+    #[serde(default)]
+    pub down: i64,
+
+    #[serde(default)]
+    pub love: i64,
+
+    /// Share of ratings that are up or love, 0–100; unset until rated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_percent: Option<i32>,
+}
+
+impl RatingSummary {
+    pub fn is_empty(&self) -> bool {
+        self.up == 0 && self.down == 0 && self.love == 0
+    }
+}
@@ -0,0 +1,29 @@
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+/// A title often watched or liked by the same people, with their cosine
+/// similarity (0 to 1).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Neighbor {
+    pub movie_id: String,
+
+    pub score: f64,
+}
+
+/// One title's row of the item-item recommendation model. The whole model is
+/// rebuilt periodically from views and ratings; rows from an earlier build
+/// are deleted once the new one is written.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ItemModel {
+    /// Hex id of the movie.
+    #[serde(rename = "_id")]
+    pub movie_id: String,
+
+    /// Weighted interactions over the recent window; ranks the cold-start fallback.
+    pub popularity: f64,
+
+    /// Most similar titles, best first.
+    pub neighbors: Vec<Neighbor>,
+
+    pub built_at: DateTime,
+}
@@ -0,0 +1,86 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// Moderation state of a review. Only approved reviews are listed publicly.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A written review of a title. A unique index on `(movie_id, user)` keeps
+/// one review per user and title; later changes are edits.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Review {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub movie_id: String,
+
+    /// Email of the author (the token `sub`); left out of public listings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+
+    /// Display name shown with the review.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+
+    pub text: String,
+
+    /// Stars, 1 to 5.
+    pub rating: u8,
+
+    pub status: ReviewStatus,
+
+    /// Why the content filter held the review for moderation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,
+
+    /// Number of users who marked the review helpful.
+    #[serde(default)]
+    pub helpful_count: i64,
+
+    /// Email of the admin who last approved or rejected it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderated_by: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderation_note: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderated_at: Option<DateTime>,
+
+    pub created_at: DateTime,
+
+    pub updated_at: DateTime,
+}
+
+/// One user's "helpful" mark on a review; unique per `(review_id, user)`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReviewVote {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub review_id: ObjectId,
+
+    pub user: String,
+
+    pub at: DateTime,
+}
+
+/// One review written or edited, kept for the per-user rate limit. Attempts
+/// outlive the review itself, so deleting and rewriting does not reset the
+/// count; a TTL index on `expires_at` drops them once out of the window.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReviewAttempt {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+
+    pub user: String,
+
+    pub at: DateTime,
+
+    pub expires_at: DateTime,
+}
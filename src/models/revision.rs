@@ -0,0 +1,34 @@
+use mongodb::bson::{oid::ObjectId, Bson, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// One top-level field that changed in a write. A missing side means the
+/// field was absent (or null) on that side.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FieldChange {
+    pub field: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<Bson>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<Bson>,
+}
+
+/// A recorded write to a movie: what changed, who changed it and when.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MovieRevision {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub movie_id: String,
+
+    /// Matches the movie's `revision` counter right after the write.
+    pub rev: i64,
+
+    pub changes: Vec<FieldChange>,
+
+    /// Email of the admin who made the write.
+    pub actor: String,
+
+    pub at: DateTime,
+}
@@ -0,0 +1,42 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// One active playback session. A lease is created when streaming starts and
+/// kept alive by heartbeats; leases whose heartbeat goes stale are expired by
+/// a TTL index on `heartbeat_at`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamLease {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    /// Email of the account the stream counts against (the token `sub`).
+    pub user: String,
+
+    pub movie_id: String,
+
+    pub device: String,
+
+    /// Which of the plan's stream slots the lease holds, from 0. A unique
+    /// index on `(user, slot)` keeps parallel starts from exceeding the cap.
+    /// Leases from before slots existed have none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u32>,
+
+    /// Last reported playback position, updated by heartbeats.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_secs: Option<u64>,
+
+    pub started_at: DateTime,
+
+    pub heartbeat_at: DateTime,
+}
+
+/// Maximum simultaneous streams allowed for a subscription plan.
+/// Accounts without a plan are treated as `basic`.
+pub fn stream_cap(plan: Option<&str>) -> u64 {
+    match plan {
+        Some("premium") => 4,
+        Some("standard") => 2,
+        _ => 1,
+    }
+}
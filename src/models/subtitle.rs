@@ -0,0 +1,67 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// A stored WebVTT subtitle document for one movie (or episode) and language.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Subtitle {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub movie_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<String>,
+
+    pub lang: String,
+
+    /// Always WebVTT; SRT uploads are converted before storage.
+    pub vtt: String,
+
+    pub cue_count: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime>,
+
+    /// Email of the admin who uploaded it; cleared when their account is purged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uploaded_by: Option<String>,
+}
+
+/// Subtitle track metadata embedded on the movie detail.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubtitleTrack {
+    pub lang: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<String>,
+
+    pub url: String,
+}
+
+/// Audio track metadata embedded on the movie detail, keyed the same way as subtitles.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AudioTrack {
+    pub lang: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels: Option<u8>,
+
+    #[serde(default)]
+    pub is_default: bool,
+
+    /// Rendition URI for the track, when it is packaged separately.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
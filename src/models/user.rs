@@ -17,4 +17,16 @@ pub struct User {
 
     #[serde(default)] 
     pub is_admin: bool,
+
+    /// Subscription plan (`basic`, `standard`, `premium`); `None` means basic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<String>,
+
+    /// Editorial role of an admin (`editor` or `publisher`); `None` means editor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+
+    /// Preferred locale for catalog text, e.g. `pt-BR`; `Accept-Language` decides when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
 }
\ No newline at end of file
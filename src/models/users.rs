@@ -19,11 +19,26 @@ pub struct Users {
     #[serde(default)]
     pub is_admin: bool,
 
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<BsonDateTime>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<BsonDateTime>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<BsonDateTime>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<String>,
 }
 
 /// Converts JWT claims (HashMap<String, String>) back into a `Users` instance.
@@ -39,8 +54,13 @@ impl From<HashMap<String, String>> for Users {
                 .get("is_admin")
                 .and_then(|s| s.parse::<bool>().ok())
                 .unwrap_or(false),
+            plan: data.get("plan").cloned(),
+            role: data.get("role").cloned(),
+            language: data.get("language").cloned(),
             created_at: None, 
             updated_at: None, 
+            deleted_at: None,
+            deleted_by: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,38 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// A user's viewing history for one title, recorded when playback starts.
+/// A unique index on `(user, movie_id)` keeps one document per pair; watching
+/// again bumps `plays`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct View {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    /// Email of the watching account (the token `sub`).
+    pub user: String,
+
+    pub movie_id: String,
+
+    pub plays: i64,
+
+    pub first_watched_at: DateTime,
+
+    pub last_watched_at: DateTime,
+}
+
+/// One playback start, kept for a week to compute the activity charts.
+/// Unlike `View` it is not tied to a user.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ViewEvent {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    pub movie_id: String,
+
+    /// Region the stream was started from, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+
+    pub at: DateTime,
+}
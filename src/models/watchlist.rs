@@ -0,0 +1,28 @@
+use crate::models::movie::MovieSummary;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// One title on a user's personal "My List". A unique index on
+/// `(user, movie_id)` keeps each title on a list at most once.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WatchlistEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    /// Email of the owning account (the token `sub`).
+    pub user: String,
+
+    pub movie_id: String,
+
+    pub added_at: DateTime,
+}
+
+/// A watchlist entry joined with its movie, as returned by `GET /me/list`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchlistItem {
+    pub movie_id: String,
+
+    pub added_at: DateTime,
+
+    pub movie: MovieSummary,
+}
@@ -0,0 +1,166 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Editorial lifecycle of catalog entries (movies and lists).
+/// Documents created before the workflow existed have no status and count as published.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentStatus {
+    Draft,
+    InReview,
+    Published,
+    Archived,
+}
+
+impl fmt::Display for ContentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ContentStatus::Draft => "draft",
+            ContentStatus::InReview => "in_review",
+            ContentStatus::Published => "published",
+            ContentStatus::Archived => "archived",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Editorial role of an admin, read from the account on every request.
+/// Admins without a role are editors until a publisher promotes them; admins
+/// from before the workflow were made publishers at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorialRole {
+    Editor,
+    Publisher,
+}
+
+impl EditorialRole {
+    pub fn from_claim(role: Option<&str>) -> Self {
+        match role {
+            Some("publisher") => EditorialRole::Publisher,
+            _ => EditorialRole::Editor,
+        }
+    }
+
+    /// Parses a role as stored on the account.
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "editor" => Some(EditorialRole::Editor),
+            "publisher" => Some(EditorialRole::Publisher),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EditorialRole::Editor => "editor",
+            EditorialRole::Publisher => "publisher",
+        }
+    }
+}
+
+/// Whether `role` may move an entry from `from` to `to`.
+///
+/// Editors draft and submit for review; publishers additionally approve,
+/// reject, publish, unpublish and archive.
+pub fn can_transition(from: ContentStatus, to: ContentStatus, role: EditorialRole) -> bool {
+    use ContentStatus::*;
+    match (from, to) {
+        (Draft, InReview) | (Archived, Draft) => true,
+        (InReview, Draft) | (InReview, Published) | (Published, Draft) | (Published, Archived) => {
+            role == EditorialRole::Publisher
+        }
+        _ => false,
+    }
+}
+
+/// Audit record of one status transition.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StatusChange {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+
+    /// `movie` or `list`.
+    pub entity: String,
+
+    pub entity_id: String,
+
+    pub from: ContentStatus,
+
+    pub to: ContentStatus,
+
+    /// Email of the admin who made the change.
+    pub actor: String,
+
+    pub at: DateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ContentStatus::*;
+
+    const ALL: [ContentStatus; 4] = [Draft, InReview, Published, Archived];
+
+    #[test]
+    fn editors_draft_and_submit_only() {
+        let allowed: Vec<_> = ALL
+            .iter()
+            .flat_map(|&from| ALL.iter().map(move |&to| (from, to)))
+            .filter(|&(from, to)| can_transition(from, to, EditorialRole::Editor))
+            .collect();
+        assert_eq!(allowed, vec![(Draft, InReview), (Archived, Draft)]);
+    }
+
+    #[test]
+    fn publishers_run_the_whole_workflow() {
+        let allowed: Vec<_> = ALL
+            .iter()
+            .flat_map(|&from| ALL.iter().map(move |&to| (from, to)))
+            .filter(|&(from, to)| can_transition(from, to, EditorialRole::Publisher))
+            .collect();
+        assert_eq!(
+            allowed,
+            vec![
+                (Draft, InReview),
+                (InReview, Draft),
+                (InReview, Published),
+                (Published, Draft),
+                (Published, Archived),
+                (Archived, Draft),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_status_moves_to_itself() {
+        for status in ALL {
+            assert!(!can_transition(status, status, EditorialRole::Publisher));
+        }
+    }
+
+    #[test]
+    fn role_claim_defaults_to_editor() {
+        assert_eq!(
+            EditorialRole::from_claim(Some("editor")),
+            EditorialRole::Editor
+        );
+        assert_eq!(
+            EditorialRole::from_claim(Some("publisher")),
+            EditorialRole::Publisher
+        );
+        assert_eq!(EditorialRole::from_claim(None), EditorialRole::Editor);
+        assert_eq!(
+            EditorialRole::from_claim(Some("owner")),
+            EditorialRole::Editor
+        );
+    }
+
+    #[test]
+    fn role_names_parse_back() {
+        for role in [EditorialRole::Editor, EditorialRole::Publisher] {
+            assert_eq!(EditorialRole::parse(role.name()), Some(role));
+        }
+        assert_eq!(EditorialRole::parse("Publisher"), None);
+    }
+}
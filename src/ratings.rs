@@ -0,0 +1,240 @@
+use crate::models::rating::{RatingSummary, Thumb};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, to_bson, Bson, Document};
+use mongodb::Collection;
+use std::collections::HashMap;
+use std::env;
+use std::time::Duration;
+
+// ── Incremental aggregates ────────────────────────────────────────────────────
+//
+// `Movie.ratings` holds per-thumb counts and the derived match percentage.
+// Each rating change is applied as a delta in a single pipeline update, so
+// concurrent raters never lose each other's counts. The rating and the delta
+// are two writes; if the second fails, the periodic recount below repairs
+// the movie from the ratings collection.
+
+/// Per-thumb changes to apply to a movie's counts.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RatingDelta {
+    pub up: i64,
+    pub down: i64,
+    pub love: i64,
+}
+
+impl RatingDelta {
+    /// The delta for one user's rating going from `from` to `to`
+    /// (`None` meaning unrated).
+    pub fn change(from: Option<Thumb>, to: Option<Thumb>) -> Self {
+        let mut delta = RatingDelta::default();
+        if from == to {
+            return delta;
+        }
+        if let Some(thumb) = from {
+            *delta.counter(thumb) -= 1;
+        }
+        if let Some(thumb) = to {
+            *delta.counter(thumb) += 1;
+        }
+        delta
+    }
+
+    pub fn counter(&mut self, thumb: Thumb) -> &mut i64 {
+        match thumb {
+            Thumb::Up => &mut self.up,
+            Thumb::Down => &mut self.down,
+            Thumb::Love => &mut self.love,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.up == 0 && self.down == 0 && self.love == 0
+    }
+}
+
+/// Adds `delta` to the movie's counts and recomputes `match_percent`,
+/// the share of up and love among all ratings.
+pub async fn apply_rating_delta(
+    movies: &Collection<Document>,
+    movie_id: ObjectId,
+    delta: RatingDelta,
+) -> mongodb::error::Result<()> {
+    if delta.is_zero() {
+        return Ok(());
+    }
+
+    let bump = |counter: &str, by: i64| {
+        let path = format!("$ratings.{}", counter);
+        doc! { "$max": [0_i64, { "$add": [{ "$ifNull": [path, 0_i64] }, by] }] }
+    };
+    let pipeline = vec![
+        doc! { "$set": {
+            "ratings.up": bump("up", delta.up),
+            "ratings.down": bump("down", delta.down),
+            "ratings.love": bump("love", delta.love),
+        } },
+        doc! { "$set": {
+            "ratings.match_percent": { "$let": {
+                "vars": {
+                    "liked": { "$add": ["$ratings.up", "$ratings.love"] },
+                    "total": { "$add": ["$ratings.up", "$ratings.love", "$ratings.down"] },
+                },
+                "in": { "$cond": [
+                    { "$gt": ["$$total", 0] },
+                    { "$toInt": { "$round": [
+                        { "$multiply": [100, { "$divide": ["$$liked", "$$total"] }] },
+                        0,
+                    ] } },
+                    "$$REMOVE",
+                ] },
+            } },
+        } },
+    ];
+
+    movies
+        .update_one(doc! { "_id": movie_id }, pipeline)
+        .await
+        .map(|_| ())
+}
+
+// ── Recount ───────────────────────────────────────────────────────────────────
+
+/// How often every movie's counts are checked against the ratings collection
+/// (`RATING_RECOUNT_SECS`, default 3600).
+fn rating_recount_secs() -> u64 {
+    env::var("RATING_RECOUNT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600)
+}
+
+/// The aggregate for the given per-thumb counts. `match_percent` rounds half
+/// to even, like the `$round` in `apply_rating_delta`.
+pub fn summarize(up: i64, down: i64, love: i64) -> RatingSummary {
+    let total = up + down + love;
+    let match_percent =
+        (total > 0).then(|| (100.0 * ((up + love) as f64 / total as f64)).round_ties_even() as i32);
+    RatingSummary {
+        up,
+        down,
+        love,
+        match_percent,
+    }
+}
+
+/// Rewrites every movie's counts whose stored aggregate differs from a fresh
+/// count of its ratings; returns how many were repaired.
+///
+/// Each rewrite only applies if the stored aggregate is still the one that was
+/// read, so a delta landing in between is never overwritten; a movie whose
+/// count is still off is picked up by the next run.
+pub async fn recount_ratings(
+    movies: &Collection<Document>,
+    ratings: &Collection<Document>,
+) -> mongodb::error::Result<u64> {
+    let tallies: Vec<Document> = ratings
+        .aggregate(vec![doc! { "$group": {
+            "_id": { "movie_id": "$movie_id", "thumb": "$thumb" },
+            "count": { "$sum": 1_i64 },
+        } }])
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut counts: HashMap<String, (i64, i64, i64)> = HashMap::new();
+    for tally in &tallies {
+        let Ok(key) = tally.get_document("_id") else {
+            continue;
+        };
+        let (Ok(movie_id), Ok(thumb)) = (key.get_str("movie_id"), key.get_str("thumb")) else {
+            continue;
+        };
+        let count = tally.get_i64("count").unwrap_or(0);
+        let entry = counts.entry(movie_id.to_string()).or_default();
+        match thumb {
+            "up" => entry.0 += count,
+            "down" => entry.1 += count,
+            "love" => entry.2 += count,
+            _ => {}
+        }
+    }
+
+    let mut stored = movies
+        .find(doc! {})
+        .projection(doc! { "ratings": 1 })
+        .await?;
+    let mut repaired = 0;
+    while let Some(movie) = stored.try_next().await? {
+        let Ok(oid) = movie.get_object_id("_id") else {
+            continue;
+        };
+        let (up, down, love) = counts.get(&oid.to_hex()).copied().unwrap_or_default();
+        let expected = summarize(up, down, love);
+        let current = movie.get("ratings").cloned();
+        let current_summary = current
+            .clone()
+            .and_then(|ratings| mongodb::bson::from_bson::<RatingSummary>(ratings).ok())
+            .unwrap_or_default();
+        if current_summary == expected {
+            continue;
+        }
+
+        let unchanged = match current {
+            Some(ratings) => doc! { "_id": oid, "ratings": ratings },
+            None => doc! { "_id": oid, "ratings": { "$exists": false } },
+        };
+        let expected = to_bson(&expected).unwrap_or(Bson::Null);
+        let result = movies
+            .update_one(unchanged, doc! { "$set": { "ratings": expected } })
+            .await?;
+        repaired += result.modified_count;
+    }
+    Ok(repaired)
+}
+
+/// Spawns a background task that periodically repairs movies' rating counts.
+pub fn spawn_rating_recounter(movies: Collection<Document>, ratings: Collection<Document>) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(rating_recount_secs()));
+        loop {
+            interval.tick().await;
+            match recount_ratings(&movies, &ratings).await {
+                Ok(0) => {}
+                Ok(repaired) => log::info!("Repaired rating counts of {} movies", repaired),
+                Err(e) => log::warn!("Failed to recount ratings: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Thumb::*;
+
+    #[test]
+    fn deltas_follow_one_users_rating() {
+        let delta = RatingDelta::change(None, Some(Up));
+        assert_eq!((delta.up, delta.down, delta.love), (1, 0, 0));
+
+        let delta = RatingDelta::change(Some(Up), Some(Love));
+        assert_eq!((delta.up, delta.down, delta.love), (-1, 0, 1));
+
+        let delta = RatingDelta::change(Some(Down), None);
+        assert_eq!((delta.up, delta.down, delta.love), (0, -1, 0));
+
+        assert!(RatingDelta::change(Some(Love), Some(Love)).is_zero());
+        assert!(RatingDelta::change(None, None).is_zero());
+    }
+
+    #[test]
+    fn summary_counts_up_and_love_as_a_match() {
+        assert_eq!(summarize(0, 0, 0), RatingSummary::default());
+        assert_eq!(summarize(1, 1, 2).match_percent, Some(75));
+        assert_eq!(summarize(0, 3, 0).match_percent, Some(0));
+        assert_eq!(summarize(2, 1, 0).match_percent, Some(67));
+        // 12.5 and 87.5 round to even, as MongoDB's `$round` does.
+        assert_eq!(summarize(1, 7, 0).match_percent, Some(12));
+        assert_eq!(summarize(7, 1, 0).match_percent, Some(88));
+    }
+}
@@ -0,0 +1,591 @@
+use crate::models::rating::{Rating, Thumb};
+use crate::models::recommendation::{ItemModel, Neighbor};
+use crate::models::view::View;
+use actix_web::web;
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, DateTime};
+use mongodb::{Collection, Cursor};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::time::Duration;
+
+// ── Interactions ──────────────────────────────────────────────────────────────
+
+/// How much each kind of interaction says about a user's taste.
+const VIEW_WEIGHT: f64 = 1.0;
+const UP_WEIGHT: f64 = 2.0;
+const LOVE_WEIGHT: f64 = 3.0;
+
+/// What one user did with one title.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Interaction {
+    pub viewed: bool,
+    pub thumb: Option<Thumb>,
+    /// Time of the latest view or rating, in milliseconds.
+    pub at: i64,
+}
+
+impl Interaction {
+    /// Strength of the signal, or `None` for a thumbs down: disliked titles
+    /// neither seed recommendations nor count towards similarity.
+    pub fn weight(&self) -> Option<f64> {
+        match self.thumb {
+            Some(Thumb::Down) => None,
+            Some(Thumb::Love) => Some(LOVE_WEIGHT),
+            Some(Thumb::Up) => Some(UP_WEIGHT),
+            None => self.viewed.then_some(VIEW_WEIGHT),
+        }
+    }
+}
+
+/// One user's interactions, keyed by movie id.
+pub type History = HashMap<String, Interaction>;
+
+fn record_view(history: &mut History, view: View) {
+    let interaction = history.entry(view.movie_id).or_default();
+    interaction.viewed = true;
+    interaction.at = interaction.at.max(view.last_watched_at.timestamp_millis());
+}
+
+fn record_rating(history: &mut History, rating: Rating) {
+    let interaction = history.entry(rating.movie_id).or_default();
+    interaction.thumb = Some(rating.thumb);
+    interaction.at = interaction.at.max(rating.rated_at.timestamp_millis());
+}
+
+/// The user's views and ratings merged per title.
+pub async fn user_history(
+    views: &Collection<View>,
+    ratings: &Collection<Rating>,
+    user: &str,
+) -> mongodb::error::Result<History> {
+    let mut history = History::new();
+    let mut cursor = views.find(doc! { "user": user }).await?;
+    while let Some(view) = cursor.try_next().await? {
+        record_view(&mut history, view);
+    }
+    let mut cursor = ratings.find(doc! { "user": user }).await?;
+    while let Some(rating) = cursor.try_next().await? {
+        record_rating(&mut history, rating);
+    }
+    Ok(history)
+}
+
+/// Every user's history, one user at a time: views and ratings are both read
+/// in `user` order and merged, so only one history is held in memory.
+struct HistoryStream {
+    views: Cursor<View>,
+    ratings: Cursor<Rating>,
+    next_view: Option<View>,
+    next_rating: Option<Rating>,
+}
+
+impl HistoryStream {
+    async fn open(
+        views: &Collection<View>,
+        ratings: &Collection<Rating>,
+    ) -> mongodb::error::Result<Self> {
+        let mut views = views.find(doc! {}).sort(doc! { "user": 1 }).await?;
+        let mut ratings = ratings.find(doc! {}).sort(doc! { "user": 1 }).await?;
+        Ok(HistoryStream {
+            next_view: views.try_next().await?,
+            next_rating: ratings.try_next().await?,
+            views,
+            ratings,
+        })
+    }
+
+    /// The next user's history, or `None` once every user has been read.
+    async fn next(&mut self) -> mongodb::error::Result<Option<History>> {
+        let user = match (&self.next_view, &self.next_rating) {
+            (Some(view), Some(rating)) => view.user.clone().min(rating.user.clone()),
+            (Some(view), None) => view.user.clone(),
+            (None, Some(rating)) => rating.user.clone(),
+            (None, None) => return Ok(None),
+        };
+
+        let mut history = History::new();
+        while let Some(view) = self.next_view.take_if(|view| view.user == user) {
+            record_view(&mut history, view);
+            self.next_view = self.views.try_next().await?;
+        }
+        while let Some(rating) = self.next_rating.take_if(|rating| rating.user == user) {
+            record_rating(&mut history, rating);
+            self.next_rating = self.ratings.try_next().await?;
+        }
+        Ok(Some(history))
+    }
+}
+
+// ── Personalization ───────────────────────────────────────────────────────────
+
+/// A title recommended from the user's history.
+pub struct Candidate {
+    pub movie_id: String,
+    pub score: f64,
+    /// The history title that contributed most to the score.
+    pub because: String,
+}
+
+/// Scores every neighbor of the seed titles in `rows` by similarity times
+/// the seed's weight in `history`, best first. Titles already in the history,
+/// disliked ones included, are never recommended.
+pub fn personalize(history: &History, rows: &[ItemModel]) -> Vec<Candidate> {
+    let mut candidates: HashMap<&str, Candidate> = HashMap::new();
+    let mut best: HashMap<&str, f64> = HashMap::new();
+
+    for row in rows {
+        let Some(weight) = history.get(&row.movie_id).and_then(Interaction::weight) else {
+            continue;
+        };
+        for neighbor in &row.neighbors {
+            if history.contains_key(&neighbor.movie_id) {
+                continue;
+            }
+            let contribution = neighbor.score * weight;
+            let candidate = candidates
+                .entry(neighbor.movie_id.as_str())
+                .or_insert_with(|| Candidate {
+                    movie_id: neighbor.movie_id.clone(),
+                    score: 0.0,
+                    because: row.movie_id.clone(),
+                });
+            candidate.score += contribution;
+
+            let strongest = best.entry(neighbor.movie_id.as_str()).or_default();
+            if contribution > *strongest {
+                *strongest = contribution;
+                candidate.because = row.movie_id.clone();
+            }
+        }
+    }
+
+    let mut ranked: Vec<Candidate> = candidates.into_values().collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+}
+
+/// Share of the final score a popularity pick can reach; personal picks go
+/// first unless they are much weaker than their best.
+const POPULAR_WEIGHT: f64 = 0.5;
+
+/// Personal picks and popular titles merged into one ranking, best first;
+/// `None` marks a popularity pick. Both lists are scaled to 0–1 by their best
+/// score first, since neighbor sums and interaction counts are not comparable.
+pub fn blend(
+    personal: Vec<Candidate>,
+    popular: Vec<ItemModel>,
+) -> Vec<(String, f64, Option<String>)> {
+    let best_personal = personal.iter().map(|c| c.score).fold(0.0, f64::max);
+    let best_popular = popular.iter().map(|row| row.popularity).fold(0.0, f64::max);
+
+    let mut ranked: Vec<(String, f64, Option<String>)> = personal
+        .into_iter()
+        .filter(|candidate| best_personal > 0.0 && candidate.score > 0.0)
+        .map(|candidate| {
+            let score = candidate.score / best_personal;
+            (candidate.movie_id, score, Some(candidate.because))
+        })
+        .collect();
+    let mut listed: HashSet<String> = ranked.iter().map(|(id, _, _)| id.clone()).collect();
+    for row in popular {
+        if best_popular > 0.0 && listed.insert(row.movie_id.clone()) {
+            let score = POPULAR_WEIGHT * row.popularity / best_popular;
+            ranked.push((row.movie_id, score, None));
+        }
+    }
+
+    // Stable, so equal scores keep personal picks ahead.
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+// ── Model build ───────────────────────────────────────────────────────────────
+
+/// Neighbors kept per title.
+const MAX_NEIGHBORS: usize = 50;
+
+/// Most recent interactions per user that count towards similarity; bounds
+/// the pairwise work for heavy viewers.
+const MAX_ITEMS_PER_USER: usize = 200;
+
+/// Fewest users two titles must share before they count as similar, so one
+/// person's odd pairing does not become everyone's recommendation.
+const MIN_SUPPORT: u32 = 2;
+
+/// Days of interactions counted towards popularity.
+const POPULARITY_WINDOW_DAYS: i64 = 30;
+
+/// How often the model is rebuilt (`RECOMMENDATION_REBUILD_SECS`, default 3600).
+fn recommendation_rebuild_secs() -> u64 {
+    env::var("RECOMMENDATION_REBUILD_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600)
+}
+
+/// Most distinct co-occurring titles tracked per title; bounds the pair table
+/// for titles watched alongside most of the catalog. Pairs already tracked
+/// keep accumulating.
+const MAX_PAIRS_PER_ITEM: usize = 1000;
+
+/// Users folded into the model per blocking task.
+const USERS_PER_BATCH: usize = 1000;
+
+/// Rows per bulk write.
+const ROWS_PER_WRITE: usize = 1000;
+
+/// Item-item cosine similarity over users' weighted interactions, plus each
+/// title's popularity over the recent window, accumulated one history at a time.
+struct ModelBuilder {
+    built_at: DateTime,
+    since: i64,
+    index: HashMap<String, usize>,
+    ids: Vec<String>,
+    norms: Vec<f64>,
+    popularity: Vec<f64>,
+    /// Distinct partners tracked per title.
+    partners: Vec<usize>,
+    pairs: HashMap<(usize, usize), (f64, u32)>,
+}
+
+impl ModelBuilder {
+    fn new(built_at: DateTime) -> Self {
+        ModelBuilder {
+            built_at,
+            since: built_at.timestamp_millis() - POPULARITY_WINDOW_DAYS * 24 * 60 * 60 * 1000,
+            index: HashMap::new(),
+            ids: Vec::new(),
+            norms: Vec::new(),
+            popularity: Vec::new(),
+            partners: Vec::new(),
+            pairs: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, history: History) {
+        let mut items: Vec<(usize, f64, i64)> = Vec::new();
+        for (movie_id, interaction) in history {
+            let Some(weight) = interaction.weight() else {
+                continue;
+            };
+            let i = match self.index.get(&movie_id) {
+                Some(&i) => i,
+                None => {
+                    self.ids.push(movie_id.clone());
+                    self.norms.push(0.0);
+                    self.popularity.push(0.0);
+                    self.partners.push(0);
+                    self.index.insert(movie_id, self.ids.len() - 1);
+                    self.ids.len() - 1
+                }
+            };
+            if interaction.at >= self.since {
+                self.popularity[i] += weight;
+            }
+            items.push((i, weight, interaction.at));
+        }
+
+        items.sort_by_key(|&(_, _, at)| Reverse(at));
+        items.truncate(MAX_ITEMS_PER_USER);
+        for (n, &(i, wi, _)) in items.iter().enumerate() {
+            self.norms[i] += wi * wi;
+            for &(j, wj, _) in &items[n + 1..] {
+                let key = (i.min(j), i.max(j));
+                let pair = match self.pairs.get_mut(&key) {
+                    Some(pair) => pair,
+                    None if self.partners[i] < MAX_PAIRS_PER_ITEM
+                        && self.partners[j] < MAX_PAIRS_PER_ITEM =>
+                    {
+                        self.partners[i] += 1;
+                        self.partners[j] += 1;
+                        self.pairs.entry(key).or_default()
+                    }
+                    None => continue,
+                };
+                pair.0 += wi * wj;
+                pair.1 += 1;
+            }
+        }
+    }
+
+    fn finish(self) -> Vec<ItemModel> {
+        let mut neighbors: Vec<Vec<Neighbor>> = vec![Vec::new(); self.ids.len()];
+        for ((i, j), (dot, support)) in self.pairs {
+            if support < MIN_SUPPORT {
+                continue;
+            }
+            let score = dot / (self.norms[i].sqrt() * self.norms[j].sqrt());
+            neighbors[i].push(Neighbor {
+                movie_id: self.ids[j].clone(),
+                score,
+            });
+            neighbors[j].push(Neighbor {
+                movie_id: self.ids[i].clone(),
+                score,
+            });
+        }
+
+        let built_at = self.built_at;
+        self.ids
+            .into_iter()
+            .zip(self.popularity)
+            .zip(neighbors)
+            .map(|((movie_id, popularity), mut neighbors)| {
+                neighbors.sort_by(|a, b| b.score.total_cmp(&a.score));
+                neighbors.truncate(MAX_NEIGHBORS);
+                ItemModel {
+                    movie_id,
+                    popularity,
+                    neighbors,
+                    built_at,
+                }
+            })
+            .filter(|row| row.popularity > 0.0 || !row.neighbors.is_empty())
+            .collect()
+    }
+}
+
+/// Folds every user's history into a new model. Pair counting is CPU-bound,
+/// so each batch of users is added off the event loop.
+async fn build_model(
+    views: &Collection<View>,
+    ratings: &Collection<Rating>,
+    built_at: DateTime,
+) -> Result<Vec<ItemModel>, String> {
+    let mut stream = HistoryStream::open(views, ratings)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut builder = ModelBuilder::new(built_at);
+    let mut batch: Vec<History> = Vec::new();
+
+    loop {
+        let history = stream.next().await.map_err(|e| e.to_string())?;
+        let done = history.is_none();
+        batch.extend(history);
+        if batch.len() >= USERS_PER_BATCH || (done && !batch.is_empty()) {
+            let users = std::mem::take(&mut batch);
+            builder = web::block(move || {
+                users.into_iter().for_each(|history| builder.add(history));
+                builder
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        if done {
+            break;
+        }
+    }
+
+    web::block(move || builder.finish())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Upserts the new rows in bulk, then drops those left over from earlier
+/// builds. Readers may briefly see a mix of both, which only blends two good
+/// answers.
+async fn write_model(
+    model: &Collection<ItemModel>,
+    rows: Vec<ItemModel>,
+    built_at: DateTime,
+) -> mongodb::error::Result<()> {
+    for chunk in rows.chunks(ROWS_PER_WRITE) {
+        let mut writes = Vec::with_capacity(chunk.len());
+        for row in chunk {
+            let mut write = model.replace_one_model(doc! { "_id": &row.movie_id }, row)?;
+            write.upsert = Some(true);
+            writes.push(write);
+        }
+        model.client().bulk_write(writes).ordered(false).await?;
+    }
+    model
+        .delete_many(doc! { "built_at": { "$lt": built_at } })
+        .await?;
+    Ok(())
+}
+
+/// Spawns a background task that rebuilds the recommendation model from all
+/// views and ratings at startup and then periodically.
+pub fn spawn_recommendation_builder(
+    views: Collection<View>,
+    ratings: Collection<Rating>,
+    model: Collection<ItemModel>,
+) {
+    actix_rt::spawn(async move {
+        let mut interval =
+            actix_rt::time::interval(Duration::from_secs(recommendation_rebuild_secs()));
+
+        loop {
+            interval.tick().await;
+            let built_at = DateTime::now();
+            let rows = match build_model(&views, &ratings, built_at).await {
+                Ok(rows) => rows,
+                Err(e) => {
+                    log::warn!("Recommendation model rebuild failed: {}", e);
+                    continue;
+                }
+            };
+            let count = rows.len();
+            match write_model(&model, rows, built_at).await {
+                Ok(()) => log::info!("Recommendation model rebuilt over {} titles", count),
+                Err(e) => log::warn!("Recommendation model rebuild failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seen(thumb: Option<Thumb>, at: i64) -> Interaction {
+        Interaction {
+            viewed: true,
+            thumb,
+            at,
+        }
+    }
+
+    fn history(items: &[(&str, Option<Thumb>)]) -> History {
+        items
+            .iter()
+            .map(|&(id, thumb)| {
+                (
+                    id.to_string(),
+                    seen(thumb, DateTime::now().timestamp_millis()),
+                )
+            })
+            .collect()
+    }
+
+    fn build(histories: Vec<History>) -> HashMap<String, ItemModel> {
+        let mut builder = ModelBuilder::new(DateTime::now());
+        histories.into_iter().for_each(|h| builder.add(h));
+        builder
+            .finish()
+            .into_iter()
+            .map(|row| (row.movie_id.clone(), row))
+            .collect()
+    }
+
+    fn row(movie_id: &str, neighbors: &[(&str, f64)]) -> ItemModel {
+        ItemModel {
+            movie_id: movie_id.to_string(),
+            popularity: 0.0,
+            neighbors: neighbors
+                .iter()
+                .map(|&(id, score)| Neighbor {
+                    movie_id: id.to_string(),
+                    score,
+                })
+                .collect(),
+            built_at: DateTime::now(),
+        }
+    }
+
+    #[test]
+    fn pairs_need_support_from_two_users() {
+        let model = build(vec![
+            history(&[("a", None), ("b", None), ("c", None)]),
+            history(&[("a", None), ("b", None)]),
+        ]);
+        let neighbors: Vec<&str> = model["a"]
+            .neighbors
+            .iter()
+            .map(|n| n.movie_id.as_str())
+            .collect();
+        assert_eq!(neighbors, vec!["b"]);
+        assert!(model["c"].neighbors.is_empty());
+        assert!((model["a"].neighbors[0].score - 1.0).abs() < 1e-9);
+        assert_eq!(model["a"].popularity, 2.0);
+    }
+
+    #[test]
+    fn dislikes_count_for_nothing() {
+        let model = build(vec![
+            history(&[("a", Some(Thumb::Love)), ("b", Some(Thumb::Down))]),
+            history(&[("a", Some(Thumb::Up)), ("b", Some(Thumb::Down))]),
+        ]);
+        assert!(!model.contains_key("b"));
+        assert_eq!(model["a"].popularity, LOVE_WEIGHT + UP_WEIGHT);
+        assert!(model["a"].neighbors.is_empty());
+    }
+
+    #[test]
+    fn old_interactions_do_not_count_towards_popularity() {
+        let old = DateTime::now().timestamp_millis() - (POPULARITY_WINDOW_DAYS + 1) * 86_400_000;
+        let mut first = history(&[("a", None)]);
+        first.insert("b".to_string(), seen(None, old));
+        let mut second = history(&[("a", None)]);
+        second.insert("b".to_string(), seen(None, old));
+        let model = build(vec![first, second]);
+        assert_eq!(model["b"].popularity, 0.0);
+        assert_eq!(model["b"].neighbors.len(), 1);
+    }
+
+    #[test]
+    fn caps_distinct_pairs_per_title() {
+        let mut builder = ModelBuilder::new(DateTime::now());
+        for n in 0..MAX_PAIRS_PER_ITEM + 10 {
+            builder.add(history(&[("hub", None), (&format!("t{n}"), None)]));
+        }
+        assert_eq!(builder.partners[builder.index["hub"]], MAX_PAIRS_PER_ITEM);
+        assert_eq!(builder.pairs.len(), MAX_PAIRS_PER_ITEM);
+    }
+
+    #[test]
+    fn personalizes_from_liked_and_watched_seeds() {
+        let history = history(&[
+            ("a", Some(Thumb::Love)),
+            ("b", None),
+            ("x", Some(Thumb::Down)),
+        ]);
+        let rows = vec![
+            row("a", &[("c", 0.5), ("b", 0.9)]),
+            row("b", &[("c", 0.5), ("d", 0.8)]),
+            row("x", &[("e", 1.0)]),
+        ];
+        let ranked = personalize(&history, &rows);
+        let picks: Vec<(&str, f64, &str)> = ranked
+            .iter()
+            .map(|c| (c.movie_id.as_str(), c.score, c.because.as_str()))
+            .collect();
+        assert_eq!(picks, vec![("c", 2.0, "a"), ("d", 0.8, "b")]);
+    }
+
+    #[test]
+    fn blends_scores_scaled_to_their_best() {
+        let personal = vec![
+            Candidate {
+                movie_id: "p1".to_string(),
+                score: 8.0,
+                because: "a".to_string(),
+            },
+            Candidate {
+                movie_id: "p2".to_string(),
+                score: 2.0,
+                because: "a".to_string(),
+            },
+        ];
+        let mut hot = row("hot", &[]);
+        hot.popularity = 400.0;
+        let mut warm = row("p2", &[]);
+        warm.popularity = 100.0;
+
+        let ranked = blend(personal, vec![hot, warm]);
+        let order: Vec<(&str, f64, bool)> = ranked
+            .iter()
+            .map(|(id, score, because)| (id.as_str(), *score, because.is_some()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("p1", 1.0, true),
+                ("hot", POPULAR_WEIGHT, false),
+                ("p2", 0.25, true)
+            ]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use crate::models::review::{ReviewAttempt, ReviewStatus};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::Collection;
+use std::collections::{BTreeSet, HashSet};
+use std::env;
+
+// ── Content filters ───────────────────────────────────────────────────────────
+
+/// A check run on review text before it is stored. Filters never reject
+/// outright; anything they flag waits in the moderation queue.
+pub trait ContentFilter: Send + Sync {
+    /// Why the text needs a moderator's look; empty when it is fine.
+    fn check(&self, text: &str) -> Vec<String>;
+}
+
+/// Flags reviews containing any word or phrase from a configured list.
+/// Matching is on whole words, case-insensitively; a phrase matches the same
+/// words in order whatever punctuation or spacing separates them.
+pub struct ProfanityFilter {
+    /// Entries normalized to lowercase words joined by single spaces.
+    phrases: HashSet<String>,
+
+    /// Words in the longest entry.
+    longest: usize,
+}
+
+/// Lowercase words of `text`, split on anything that is not alphanumeric.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+impl ProfanityFilter {
+    pub fn new(words_or_phrases: impl IntoIterator<Item = String>) -> Self {
+        let phrases: HashSet<String> = words_or_phrases
+            .into_iter()
+            .filter(|entry| !entry.trim_start().starts_with('#'))
+            .map(|entry| words(&entry).join(" "))
+            .filter(|phrase| !phrase.is_empty())
+            .collect();
+        let longest = phrases
+            .iter()
+            .map(|phrase| phrase.split(' ').count())
+            .max()
+            .unwrap_or(0);
+        ProfanityFilter { phrases, longest }
+    }
+
+    /// Entries from `PROFANITY_WORDS` (comma-separated) and `PROFANITY_WORDS_FILE`
+    /// (one per line, `#` for comments).
+    pub fn from_env() -> Self {
+        let mut entries: Vec<String> = env::var("PROFANITY_WORDS")
+            .map(|v| v.split(',').map(str::to_string).collect())
+            .unwrap_or_default();
+
+        if let Ok(path) = env::var("PROFANITY_WORDS_FILE") {
+            match std::fs::read_to_string(&path) {
+                Ok(list) => entries.extend(list.lines().map(str::to_string)),
+                Err(e) => log::warn!("Failed to read profanity list {}: {}", path, e),
+            }
+        }
+
+        ProfanityFilter::new(entries)
+    }
+}
+
+impl ContentFilter for ProfanityFilter {
+    fn check(&self, text: &str) -> Vec<String> {
+        let words = words(text);
+        let mut hits = BTreeSet::new();
+        for start in 0..words.len() {
+            for len in 1..=self.longest.min(words.len() - start) {
+                let candidate = words[start..start + len].join(" ");
+                if self.phrases.contains(&candidate) {
+                    hits.insert(candidate);
+                }
+            }
+        }
+
+        if hits.is_empty() {
+            Vec::new()
+        } else {
+            let hits: Vec<String> = hits.into_iter().collect();
+            vec![format!("profanity: {}", hits.join(", "))]
+        }
+    }
+}
+
+// ── Review policy ─────────────────────────────────────────────────────────────
+
+/// Limits and checks applied to reviews, configured from the environment:
+/// - `REVIEW_PREMODERATION`: hold every review for moderation (default false).
+/// - `REVIEW_RATE_LIMIT` reviews per `REVIEW_RATE_WINDOW_SECS` per user (default 5 per hour).
+/// - `REVIEW_MAX_CHARS`: longest accepted text (default 5000).
+pub struct ReviewPolicy {
+    filters: Vec<Box<dyn ContentFilter>>,
+    attempts: Collection<ReviewAttempt>,
+    pub premoderate: bool,
+    pub rate_limit: u64,
+    pub rate_window_secs: i64,
+    pub max_chars: usize,
+}
+
+impl ReviewPolicy {
+    /// `attempts` logs every write for the rate limit.
+    pub fn from_env(attempts: Collection<ReviewAttempt>) -> Self {
+        ReviewPolicy {
+            filters: vec![Box::new(ProfanityFilter::from_env())],
+            attempts,
+            premoderate: env::var("REVIEW_PREMODERATION")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            rate_limit: env::var("REVIEW_RATE_LIMIT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            rate_window_secs: env::var("REVIEW_RATE_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+            max_chars: env::var("REVIEW_MAX_CHARS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5000),
+        }
+    }
+
+    /// Every flag raised by the configured filters.
+    pub fn check(&self, text: &str) -> Vec<String> {
+        self.filters
+            .iter()
+            .flat_map(|filter| filter.check(text))
+            .collect()
+    }
+
+    /// Status of a newly written or edited review. A rejected review stays
+    /// out of the listings until a moderator looks at the new version.
+    pub fn status_for(&self, flags: &[String], previous: Option<ReviewStatus>) -> ReviewStatus {
+        if self.premoderate || !flags.is_empty() || previous == Some(ReviewStatus::Rejected) {
+            ReviewStatus::Pending
+        } else {
+            ReviewStatus::Approved
+        }
+    }
+
+    /// Records a write by `user` and returns `None` if it is within the
+    /// limit, or the seconds until the user may write again.
+    ///
+    /// The attempt is logged before it is counted, so concurrent requests all
+    /// see each other: only the first `rate_limit` attempts of the window, in
+    /// `(at, _id)` order, are admitted. A refused attempt is removed again and
+    /// does not push the window further out.
+    pub async fn admit(&self, user: &str) -> mongodb::error::Result<Option<i64>> {
+        if self.rate_limit == 0 {
+            return Ok(Some(self.rate_window_secs.max(1)));
+        }
+        let window_millis = self.rate_window_secs * 1000;
+        let now = DateTime::now().timestamp_millis();
+        let attempt = ReviewAttempt {
+            id: ObjectId::new(),
+            user: user.to_string(),
+            at: DateTime::from_millis(now),
+            expires_at: DateTime::from_millis(now + window_millis),
+        };
+        self.attempts.insert_one(&attempt).await?;
+
+        let admitted: Vec<ReviewAttempt> = self
+            .attempts
+            .find(doc! {
+                "user": user,
+                "at": { "$gte": DateTime::from_millis(now - window_millis) },
+            })
+            .sort(doc! { "at": 1, "_id": 1 })
+            .limit(self.rate_limit as i64)
+            .await?
+            .try_collect()
+            .await?;
+        if admitted.iter().any(|earlier| earlier.id == attempt.id) {
+            return Ok(None);
+        }
+
+        if let Err(e) = self.attempts.delete_one(doc! { "_id": attempt.id }).await {
+            log::warn!("Failed to drop refused review attempt of {}: {}", user, e);
+        }
+        let oldest = admitted
+            .first()
+            .map_or(now, |earlier| earlier.at.timestamp_millis());
+        Ok(Some(((oldest + window_millis - now) / 1000).max(1)))
+    }
+}
+
+/// True for a unique-index violation, e.g. a second review of the same title.
+/// Inserts report it as a write error, `findAndModify` upserts as a command error.
+pub fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(e)) => e.code == 11000,
+        ErrorKind::Command(e) => e.code == 11000,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(entries: &[&str]) -> ProfanityFilter {
+        ProfanityFilter::new(entries.iter().map(|e| e.to_string()))
+    }
+
+    #[test]
+    fn matches_whole_words_case_insensitively() {
+        let f = filter(&["darn"]);
+        assert_eq!(f.check("Well, DARN it."), vec!["profanity: darn"]);
+        assert!(f.check("darned good").is_empty());
+    }
+
+    #[test]
+    fn matches_phrases_across_punctuation_and_spacing() {
+        let f = filter(&["son of a gun", "gun"]);
+        assert_eq!(
+            f.check("What a son-of-a   GUN!"),
+            vec!["profanity: gun, son of a gun"]
+        );
+        assert!(filter(&["son of a gun"]).check("a son of a").is_empty());
+        assert!(filter(&["son of a gun"]).check("gun of a son").is_empty());
+    }
+
+    #[test]
+    fn skips_comments_and_blank_entries() {
+        let f = filter(&["# heading", "  ", " Heck  Yes "]);
+        assert_eq!(f.longest, 2);
+        assert_eq!(f.check("heck yes"), vec!["profanity: heck yes"]);
+        assert!(f.check("heading").is_empty());
+    }
+}
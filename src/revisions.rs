@@ -0,0 +1,331 @@
+use crate::models::movie::METADATA_FIELDS;
+use crate::models::revision::{FieldChange, MovieRevision};
+use crate::search;
+use mongodb::bson::{doc, Bson, DateTime, Document};
+use mongodb::options::{ReturnDocument, UpdateModifications};
+use mongodb::Collection;
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+// ── Recorded movie writes ─────────────────────────────────────────────────────
+//
+// Every edit to a movie goes through `write_movie`, which stores a
+// `MovieRevision` with the field-level diff. Counters the system maintains,
+// like rating aggregates, are updated directly and not recorded. Each movie carries a `revision`
+// counter; the update only applies if the counter is unchanged since the
+// "before" snapshot was read, so the diff is exactly what this write did.
+
+/// Fields that change on every write or are derived, and say nothing about
+/// the content.
+const UNTRACKED_FIELDS: &[&str] = &["_id", "revision", "updated_at", "search_keys"];
+
+/// Fields a restore rolls back besides `METADATA_FIELDS`. Translations have
+/// their own endpoints, but their edits are recorded like any other.
+const RESTORED_FIELDS: &[&str] = &["translations"];
+
+/// How many times a write is retried when another writer gets in between.
+const MAX_ATTEMPTS: usize = 5;
+
+#[derive(Error, Debug)]
+pub enum RevisionError {
+    #[error("The movie kept changing while being updated; try again")]
+    Conflict,
+
+    #[error(transparent)]
+    Db(#[from] mongodb::error::Error),
+}
+
+/// Matches a movie still at `rev`. Movies written before revisions existed
+/// have no counter, which counts as revision 0.
+fn revision_filter(rev: i64) -> Document {
+    if rev == 0 {
+        doc! { "revision": { "$in": [0_i64, null] } }
+    } else {
+        doc! { "revision": rev }
+    }
+}
+
+/// Adds the revision bump and `updated_at` stamp to a caller's update.
+fn with_bump(update: UpdateModifications, rev: i64, now: DateTime) -> UpdateModifications {
+    match update {
+        UpdateModifications::Document(mut update) => {
+            let mut set = update.get_document("$set").cloned().unwrap_or_default();
+            set.insert("revision", rev);
+            set.insert("updated_at", now);
+            update.insert("$set", set);
+            UpdateModifications::Document(update)
+        }
+        UpdateModifications::Pipeline(mut stages) => {
+            stages.push(doc! { "$set": { "revision": rev, "updated_at": now } });
+            UpdateModifications::Pipeline(stages)
+        }
+        other => other,
+    }
+}
+
+/// Top-level fields that differ between two versions of a movie.
+pub fn diff(before: &Document, after: &Document) -> Vec<FieldChange> {
+    let present = |d: &Document, key: &str| d.get(key).filter(|v| **v != Bson::Null).cloned();
+
+    let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|field| !UNTRACKED_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let from = present(before, field);
+            let to = present(after, field);
+            (from != to).then(|| FieldChange {
+                field: field.clone(),
+                from,
+                to,
+            })
+        })
+        .collect()
+}
+
+/// The update that undoes `later` (revisions after the target, oldest first)
+/// on the editable metadata and translations, or `None` when none of them
+/// touched those.
+pub fn restore_update(later: &[MovieRevision]) -> Option<Document> {
+    // The first later change to a field holds its value as of the target.
+    let mut set = Document::new();
+    let mut unset = Document::new();
+    for change in later.iter().flat_map(|revision| &revision.changes) {
+        let field = change.field.as_str();
+        let restored = METADATA_FIELDS.contains(&field) || RESTORED_FIELDS.contains(&field);
+        if !restored || set.contains_key(field) || unset.contains_key(field) {
+            continue;
+        }
+        match &change.from {
+            Some(value) => set.insert(field, value.clone()),
+            None => unset.insert(field, ""),
+        };
+    }
+
+    if set.is_empty() && unset.is_empty() {
+        return None;
+    }
+    let mut update = Document::new();
+    if !set.is_empty() {
+        update.insert("$set", set);
+    }
+    if !unset.is_empty() {
+        update.insert("$unset", unset);
+    }
+    Some(update)
+}
+
+/// Stores a revision unless the write changed nothing that is tracked.
+pub async fn record_revision(
+    revisions: &Collection<MovieRevision>,
+    movie_id: String,
+    rev: i64,
+    changes: Vec<FieldChange>,
+    actor: &str,
+) -> mongodb::error::Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let revision = MovieRevision {
+        id: None,
+        movie_id,
+        rev,
+        changes,
+        actor: actor.to_string(),
+        at: DateTime::now(),
+    };
+    revisions.insert_one(revision).await.map(|_| ())
+}
+
+/// Applies `update` to the movie matching `filter` and records the diff as a
+/// new revision by `actor`. Returns the updated movie, or `None` if nothing
+/// matches `filter`. If the revision cannot be stored the write is undone
+/// and the error returned.
+pub async fn write_movie(
+    movies: &Collection<Document>,
+    revisions: &Collection<MovieRevision>,
+    filter: Document,
+    update: impl Into<UpdateModifications>,
+    actor: &str,
+) -> Result<Option<Document>, RevisionError> {
+    let update = update.into();
+
+    for _ in 0..MAX_ATTEMPTS {
+        let Some(before) = movies.find_one(filter.clone()).await? else {
+            return Ok(None);
+        };
+        let Ok(oid) = before.get_object_id("_id") else {
+            return Ok(None);
+        };
+        let rev = before.get_i64("revision").unwrap_or(0);
+
+        let mut guarded = doc! { "_id": oid };
+        guarded.extend(revision_filter(rev));
+
+        let after = movies
+            .find_one_and_update(guarded, with_bump(update.clone(), rev + 1, DateTime::now()))
+            .return_document(ReturnDocument::After)
+            .await?;
+
+        if let Some(after) = after {
+            let changes = diff(&before, &after);
+            if let Err(e) = record_revision(revisions, oid.to_hex(), rev + 1, changes, actor).await
+            {
+                // Without transactions, the write is undone so no change goes
+                // unrecorded, unless someone has written on top of it since.
+                let mut undo = doc! { "_id": oid };
+                undo.extend(revision_filter(rev + 1));
+                if let Err(undo_error) = movies.replace_one(undo, before).await {
+                    log::warn!("Failed to undo unrecorded write to {}: {}", oid, undo_error);
+                }
+                return Err(e.into());
+            }
+            if let Err(e) = search::index_movie(movies, &after).await {
+                log::warn!("Failed to update movie search keys: {}", e);
+            }
+            return Ok(Some(after));
+        }
+    }
+
+    Err(RevisionError::Conflict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(field: &str, from: Option<Bson>, to: Option<Bson>) -> FieldChange {
+        FieldChange {
+            field: field.to_string(),
+            from,
+            to,
+        }
+    }
+
+    fn revision(rev: i64, changes: Vec<FieldChange>) -> MovieRevision {
+        MovieRevision {
+            id: None,
+            movie_id: "m1".to_string(),
+            rev,
+            changes,
+            actor: "admin@example.com".to_string(),
+            at: DateTime::from_millis(0),
+        }
+    }
+
+    #[test]
+    fn diff_reports_changed_added_and_removed_fields() {
+        let before = doc! { "_id": 1, "title": "Old", "desc": "Same", "year": "1999" };
+        let after = doc! { "_id": 2, "title": "New", "desc": "Same", "genre": "drama" };
+        let changes = diff(&before, &after);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.field.as_str(), c.from.clone(), c.to.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("genre", None, Some(Bson::from("drama"))),
+                ("title", Some(Bson::from("Old")), Some(Bson::from("New"))),
+                ("year", Some(Bson::from("1999")), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_ignores_bookkeeping_and_null_versus_missing() {
+        let before =
+            doc! { "revision": 1_i64, "updated_at": DateTime::from_millis(1), "desc": null };
+        let after = doc! { "revision": 2_i64, "updated_at": DateTime::from_millis(2) };
+        assert!(diff(&before, &after).is_empty());
+    }
+
+    #[test]
+    fn diff_compares_nested_values_whole() {
+        let before = doc! { "genres": ["drama"] };
+        let after = doc! { "genres": ["drama", "comedy"] };
+        let changes = diff(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "genres");
+    }
+
+    #[test]
+    fn restore_takes_the_earliest_later_value_of_each_field() {
+        let later = vec![
+            revision(3, vec![change("title", Some("A".into()), Some("B".into()))]),
+            revision(
+                4,
+                vec![
+                    change("title", Some("B".into()), Some("C".into())),
+                    change("desc", None, Some("added".into())),
+                ],
+            ),
+        ];
+        assert_eq!(
+            restore_update(&later),
+            Some(doc! { "$set": { "title": "A" }, "$unset": { "desc": "" } })
+        );
+    }
+
+    #[test]
+    fn restore_rolls_back_translations() {
+        let later = vec![revision(
+            5,
+            vec![change(
+                "translations",
+                Some(Bson::Document(doc! { "de": { "title": "Alt" } })),
+                Some(Bson::Document(doc! { "de": { "title": "Neu" } })),
+            )],
+        )];
+        assert_eq!(
+            restore_update(&later),
+            Some(doc! { "$set": { "translations": { "de": { "title": "Alt" } } } })
+        );
+    }
+
+    #[test]
+    fn restore_leaves_non_metadata_fields_alone() {
+        let later = vec![revision(
+            2,
+            vec![
+                change("status", Some("draft".into()), Some("published".into())),
+                change("subtitles", None, Some(Bson::Array(vec![]))),
+                change("deleted_at", None, Some(DateTime::from_millis(5).into())),
+            ],
+        )];
+        assert_eq!(restore_update(&later), None);
+        assert_eq!(restore_update(&[]), None);
+    }
+
+    #[test]
+    fn bump_is_added_to_documents_and_pipelines() {
+        let now = DateTime::from_millis(7);
+        let update = with_bump(doc! { "$set": { "title": "T" } }.into(), 3, now);
+        let UpdateModifications::Document(update) = update else {
+            panic!("expected a document update");
+        };
+        assert_eq!(
+            update,
+            doc! { "$set": { "title": "T", "revision": 3_i64, "updated_at": now } }
+        );
+
+        let update = with_bump(vec![doc! { "$unset": "desc" }].into(), 1, now);
+        let UpdateModifications::Pipeline(stages) = update else {
+            panic!("expected a pipeline update");
+        };
+        assert_eq!(stages.len(), 2);
+        assert_eq!(
+            stages[1],
+            doc! { "$set": { "revision": 1_i64, "updated_at": now } }
+        );
+    }
+
+    #[test]
+    fn revision_zero_matches_movies_without_a_counter() {
+        assert_eq!(
+            revision_filter(0),
+            doc! { "revision": { "$in": [0_i64, null] } }
+        );
+        assert_eq!(revision_filter(4), doc! { "revision": 4_i64 });
+    }
+}
@@ -0,0 +1,227 @@
+use crate::catalog::{and, not_deleted};
+use crate::images::{blurhash, exceeds_limits, max_dimension, prerender_variants, RenderSlots};
+use crate::models::movie::Movie;
+use crate::models::revision::MovieRevision;
+use crate::models::users::Users;
+use crate::revisions::{write_movie, RevisionError};
+use crate::routes::auth::{claims_email, require_admin, require_user};
+use crate::storage::{asset_key, max_image_bytes, parse_asset_key, AssetStorage, ImageType};
+use actix_multipart::Multipart;
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, from_document, oid::ObjectId, DateTime, Document};
+use mongodb::Collection;
+use serde_json::json;
+
+/// Movie fields that hold an image URL; also the `{field}` path segment.
+const MOVIE_IMAGE_FIELDS: &[&str] = &["img", "img_title", "img_sm"];
+
+// ── Upload helpers ────────────────────────────────────────────────────────────
+
+/// Reads the first file part of a multipart body, enforcing `MAX_IMAGE_BYTES`
+/// while streaming, and checks that it really is a JPEG, PNG or WebP image.
+async fn read_image(mut payload: Multipart) -> Result<(Bytes, ImageType), HttpResponse> {
+    let limit = max_image_bytes();
+
+    loop {
+        let mut field = match payload.try_next().await {
+            Ok(Some(field)) => field,
+            Ok(None) => return Err(HttpResponse::BadRequest().body("No file part in the upload")),
+            Err(e) => return Err(HttpResponse::BadRequest().body(e.to_string())),
+        };
+        let is_file = field
+            .content_disposition()
+            .map(|cd| cd.get_filename().is_some() || cd.get_name() == Some("file"))
+            .unwrap_or(false);
+        if !is_file {
+            continue;
+        }
+
+        let mut bytes = BytesMut::new();
+        loop {
+            match field.try_next().await {
+                Ok(Some(chunk)) => {
+                    if bytes.len() + chunk.len() > limit {
+                        return Err(HttpResponse::PayloadTooLarge()
+                            .body(format!("Images are limited to {} bytes", limit)));
+                    }
+                    bytes.extend_from_slice(&chunk);
+                }
+                Ok(None) => break,
+                Err(e) => return Err(HttpResponse::BadRequest().body(e.to_string())),
+            }
+        }
+
+        return match ImageType::sniff(&bytes) {
+            // The image build encodes AVIF but cannot decode it, so an AVIF
+            // original could be neither checked, resized nor hashed.
+            Some(ImageType::Avif) => Err(HttpResponse::UnsupportedMediaType()
+                .body("AVIF uploads are not supported; upload a JPEG, PNG or WebP image")),
+            Some(_) if exceeds_limits(&bytes) => Err(HttpResponse::BadRequest().body(format!(
+                "Images are limited to {0}x{0} pixels",
+                max_dimension()
+            ))),
+            Some(image_type) => Ok((bytes.freeze(), image_type)),
+            None => {
+                Err(HttpResponse::UnsupportedMediaType().body("Upload a JPEG, PNG or WebP image"))
+            }
+        };
+    }
+}
+
+/// Stores the image under its content hash and returns the URL it is served
+/// from. Its resized variants are rendered in the background.
+async fn store_image(
+    storage: &dyn AssetStorage,
+    slots: web::Data<RenderSlots>,
+    bytes: Bytes,
+    image_type: ImageType,
+) -> Result<String, HttpResponse> {
+    let key = asset_key(&bytes, image_type);
+    if let Err(e) = storage
+        .put(&key, bytes.clone(), image_type.content_type())
+        .await
+    {
+        return Err(HttpResponse::InternalServerError().body(e.to_string()));
+    }
+    let url = storage.url(&key);
+    actix_rt::spawn(prerender_variants(
+        slots.into_inner(),
+        key,
+        bytes,
+        image_type,
+    ));
+    Ok(url)
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// POST /movies/{id}/images/{field}  — admin only
+///
+/// Multipart upload of a poster; `field` is `img`, `img_title` or `img_sm`.
+/// The movie field is set to the served URL, with a BlurHash placeholder
+/// under `blurhash.<field>`, and recorded as a revision.
+pub async fn upload_movie_image(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    payload: Multipart,
+    storage: web::Data<dyn AssetStorage>,
+    slots: web::Data<RenderSlots>,
+    movie_collection: web::Data<Collection<Movie>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (movie_id, field) = path.into_inner();
+    if !MOVIE_IMAGE_FIELDS.contains(&field.as_str()) {
+        return HttpResponse::NotFound().body("Image field must be img, img_title or img_sm");
+    }
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    let filter = and(doc! { "_id": oid }, not_deleted());
+    match movie_collection.count_documents(filter.clone()).await {
+        Ok(0) => return HttpResponse::NotFound().body("Movie not found"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let (bytes, image_type) = match read_image(payload).await {
+        Ok(image) => image,
+        Err(res) => return res,
+    };
+    let placeholder = bytes.clone();
+    let placeholder = match web::block(move || blurhash(&placeholder)).await {
+        Ok(Ok(hash)) => Some(hash),
+        Ok(Err(e)) => {
+            log::warn!("No blurhash for {} of movie {}: {}", field, movie_id, e);
+            None
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let url = match store_image(storage.get_ref(), slots, bytes, image_type).await {
+        Ok(url) => url,
+        Err(res) => return res,
+    };
+
+    let movies = movie_collection.clone_with_type::<Document>();
+    let blurhash_field = format!("blurhash.{}", field);
+    let update = match placeholder {
+        Some(hash) => doc! { "$set": { field.as_str(): url, blurhash_field: hash } },
+        None => doc! { "$set": { field.as_str(): url }, "$unset": { blurhash_field: "" } },
+    };
+
+    match write_movie(&movies, &revision_collection, filter, update, &actor).await {
+        Ok(Some(updated)) => match from_document::<Movie>(updated) {
+            Ok(movie) => HttpResponse::Ok().json(movie),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Ok(None) => HttpResponse::NotFound().body("Movie not found"),
+        Err(e @ RevisionError::Conflict) => HttpResponse::Conflict().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// POST /me/avatar  — any authenticated user
+///
+/// Multipart upload of the caller's profile picture; sets `profile_pic`.
+pub async fn upload_avatar(
+    req: HttpRequest,
+    payload: Multipart,
+    storage: web::Data<dyn AssetStorage>,
+    slots: web::Data<RenderSlots>,
+    users_collection: web::Data<Collection<Users>>,
+) -> HttpResponse {
+    let email = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    let (bytes, image_type) = match read_image(payload).await {
+        Ok(image) => image,
+        Err(res) => return res,
+    };
+    let url = match store_image(storage.get_ref(), slots, bytes, image_type).await {
+        Ok(url) => url,
+        Err(res) => return res,
+    };
+
+    let filter = and(doc! { "email": &email }, not_deleted());
+    let update = doc! { "$set": { "profile_pic": &url, "updated_at": DateTime::now() } };
+
+    match users_collection.update_one(filter, update).await {
+        Ok(result) if result.matched_count == 0 => HttpResponse::NotFound().body("User not found"),
+        Ok(_) => HttpResponse::Ok().json(json!({ "profile_pic": url })),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /assets/{key}
+///
+/// Serves stored uploads. Keys are content hashes, so responses never change
+/// and can be cached indefinitely.
+pub async fn get_asset(
+    key: web::Path<String>,
+    storage: web::Data<dyn AssetStorage>,
+) -> HttpResponse {
+    let key = key.into_inner();
+    let Some(image_type) = parse_asset_key(&key) else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    match storage.get(&key).await {
+        Ok(Some(bytes)) => HttpResponse::Ok()
+            .content_type(image_type.content_type())
+            .insert_header((header::CACHE_CONTROL, "public, max-age=31536000, immutable"))
+            .insert_header((header::ETAG, format!("\"{}\"", key)))
+            .body(bytes),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
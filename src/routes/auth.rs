@@ -1,11 +1,15 @@
+use crate::locale::normalize_locale;
 use crate::models::user::User;
+use crate::models::workflow::EditorialRole;
 use crate::utils::{decrypt_password, encrypt_password, get_secret_key};
-use crate::verify_token::verify;
+use crate::verify_token::{verify, AppError};
 use actix_web::{web, HttpRequest, HttpResponse};
 use bson::doc;
 use jsonwebtoken::{encode, EncodingKey, Header};
+use mongodb::options::ReturnDocument;
 use mongodb::Collection;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // ── Token claims ──────────────────────────────────────────────────────────────
 
@@ -13,31 +17,70 @@ use serde::{Deserialize, Serialize};
 pub struct Claims {
     pub sub: String, // user's email
     pub exp: usize,
-    pub is_admin: bool,
+    pub is_admin: bool, // informational; requests check the account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>, // profile locale, e.g. pt-BR
 }
 
 // ── Shared auth guards ────────────────────────────────────────────────────────
 
+/// True when the verified claims belong to an admin.
+pub fn claims_is_admin(claims: &HashMap<String, String>) -> bool {
+    claims.get("is_admin").map(|v| v == "true").unwrap_or(false)
+}
+
+/// The caller's editorial role; only meaningful for admins.
+pub fn claims_role(claims: &HashMap<String, String>) -> EditorialRole {
+    EditorialRole::from_claim(claims.get("role").map(String::as_str))
+}
+
+/// The caller's email (the `sub` claim), e.g. to record who made a change.
+pub fn claims_email(claims: &HashMap<String, String>) -> String {
+    claims.get("sub").cloned().unwrap_or_default()
+}
+
+/// 401 for a missing, invalid or inactive token; 500 when the server could
+/// not check it, so a database outage does not log clients out.
+fn auth_error(error: AppError) -> HttpResponse {
+    match error {
+        AppError::TokenNotFound | AppError::DecodeError(_) | AppError::UserInactive => {
+            HttpResponse::Unauthorized().finish()
+        }
+        AppError::SecretKeyNotSet | AppError::UserLookupUnavailable | AppError::UserLookup(_) => {
+            log::error!("Failed to verify token: {}", error);
+            HttpResponse::InternalServerError().body(error.to_string())
+        }
+    }
+}
+
 /// Verifies the request token and confirms the caller is an admin.
-/// Returns `Ok(())` on success, or the ready-to-return `HttpResponse` on failure.
-pub async fn require_admin(req: HttpRequest) -> Result<(), HttpResponse> {
+/// Returns the claims on success, or the ready-to-return `HttpResponse` on failure.
+pub async fn require_admin(req: HttpRequest) -> Result<HashMap<String, String>, HttpResponse> {
     match verify(req).await {
         Ok(claims) => {
-            if claims.get("is_admin").map(|v| v == "true").unwrap_or(false) {
-                Ok(())
+            if claims_is_admin(&claims) {
+                Ok(claims)
             } else {
                 Err(HttpResponse::Forbidden().body("You are not allowed!"))
             }
         }
-        Err(_) => Err(HttpResponse::Unauthorized().finish()),
+        Err(e) => Err(auth_error(e)),
     }
 }
 
 /// Verifies the request token only — no admin check.
-pub async fn require_auth(req: HttpRequest) -> Result<(), HttpResponse> {
+/// Returns the claims so handlers can relax filters for admins.
+pub async fn require_auth(req: HttpRequest) -> Result<HashMap<String, String>, HttpResponse> {
+    verify(req).await.map_err(auth_error)
+}
+
+/// Verifies the request token and returns the caller's email (the `sub` claim).
+pub async fn require_user(req: HttpRequest) -> Result<String, HttpResponse> {
     match verify(req).await {
-        Ok(_) => Ok(()),
-        Err(_) => Err(HttpResponse::Unauthorized().finish()),
+        Ok(mut claims) => claims
+            .remove("sub")
+            .ok_or_else(|| HttpResponse::Unauthorized().finish()),
+        Err(e) => Err(auth_error(e)),
     }
 }
 
@@ -56,6 +99,13 @@ pub async fn register_user(
         return HttpResponse::BadRequest().body("Password is required.");
     }
 
+    let language = match user_info.language.as_deref().map(normalize_locale) {
+        Some(None) => {
+            return HttpResponse::BadRequest().body("language must be a locale such as pt-BR.")
+        }
+        language => language.flatten(),
+    };
+
     let encrypted_password = match encrypt_password(&user_info.password) {
         Ok(pw) => pw,
         Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
@@ -68,6 +118,9 @@ pub async fn register_user(
         password: encrypted_password,
         profile_pic: user_info.profile_pic.clone(),
         is_admin: false,
+        plan: None,
+        role: None,
+        language,
     };
 
     match auth_db.insert_one(new_user).await {
@@ -96,8 +149,10 @@ pub async fn login_user(
     }
 
     let query = match (&user_info.email, &user_info.username) {
-        (Some(email), _) if !email.is_empty() => doc! { "email": email },
-        (_, Some(username)) if !username.is_empty() => doc! { "username": username },
+        (Some(email), _) if !email.is_empty() => doc! { "email": email, "deleted_at": null },
+        (_, Some(username)) if !username.is_empty() => {
+            doc! { "username": username, "deleted_at": null }
+        }
         _ => return HttpResponse::BadRequest().body("Email or username is required."),
     };
 
@@ -121,20 +176,102 @@ pub async fn login_user(
         return HttpResponse::Unauthorized().body("Wrong credentials.");
     }
 
+    match issue_token(user, &secret_key) {
+        Ok(token) => HttpResponse::Ok().json(token),
+        Err(res) => res,
+    }
+}
+
+/// Signs a token carrying `user`'s identity and language.
+fn issue_token(user: User, secret_key: &[u8]) -> Result<String, HttpResponse> {
     let claims = Claims {
         sub: user.email,
         exp: 1_000_000_000,
         is_admin: user.is_admin,
+        language: user.language,
     };
 
-    let token = match encode(
+    encode(
         &Header::default(),
         &claims,
-        &EncodingKey::from_secret(&secret_key),
-    ) {
-        Ok(t) => t,
-        Err(_) => return HttpResponse::InternalServerError().body("Failed to generate token."),
+        &EncodingKey::from_secret(secret_key),
+    )
+    .map_err(|_| HttpResponse::InternalServerError().body("Failed to generate token."))
+}
+
+// ── Profile language ──────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct LanguageInput {
+    /// A locale such as `pt-BR`; `null` goes back to `Accept-Language`.
+    pub language: Option<String>,
+}
+
+/// PUT /me/language  — any authenticated user
+///
+/// Sets the profile language catalog text is shown in. The language travels
+/// in the token, so a fresh token is returned to use from now on.
+pub async fn set_language(
+    req: HttpRequest,
+    auth_db: web::Data<Collection<User>>,
+    input: web::Json<LanguageInput>,
+) -> HttpResponse {
+    let email = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    let update = match input.language.as_deref().map(normalize_locale) {
+        Some(Some(language)) => doc! { "$set": { "language": language } },
+        Some(None) => {
+            return HttpResponse::BadRequest().body("language must be a locale such as pt-BR.")
+        }
+        None => doc! { "$unset": { "language": "" } },
+    };
+
+    let user = match auth_db
+        .find_one_and_update(doc! { "email": &email, "deleted_at": null }, update)
+        .return_document(ReturnDocument::After)
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::NotFound().body("User not found."),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
     };
 
-    HttpResponse::Ok().json(token)
-}
\ No newline at end of file
+    let secret_key = match get_secret_key() {
+        Ok(k) => k,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    match issue_token(user, &secret_key) {
+        Ok(token) => HttpResponse::Ok().json(token),
+        Err(res) => res,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+
+    #[test]
+    fn only_token_problems_are_unauthorized() {
+        for error in [
+            AppError::TokenNotFound,
+            AppError::DecodeError("bad".to_string()),
+            AppError::UserInactive,
+        ] {
+            assert_eq!(auth_error(error).status(), StatusCode::UNAUTHORIZED);
+        }
+        for error in [
+            AppError::SecretKeyNotSet,
+            AppError::UserLookupUnavailable,
+            AppError::UserLookup("down".to_string()),
+        ] {
+            assert_eq!(
+                auth_error(error).status(),
+                StatusCode::INTERNAL_SERVER_ERROR
+            );
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::catalog::{and, not_deleted};
+use crate::charts::ChartRefresh;
+use crate::geo::normalize_region;
+use crate::models::chart::{ChartOverride, OverrideAction};
+use crate::models::list::ChartKind;
+use crate::models::movie::Movie;
+use crate::routes::auth::{claims_email, require_admin};
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use mongodb::Collection;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct OverrideInput {
+    pub kind: ChartKind,
+    pub region: Option<String>,
+    pub type_list: Option<String>,
+    pub movie_id: String,
+    pub action: OverrideAction,
+    pub position: Option<u32>,
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /admin/charts/overrides  — admin only
+pub async fn get_chart_overrides(
+    req: HttpRequest,
+    override_collection: web::Data<Collection<ChartOverride>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    match override_collection
+        .find(doc! {})
+        .sort(doc! { "created_at": 1 })
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<ChartOverride>>().await {
+            Ok(overrides) => HttpResponse::Ok().json(overrides),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// POST /admin/charts/overrides  — admin only
+///
+/// Pins a title into, or excludes it from, the charts of `kind`; `region`
+/// and `type_list` narrow it to matching rows.
+pub async fn add_chart_override(
+    req: HttpRequest,
+    input: web::Json<OverrideInput>,
+    override_collection: web::Data<Collection<ChartOverride>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    chart_refresh: web::Data<ChartRefresh>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let input = input.into_inner();
+    let region = match input.region.as_deref() {
+        None => None,
+        Some(raw) => match normalize_region(raw) {
+            Some(region) => Some(region),
+            None => return HttpResponse::BadRequest().body("region must be a two-letter code."),
+        },
+    };
+    if let Some(type_list) = input.type_list.as_deref() {
+        if type_list != "movie" && type_list != "series" {
+            return HttpResponse::BadRequest().body("type_list must be movie or series.");
+        }
+    }
+    match (input.action, input.position) {
+        (OverrideAction::Exclude, Some(_)) => {
+            return HttpResponse::BadRequest().body("Only pins take a position.");
+        }
+        (OverrideAction::Pin, Some(0)) => {
+            return HttpResponse::BadRequest().body("position is 1-based.");
+        }
+        _ => {}
+    }
+
+    let oid = match ObjectId::parse_str(&input.movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+    match movie_collection
+        .count_documents(and(doc! { "_id": oid }, not_deleted()))
+        .await
+    {
+        Ok(0) => return HttpResponse::NotFound().body("Movie not found"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let chart_override = ChartOverride {
+        id: None,
+        kind: input.kind,
+        region,
+        type_list: input.type_list,
+        movie_id: input.movie_id,
+        action: input.action,
+        position: input.position,
+        created_by: actor,
+        created_at: DateTime::now(),
+    };
+    let inserted = match override_collection.insert_one(chart_override).await {
+        Ok(result) => result.inserted_id,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    // The charts pick the change up shortly, without holding up the response.
+    chart_refresh.request();
+    HttpResponse::Created().json(inserted)
+}
+
+/// DELETE /admin/charts/overrides/{id}  — admin only
+pub async fn delete_chart_override(
+    req: HttpRequest,
+    override_id: web::Path<String>,
+    override_collection: web::Data<Collection<ChartOverride>>,
+    chart_refresh: web::Data<ChartRefresh>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let oid = match ObjectId::parse_str(override_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid override ID format."),
+    };
+
+    match override_collection.delete_one(doc! { "_id": oid }).await {
+        Ok(result) if result.deleted_count == 0 => {
+            return HttpResponse::NotFound().body("Override not found");
+        }
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    // The charts pick the change up shortly, without holding up the response.
+    chart_refresh.request();
+    HttpResponse::Ok().body("Override removed")
+}
@@ -0,0 +1,169 @@
+use crate::catalog::{and, not_deleted};
+use crate::credits::{movie_credits, normalize_episode, people_by_id};
+use crate::models::movie::Movie;
+use crate::models::person::{Credit, CreditRole, Person};
+use crate::routes::auth::require_admin;
+use actix_web::{web, HttpRequest, HttpResponse};
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use mongodb::Collection;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct CreditQuery {
+    /// ?episode=s01e02  — omit for credits on the whole title
+    episode: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreditInput {
+    pub person_id: String,
+    pub role: CreditRole,
+    pub character: Option<String>,
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// PUT /movies/{id}/credits?episode=  — admin only
+///
+/// Replaces the credits of the title, or of one episode. Billing order is the
+/// order people appear in the body within each role. Returns the title's
+/// credits as shown in its detail.
+pub async fn set_movie_credits(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    query: web::Query<CreditQuery>,
+    input: web::Json<Vec<CreditInput>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    person_collection: web::Data<Collection<Person>>,
+    credit_collection: web::Data<Collection<Credit>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let movie_id = movie_id.into_inner();
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+    let episode = match query.episode.as_deref() {
+        None => None,
+        Some(raw) => match normalize_episode(raw) {
+            Some(episode) => Some(episode),
+            None => {
+                return HttpResponse::BadRequest()
+                    .body("episode must be a season and episode such as s01e02.")
+            }
+        },
+    };
+
+    match movie_collection
+        .count_documents(and(doc! { "_id": oid }, not_deleted()))
+        .await
+    {
+        Ok(0) => return HttpResponse::NotFound().body("Movie not found"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let input = input.into_inner();
+    let mut seen: HashSet<(CreditRole, &str)> = HashSet::new();
+    let mut repeated: Vec<&str> = input
+        .iter()
+        .filter(|credit| !seen.insert((credit.role, credit.person_id.as_str())))
+        .map(|credit| credit.person_id.as_str())
+        .collect();
+    if !repeated.is_empty() {
+        repeated.sort_unstable();
+        repeated.dedup();
+        return HttpResponse::BadRequest().json(json!({
+            "error": "A person can be credited only once per role.",
+            "repeated": repeated,
+        }));
+    }
+
+    let people = match people_by_id(
+        &person_collection,
+        input.iter().map(|c| c.person_id.as_str()),
+    )
+    .await
+    {
+        Ok(people) => people,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let mut unknown: Vec<&str> = input
+        .iter()
+        .map(|credit| credit.person_id.as_str())
+        .filter(|id| !people.contains_key(*id))
+        .collect();
+    if !unknown.is_empty() {
+        unknown.sort_unstable();
+        unknown.dedup();
+        return HttpResponse::BadRequest().json(json!({
+            "error": "Some people do not exist.",
+            "unknown": unknown,
+        }));
+    }
+
+    let now = DateTime::now();
+    let set_id = ObjectId::new();
+    let mut billed: HashMap<CreditRole, u32> = HashMap::new();
+    let credits: Vec<Credit> = input
+        .into_iter()
+        .map(|credit| {
+            let order = billed.entry(credit.role).or_default();
+            *order += 1;
+            Credit {
+                id: None,
+                person_id: credit.person_id,
+                movie_id: movie_id.clone(),
+                episode: episode.clone(),
+                role: credit.role,
+                character: credit.character.filter(|c| !c.trim().is_empty()),
+                order: *order,
+                set_id: Some(set_id),
+                created_at: now,
+            }
+        })
+        .collect();
+
+    // Without transactions, the new set is stored before the old ones are
+    // dropped, so a failure never leaves the title without credits. Of
+    // concurrent replaces the newest set wins: each drops the sets older
+    // than its own, and a set that finds a newer one drops itself.
+    if !credits.is_empty() {
+        if let Err(e) = credit_collection.insert_many(credits).await {
+            return HttpResponse::InternalServerError().body(e.to_string());
+        }
+    }
+    let older = doc! {
+        "movie_id": &movie_id,
+        "episode": &episode,
+        "set_id": { "$not": { "$gte": set_id } },
+    };
+    if let Err(e) = credit_collection.delete_many(older).await {
+        return HttpResponse::InternalServerError().body(e.to_string());
+    }
+    let newer = doc! { "movie_id": &movie_id, "episode": &episode, "set_id": { "$gt": set_id } };
+    match credit_collection.count_documents(newer).await {
+        Ok(0) => {}
+        Ok(_) => {
+            if let Err(e) = credit_collection
+                .delete_many(doc! { "set_id": set_id })
+                .await
+            {
+                return HttpResponse::InternalServerError().body(e.to_string());
+            }
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    match movie_credits(&credit_collection, &person_collection, &movie_id).await {
+        Ok(credits) => HttpResponse::Ok().json(credits),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
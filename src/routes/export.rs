@@ -0,0 +1,121 @@
+use crate::catalog::{and, not_deleted};
+use crate::export::{
+    encode, epilogue, exportable_fields, parse_filter, preamble, projection, select_fields,
+    ExportFormat,
+};
+use crate::models::list::List;
+use crate::models::movie::Movie;
+use crate::models::users::Users;
+use crate::routes::auth::require_admin;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{error, web, HttpRequest, HttpResponse};
+use futures_util::stream::{self, StreamExt};
+use mongodb::bson::{doc, Document};
+use mongodb::Collection;
+use serde::Deserialize;
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    /// ?format=csv|ndjson|json  — defaults to ndjson
+    format: Option<String>,
+
+    /// ?fields=_id,title,year  — defaults to every exportable field
+    fields: Option<String>,
+
+    /// ?filter={"genre":"Action"}  — equality conditions on exportable fields
+    filter: Option<String>,
+
+    /// ?include_deleted=true  — also export what is in the trash
+    #[serde(default)]
+    include_deleted: bool,
+}
+
+/// Documents fetched from MongoDB per round trip while streaming.
+const EXPORT_BATCH_SIZE: u32 = 500;
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /admin/export/{collection}?format=&fields=&filter=&include_deleted=  — admin only
+///
+/// `collection` is `movies`, `lists` or `users`. The response is streamed as
+/// the cursor is read, so memory use does not grow with the collection.
+pub async fn export_collection(
+    req: HttpRequest,
+    collection: web::Path<String>,
+    query: web::Query<ExportQuery>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    users_collection: web::Data<Collection<Users>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let name = collection.into_inner();
+    let Some(allowed) = exportable_fields(&name) else {
+        return HttpResponse::NotFound().body("Unknown collection");
+    };
+
+    let format = match query.format.as_deref().map(ExportFormat::from_name) {
+        None => ExportFormat::Ndjson,
+        Some(Some(format)) => format,
+        Some(None) => return HttpResponse::BadRequest().body("format must be csv, ndjson or json"),
+    };
+
+    let fields = match select_fields(allowed, query.fields.as_deref()) {
+        Ok(fields) => fields,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let mut filter = match parse_filter(allowed, query.filter.as_deref()) {
+        Ok(filter) => filter,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    if !query.include_deleted {
+        filter = and(filter, not_deleted());
+    }
+
+    let source: Collection<Document> = match name.as_str() {
+        "movies" => movie_collection.clone_with_type(),
+        "lists" => list_collection.clone_with_type(),
+        _ => users_collection.clone_with_type(),
+    };
+
+    let cursor = match source
+        .find(filter)
+        .projection(projection(&fields))
+        .sort(doc! { "_id": 1 })
+        .batch_size(EXPORT_BATCH_SIZE)
+        .await
+    {
+        Ok(cursor) => cursor,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let header_bytes = preamble(format, &fields);
+    let rows = cursor.enumerate().map(move |(index, document)| {
+        document
+            .map(|document| encode(format, &fields, index, document))
+            .map_err(error::ErrorInternalServerError)
+    });
+    let body = stream::once(async move { Ok(header_bytes) })
+        .chain(rows)
+        .chain(stream::once(async move { Ok(epilogue(format)) }));
+
+    let filename = format!(
+        "{}-{}.{}",
+        name,
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ"),
+        format.extension()
+    );
+
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(filename)],
+        })
+        .streaming(body)
+}
@@ -0,0 +1,418 @@
+use crate::genres::{creates_cycle, run_migration, slugify};
+use crate::geo::{viewer, RegionResolver};
+use crate::locale::{normalize_locale, preferred_locales, DEFAULT_LOCALE};
+use crate::models::genre::{Genre, GenreEntry, GenreMigration, MigrationStatus};
+use crate::models::list::List;
+use crate::models::movie::Movie;
+use crate::models::revision::MovieRevision;
+use crate::reviews::is_duplicate_key;
+use crate::routes::auth::{claims_email, claims_is_admin, require_admin, require_auth};
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime, Document};
+use mongodb::options::ReturnDocument;
+use mongodb::Collection;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct GenreQuery {
+    /// ?lang=pt-BR  — display names in this locale; otherwise the profile
+    /// language, then `Accept-Language`
+    lang: Option<String>,
+
+    /// ?region=BR  — admins only: count titles as seen from that region
+    region: Option<String>,
+}
+
+// ── Inputs ────────────────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct GenreInput {
+    /// Derived from the English name when omitted.
+    pub slug: Option<String>,
+    pub names: BTreeMap<String, String>,
+    pub parent: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GenreUpdate {
+    pub names: BTreeMap<String, String>,
+    pub parent: Option<String>,
+}
+
+/// Canonicalizes the locale keys of `names`. Every genre needs an English
+/// name, the fallback for other locales.
+fn check_names(names: BTreeMap<String, String>) -> Result<BTreeMap<String, String>, HttpResponse> {
+    let mut checked = BTreeMap::new();
+    for (tag, name) in names {
+        let Some(locale) = normalize_locale(&tag) else {
+            return Err(HttpResponse::BadRequest().body(format!("Invalid locale: {}", tag)));
+        };
+        if name.trim().is_empty() {
+            return Err(HttpResponse::BadRequest().body("Genre names must not be empty."));
+        }
+        checked.insert(locale, name);
+    }
+    if !checked.contains_key(DEFAULT_LOCALE) {
+        return Err(HttpResponse::BadRequest().body("An English (en) name is required."));
+    }
+    Ok(checked)
+}
+
+/// Rejects a parent that does not exist or would make the hierarchy circular.
+async fn check_parent(
+    genre_collection: &Collection<Genre>,
+    slug: &str,
+    parent: Option<&str>,
+) -> Result<(), HttpResponse> {
+    let Some(parent) = parent else {
+        return Ok(());
+    };
+    match genre_collection
+        .count_documents(doc! { "slug": parent })
+        .await
+    {
+        Ok(0) => return Err(HttpResponse::BadRequest().body("Parent genre not found.")),
+        Ok(_) => {}
+        Err(e) => return Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+    match creates_cycle(genre_collection, slug, parent).await {
+        Ok(false) => Ok(()),
+        Ok(true) => Err(HttpResponse::BadRequest().body("A genre cannot be its own ancestor.")),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /genres?lang=&region=  — any authenticated user
+///
+/// The whole taxonomy with localized names and how many visible titles each
+/// genre holds, directly (`count`) and including its sub-genres (`total`).
+pub async fn get_genres(
+    req: HttpRequest,
+    query: web::Query<GenreQuery>,
+    genre_collection: web::Data<Collection<Genre>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let claims = match require_auth(req.clone()).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    let is_admin = claims_is_admin(&claims);
+    let viewer = viewer(&req, &region_resolver, is_admin, query.region.as_deref());
+    let locales = preferred_locales(&req, &claims, query.lang.as_deref());
+
+    let genres: Vec<Genre> = match genre_collection
+        .find(doc! {})
+        .sort(doc! { "slug": 1 })
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect().await {
+            Ok(genres) => genres,
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    // `count` comes straight from unwinding the genres. `total` must count
+    // a title once however many of its genres share an ancestor, so it is
+    // worked out from the distinct genre combinations, of which there are
+    // far fewer than titles.
+    let pipeline = vec![
+        doc! { "$match": crate::catalog::and(
+            doc! { "genres.0": { "$exists": true } },
+            viewer.movie_filter(),
+        ) },
+        doc! { "$project": { "genres": { "$setUnion": ["$genres", []] } } },
+        doc! { "$facet": {
+            "counts": [
+                { "$unwind": "$genres" },
+                { "$group": { "_id": "$genres", "titles": { "$sum": 1_i64 } } },
+            ],
+            "combinations": [
+                { "$group": { "_id": "$genres", "titles": { "$sum": 1_i64 } } },
+            ],
+        } },
+    ];
+    let facets: Document = match movie_collection.aggregate(pipeline).await {
+        Ok(mut cursor) => match cursor.try_next().await {
+            Ok(facets) => facets.unwrap_or_default(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let groups = |facet: &str| -> Vec<(&Bson, u64)> {
+        facets
+            .get_array(facet)
+            .into_iter()
+            .flatten()
+            .filter_map(|group| {
+                let group = group.as_document()?;
+                Some((group.get("_id")?, group.get_i64("titles").ok()? as u64))
+            })
+            .collect()
+    };
+
+    let counts: HashMap<&str, u64> = groups("counts")
+        .into_iter()
+        .filter_map(|(slug, titles)| Some((slug.as_str()?, titles)))
+        .collect();
+
+    let parents: HashMap<&str, &str> = genres
+        .iter()
+        .filter_map(|genre| Some((genre.slug.as_str(), genre.parent.as_deref()?)))
+        .collect();
+    let mut totals: HashMap<&str, u64> = HashMap::new();
+    for (slugs, titles) in groups("combinations") {
+        let Some(slugs) = slugs.as_array() else {
+            continue;
+        };
+        // Each title counts once per genre, however many of its genres share an ancestor.
+        let mut lineage: HashSet<&str> = HashSet::new();
+        for slug in slugs.iter().filter_map(Bson::as_str) {
+            let mut current = Some(slug);
+            while let Some(genre) = current {
+                if !lineage.insert(genre) {
+                    break;
+                }
+                current = parents.get(genre).copied();
+            }
+        }
+        for genre in lineage {
+            *totals.entry(genre).or_default() += titles;
+        }
+    }
+
+    let entries: Vec<GenreEntry> = genres
+        .iter()
+        .map(|genre| GenreEntry {
+            slug: genre.slug.clone(),
+            name: genre.display_name(&locales).to_string(),
+            names: genre.names.clone(),
+            parent: genre.parent.clone(),
+            count: counts.get(genre.slug.as_str()).copied().unwrap_or(0),
+            total: totals.get(genre.slug.as_str()).copied().unwrap_or(0),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(entries)
+}
+
+/// POST /genres  — admin only
+pub async fn create_genre(
+    req: HttpRequest,
+    input: web::Json<GenreInput>,
+    genre_collection: web::Data<Collection<Genre>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let input = input.into_inner();
+    let names = match check_names(input.names) {
+        Ok(names) => names,
+        Err(res) => return res,
+    };
+    let slug = match &input.slug {
+        Some(slug) => slug.clone(),
+        None => slugify(&names[DEFAULT_LOCALE]),
+    };
+    if slug.is_empty() || slugify(&slug) != slug {
+        return HttpResponse::BadRequest()
+            .body("slug must be lower-case letters and digits separated by dashes.");
+    }
+    if let Err(res) = check_parent(&genre_collection, &slug, input.parent.as_deref()).await {
+        return res;
+    }
+
+    let now = DateTime::now();
+    let genre = Genre {
+        id: None,
+        slug,
+        names,
+        parent: input.parent,
+        created_at: Some(now),
+        updated_at: Some(now),
+    };
+
+    match genre_collection.insert_one(&genre).await {
+        Ok(_) => HttpResponse::Created().json(genre),
+        Err(e) if is_duplicate_key(&e) => {
+            HttpResponse::Conflict().body("A genre with this slug already exists.")
+        }
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// PUT /genres/{slug}  — admin only
+///
+/// Replaces the names and parent; the slug stays, since movies reference it.
+pub async fn update_genre(
+    req: HttpRequest,
+    slug: web::Path<String>,
+    input: web::Json<GenreUpdate>,
+    genre_collection: web::Data<Collection<Genre>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let slug = slug.into_inner();
+    let input = input.into_inner();
+    let names = match check_names(input.names) {
+        Ok(names) => names,
+        Err(res) => return res,
+    };
+    if let Err(res) = check_parent(&genre_collection, &slug, input.parent.as_deref()).await {
+        return res;
+    }
+
+    let names = match mongodb::bson::to_bson(&names) {
+        Ok(names) => names,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let mut update = doc! { "$set": { "names": names, "updated_at": DateTime::now() } };
+    match input.parent {
+        Some(parent) => {
+            if let Ok(set) = update.get_document_mut("$set") {
+                set.insert("parent", parent);
+            }
+        }
+        None => {
+            update.insert("$unset", doc! { "parent": "" });
+        }
+    }
+
+    match genre_collection
+        .find_one_and_update(doc! { "slug": &slug }, update)
+        .return_document(ReturnDocument::After)
+        .await
+    {
+        Ok(Some(genre)) => HttpResponse::Ok().json(genre),
+        Ok(None) => HttpResponse::NotFound().body("Genre not found"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// DELETE /genres/{slug}  — admin only
+///
+/// Only unused genres can be deleted: no sub-genres, titles or lists may
+/// still reference it.
+pub async fn delete_genre(
+    req: HttpRequest,
+    slug: web::Path<String>,
+    genre_collection: web::Data<Collection<Genre>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let slug = slug.into_inner();
+    let children = genre_collection.count_documents(doc! { "parent": &slug });
+    let movies = movie_collection.count_documents(doc! { "genres": &slug });
+    let lists = list_collection.count_documents(doc! { "genre": &slug });
+    let (children, movies, lists) = match (children.await, movies.await, lists.await) {
+        (Ok(children), Ok(movies), Ok(lists)) => (children, movies, lists),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            return HttpResponse::InternalServerError().body(e.to_string())
+        }
+    };
+    if children + movies + lists > 0 {
+        return HttpResponse::Conflict().json(json!({
+            "error": "The genre is still in use.",
+            "children": children,
+            "movies": movies,
+            "lists": lists,
+        }));
+    }
+
+    match genre_collection.delete_one(doc! { "slug": &slug }).await {
+        Ok(result) if result.deleted_count == 0 => HttpResponse::NotFound().body("Genre not found"),
+        Ok(_) => HttpResponse::Ok().body("Genre deleted"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// POST /admin/genres/migrate  — admin only
+///
+/// Starts converting legacy free-text genres on movies and lists into
+/// taxonomy references, creating missing genres, and answers 202 with the
+/// run to poll. Safe to run repeatedly, one run at a time.
+pub async fn migrate_legacy_genres(
+    req: HttpRequest,
+    genre_collection: web::Data<Collection<Genre>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+    migration_collection: web::Data<Collection<GenreMigration>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let now = DateTime::now();
+    let job = GenreMigration {
+        id: Some(ObjectId::new()),
+        status: MigrationStatus::Running,
+        report: None,
+        failure: None,
+        actor: actor.clone(),
+        created_at: now,
+        updated_at: now,
+        finished_at: None,
+    };
+    let job_id = job.id.unwrap_or_default();
+    match migration_collection.insert_one(&job).await {
+        Ok(_) => {}
+        Err(e) if is_duplicate_key(&e) => {
+            return HttpResponse::Conflict().body("A genre migration is already running.")
+        }
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    actix_rt::spawn(run_migration(
+        genre_collection.get_ref().clone(),
+        movie_collection.clone_with_type(),
+        list_collection.clone_with_type(),
+        revision_collection.get_ref().clone(),
+        migration_collection.get_ref().clone(),
+        job_id,
+        actor,
+    ));
+
+    HttpResponse::Accepted().json(json!({
+        "job_id": job_id.to_hex(),
+        "status_url": format!("/api/admin/genres/migrate/{}", job_id.to_hex()),
+    }))
+}
+
+/// GET /admin/genres/migrate/{id}  — admin only
+pub async fn get_genre_migration(
+    req: HttpRequest,
+    job_id: web::Path<String>,
+    migration_collection: web::Data<Collection<GenreMigration>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let job_id = match ObjectId::parse_str(job_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid migration ID format."),
+    };
+
+    match migration_collection.find_one(doc! { "_id": job_id }).await {
+        Ok(Some(job)) => HttpResponse::Ok().json(job),
+        Ok(None) => HttpResponse::NotFound().body("Genre migration not found"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
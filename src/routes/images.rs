@@ -0,0 +1,125 @@
+use crate::images::{
+    cache_variant, format_from_name, render_variant, snap_width, variant_path, variant_widths,
+    RenderSlots,
+};
+use crate::storage::{parse_asset_key, AssetStorage};
+use actix_web::http::header::{self, EntityTag, IfNoneMatch};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use std::io::ErrorKind;
+use std::path::Path;
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ImageQuery {
+    /// ?w=640  — snapped to the nearest configured width; omit for full size
+    w: Option<u32>,
+
+    /// ?fmt=jpeg|png|webp|avif  — defaults to the uploaded format
+    fmt: Option<String>,
+}
+
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /images/{id}?w=&fmt=
+///
+/// `id` is the file name of an uploaded image (the last segment of its URL).
+/// Uploads pre-render every width in their own format; other variants are
+/// rendered on first request and cached on disk. Renders share a few slots
+/// (`RenderSlots`) and a miss that cannot get one in time is answered 503.
+/// Since ids are content hashes, every response can be cached indefinitely.
+pub async fn get_image(
+    req: HttpRequest,
+    id: web::Path<String>,
+    query: web::Query<ImageQuery>,
+    storage: web::Data<dyn AssetStorage>,
+    slots: web::Data<RenderSlots>,
+) -> HttpResponse {
+    let key = id.into_inner();
+    let Some(source_type) = parse_asset_key(&key) else {
+        return HttpResponse::NotFound().finish();
+    };
+    let format = match query.fmt.as_deref().map(format_from_name) {
+        None => source_type,
+        Some(Some(format)) => format,
+        Some(None) => {
+            return HttpResponse::BadRequest().body("fmt must be jpeg, png, webp or avif")
+        }
+    };
+    let width = query.w.map(|w| snap_width(w, &variant_widths()));
+
+    let size = width.map_or_else(|| "full".to_string(), |w| w.to_string());
+    let etag = EntityTag::new_strong(format!("{}-{}.{}", key, size, format.extension()));
+    let not_modified = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header((header::CACHE_CONTROL, IMMUTABLE))
+            .insert_header(header::ETag(etag))
+            .finish();
+    }
+
+    let respond = |bytes: Vec<u8>| {
+        HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header((header::CACHE_CONTROL, IMMUTABLE))
+            .insert_header(header::ETag(etag.clone()))
+            .body(bytes)
+    };
+
+    let path = variant_path(&key, width, format);
+    match read_cached(&path).await {
+        Ok(Some(bytes)) => return respond(bytes),
+        Ok(None) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let original = match storage.get(&key).await {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    // The full-size original in its own format needs no rendering or caching.
+    if width.is_none() && format == source_type {
+        return respond(original.to_vec());
+    }
+
+    let Some(_permit) = slots.acquire().await else {
+        return HttpResponse::ServiceUnavailable()
+            .insert_header((header::RETRY_AFTER, "5"))
+            .body("Too many images are being rendered; try again shortly");
+    };
+    // Whoever held the slot before may have rendered this very variant.
+    match read_cached(&path).await {
+        Ok(Some(bytes)) => return respond(bytes),
+        Ok(None) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let rendered = match web::block(move || render_variant(&original, width, format)).await {
+        Ok(Ok(bytes)) => bytes,
+        Ok(Err(e)) => return HttpResponse::UnprocessableEntity().body(e.to_string()),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    if let Err(e) = cache_variant(&path, &rendered).await {
+        log::warn!("Failed to cache image variant {}: {}", path.display(), e);
+    }
+
+    respond(rendered)
+}
+
+async fn read_cached(path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,205 @@
+use crate::import::{
+    existing_ids, is_stalled, parse_rows, run_import, ImportCollections, ImportFormat,
+    MAX_REPORTED_ERRORS,
+};
+use crate::models::import::{ImportJob, ImportStatus, RowError};
+use crate::routes::auth::{claims_email, require_admin};
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use mongodb::bson::{doc, oid::ObjectId, to_bson, DateTime};
+use mongodb::Collection;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    /// ?format=csv|ndjson  — defaults to the Content-Type
+    format: Option<String>,
+
+    /// ?dry_run=true  — validate and report without writing anything
+    #[serde(default)]
+    dry_run: bool,
+
+    /// ?job=<id>  — resume an interrupted import of the same file
+    job: Option<String>,
+}
+
+/// Largest accepted import file.
+pub const MAX_IMPORT_BYTES: usize = 32 * 1024 * 1024;
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// POST /admin/import/movies?format=&dry_run=&job=  — admin only
+///
+/// Body is a CSV file with a header row or NDJSON, one movie per line, using
+/// `Movie` field names. Rows are upserted by `external_id`; new titles start
+/// as drafts. Genre text is resolved to taxonomy slugs, creating missing
+/// genres. A resumed job must be sent the very same file. Returns 202 with a
+/// job id to poll, or the validation report straight away for a dry run.
+pub async fn import_movies(
+    req: HttpRequest,
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+    collections: web::Data<ImportCollections>,
+) -> HttpResponse {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let format = match &query.format {
+        Some(name) => ImportFormat::from_name(name),
+        None => ImportFormat::from_content_type(&content_type),
+    };
+    let Some(format) = format else {
+        return HttpResponse::UnsupportedMediaType()
+            .body("Send text/csv or application/x-ndjson, or pass ?format=csv|ndjson.");
+    };
+
+    let rows = match parse_rows(format, &body) {
+        Ok(rows) => rows,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    let total_rows = rows.len() as u64;
+    let content_hash = hex::encode(Sha256::digest(&body));
+    let errors: Vec<RowError> = rows
+        .iter()
+        .filter_map(|r| r.as_ref().err().cloned())
+        .collect();
+
+    if query.dry_run {
+        let ids: Vec<&str> = rows
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .map(|r| r.external_id.as_str())
+            .collect();
+        let existing = match existing_ids(&collections.movies, &ids).await {
+            Ok(existing) => existing.len(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        };
+
+        return HttpResponse::Ok().json(json!({
+            "dry_run": true,
+            "total_rows": total_rows,
+            "valid_rows": ids.len(),
+            "would_insert": ids.len() - existing,
+            "would_update": existing,
+            "error_count": errors.len(),
+            "errors": errors,
+        }));
+    }
+
+    let (job_id, start) = match &query.job {
+        Some(job_id) => {
+            let job_id = match ObjectId::parse_str(job_id) {
+                Ok(oid) => oid,
+                Err(_) => return HttpResponse::BadRequest().body("Invalid job ID format."),
+            };
+            let job = match collections.jobs.find_one(doc! { "_id": job_id }).await {
+                Ok(Some(job)) => job,
+                Ok(None) => return HttpResponse::NotFound().body("Import job not found"),
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            };
+            if job.status == ImportStatus::Completed {
+                return HttpResponse::Conflict().body("The import job has already completed");
+            }
+            if job.status == ImportStatus::Running && !is_stalled(&job) {
+                return HttpResponse::Conflict().body("The import job is still running");
+            }
+            if job.content_hash != content_hash || job.format != format.name() {
+                return HttpResponse::Conflict()
+                    .body("The file does not match the one the job was started with");
+            }
+
+            // Claims the job only if nobody else resumed it in the meantime.
+            let status = match to_bson(&job.status) {
+                Ok(status) => status,
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            };
+            let unchanged = doc! {
+                "_id": job_id,
+                "status": status,
+                "updated_at": job.updated_at,
+            };
+            let resumed = doc! {
+                "$set": { "status": "running", "updated_at": DateTime::now() },
+                "$unset": { "failure": "" },
+            };
+            match collections.jobs.update_one(unchanged, resumed).await {
+                Ok(result) if result.matched_count == 0 => {
+                    return HttpResponse::Conflict().body("The import job was resumed already")
+                }
+                Ok(_) => {}
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            }
+            (job_id, job.processed_rows as usize)
+        }
+        None => {
+            let now = DateTime::now();
+            let job = ImportJob {
+                id: None,
+                status: ImportStatus::Running,
+                format: format.name().to_string(),
+                content_hash,
+                total_rows,
+                processed_rows: 0,
+                inserted: 0,
+                updated: 0,
+                error_count: errors.len() as u64,
+                errors: errors.into_iter().take(MAX_REPORTED_ERRORS).collect(),
+                genres_created: Vec::new(),
+                failure: None,
+                actor: actor.clone(),
+                created_at: now,
+                updated_at: now,
+                finished_at: None,
+            };
+            match collections.jobs.insert_one(job).await {
+                Ok(result) => match result.inserted_id.as_object_id() {
+                    Some(oid) => (oid, 0),
+                    None => return HttpResponse::InternalServerError().finish(),
+                },
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            }
+        }
+    };
+
+    let c = collections.get_ref().clone();
+    actix_rt::spawn(run_import(c, actor, job_id, rows, start));
+
+    HttpResponse::Accepted().json(json!({
+        "job_id": job_id.to_hex(),
+        "status_url": format!("/api/admin/import/jobs/{}", job_id.to_hex()),
+    }))
+}
+
+/// GET /admin/import/jobs/{id}  — admin only
+pub async fn get_import_job(
+    req: HttpRequest,
+    job_id: web::Path<String>,
+    job_collection: web::Data<Collection<ImportJob>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let job_id = match ObjectId::parse_str(job_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid job ID format."),
+    };
+
+    match job_collection.find_one(doc! { "_id": job_id }).await {
+        Ok(Some(job)) => HttpResponse::Ok().json(job),
+        Ok(None) => HttpResponse::NotFound().body("Import job not found"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
@@ -1,10 +1,21 @@
-use crate::models::list::List;
-use crate::routes::auth::{require_admin, require_auth};
+use crate::catalog::{and, not_deleted, Viewer};
+use crate::genres::{list_genre_filter, slugify, unknown_genres};
+use crate::geo::{viewer, RegionResolver};
+use crate::locale::preferred_locales;
+use crate::models::genre::Genre;
+use crate::models::list::{ExpandedList, List, ListRule};
+use crate::models::movie::{within_age, Movie, MovieSummary};
+use crate::models::workflow::ContentStatus;
+use crate::routes::auth::{claims_email, claims_is_admin, require_admin, require_auth};
+use crate::smart_lists::{resolve_content, resolve_rule, validate_rule};
 use actix_web::{web, HttpRequest, HttpResponse};
-use bson::doc;
+use bson::{doc, oid::ObjectId, Bson, DateTime, Document};
 use futures_util::TryStreamExt;
+use mongodb::options::ReturnDocument;
 use mongodb::Collection;
 use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
 
 // ── Query param extractor ─────────────────────────────────────────────────────
 
@@ -16,6 +27,344 @@ pub struct ListQuery {
 
     /// ?genre=action
     genre: Option<String>,
+
+    /// ?expand=content  — inline movie summaries instead of bare ids
+    expand: Option<String>,
+
+    /// ?max_age=13  — with expand, drop titles rated for older viewers or not rated at all
+    max_age: Option<u32>,
+
+    /// ?sample=5  — return this many random matching lists (home-page rows)
+    sample: Option<u32>,
+
+    /// ?region=BR  — admins only: preview the lists as a user from that region
+    region: Option<String>,
+}
+
+/// Largest `?sample=` honoured, so one request cannot pull the whole collection at random.
+const MAX_SAMPLE: u32 = 50;
+
+/// Builds the `get_lists` filter. The model stores the media type as `type_list`;
+/// the genre may be given as a slug or as legacy text.
+fn list_filter(query: &ListQuery) -> Document {
+    let mut filter = Document::new();
+    if let Some(t) = &query.media_type {
+        filter.insert("type_list", t);
+    }
+    if let Some(g) = &query.genre {
+        filter.extend(list_genre_filter(g));
+    }
+    filter
+}
+
+/// `$match` on the query filter and what the viewer may see, followed by
+/// `$sample` when a random selection was asked for.
+fn list_pipeline(query: &ListQuery, viewer: &Viewer) -> Vec<Document> {
+    let filter = and(list_filter(query), viewer.list_filter());
+    let mut pipeline = vec![doc! { "$match": filter }];
+    if let Some(size) = query.sample {
+        pipeline.push(doc! { "$sample": { "size": size.clamp(1, MAX_SAMPLE) as i64 } });
+    }
+    pipeline
+}
+
+// ── Update inputs ─────────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ListPatch {
+    pub title: Option<String>,
+    pub type_list: Option<String>,
+    pub genre: Option<String>,
+    pub version: i64,
+}
+
+#[derive(Deserialize)]
+pub struct AddItemInput {
+    pub movie_id: String,
+    /// Insert position; appends when omitted.
+    pub position: Option<i32>,
+    pub version: i64,
+}
+
+#[derive(Deserialize)]
+pub struct MoveItemInput {
+    pub movie_id: String,
+    pub to: usize,
+    pub version: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ReplaceItemInput {
+    pub movie_id: String,
+    pub version: i64,
+}
+
+#[derive(Deserialize)]
+pub struct VersionQuery {
+    pub version: i64,
+}
+
+// ── Versioned writes ──────────────────────────────────────────────────────────
+
+fn parse_list_id(raw: String) -> Result<ObjectId, HttpResponse> {
+    ObjectId::parse_str(raw).map_err(|_| HttpResponse::BadRequest().body("Invalid list ID format."))
+}
+
+/// Matches the list only if it is still at `version`, not in the trash and
+/// not an activity chart. Lists created before versioning have no field at
+/// all, which counts as version 0.
+fn version_filter(list_id: ObjectId, version: i64) -> Document {
+    if version == 0 {
+        doc! {
+            "_id": list_id,
+            "version": { "$in": [0_i64, null] },
+            "deleted_at": null,
+            "chart": null,
+        }
+    } else {
+        doc! { "_id": list_id, "version": version, "deleted_at": null, "chart": null }
+    }
+}
+
+/// Explains why a versioned write matched nothing: a missing list (404),
+/// a computed chart or a stale version (409, the latter with the current
+/// version) or a failed precondition (409 with `precondition_msg`).
+async fn explain_miss(
+    list_collection: &Collection<List>,
+    list_id: ObjectId,
+    version: i64,
+    precondition_msg: &str,
+) -> HttpResponse {
+    match list_collection.find_one(doc! { "_id": list_id }).await {
+        Ok(None) => HttpResponse::NotFound().body("List not found"),
+        Ok(Some(current)) if current.deleted_at.is_some() => {
+            HttpResponse::NotFound().body("List not found")
+        }
+        Ok(Some(current)) if current.chart.is_some() => HttpResponse::Conflict()
+            .body("Charts are computed from viewing activity; pin or exclude titles instead."),
+        Ok(Some(current)) if current.version != version => HttpResponse::Conflict().json(json!({
+            "error": "The list was modified by someone else.",
+            "current_version": current.version,
+        })),
+        Ok(Some(_)) => HttpResponse::Conflict().body(precondition_msg.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Applies `update` to the list if it is still at `version` (and matches
+/// `precondition`), bumping the version and returning the updated list.
+async fn versioned_update(
+    list_collection: &Collection<List>,
+    list_id: ObjectId,
+    version: i64,
+    precondition: Document,
+    mut update: Document,
+    precondition_msg: &str,
+) -> HttpResponse {
+    let mut filter = version_filter(list_id, version);
+    filter.extend(precondition);
+
+    let mut set = update.get_document("$set").cloned().unwrap_or_default();
+    set.insert("updated_at", DateTime::now());
+    update.insert("$set", set);
+    update.insert("$inc", doc! { "version": 1_i64 });
+
+    match list_collection
+        .find_one_and_update(filter, update)
+        .return_document(ReturnDocument::After)
+        .await
+    {
+        Ok(Some(list)) => HttpResponse::Ok().json(list),
+        Ok(None) => explain_miss(list_collection, list_id, version, precondition_msg).await,
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+// ── Content references ────────────────────────────────────────────────────────
+
+/// Returns the ids in `content` that do not reference an existing movie.
+/// Movies in the trash count as missing.
+async fn missing_movies(
+    movie_collection: &Collection<Movie>,
+    content: &[String],
+) -> mongodb::error::Result<Vec<String>> {
+    let oids: Vec<ObjectId> = content
+        .iter()
+        .filter_map(|id| ObjectId::parse_str(id).ok())
+        .collect();
+
+    let found: HashSet<String> = movie_collection
+        .clone_with_type::<Document>()
+        .find(and(doc! { "_id": { "$in": oids } }, not_deleted()))
+        .projection(doc! { "_id": 1 })
+        .await?
+        .try_collect::<Vec<Document>>()
+        .await?
+        .iter()
+        .filter_map(|movie| movie.get_object_id("_id").ok())
+        .map(|oid| oid.to_hex())
+        .collect();
+
+    Ok(content
+        .iter()
+        .filter(|id| !found.contains(*id))
+        .cloned()
+        .collect())
+}
+
+/// Rejects list content that points at movies which do not exist.
+async fn check_references(
+    movie_collection: &Collection<Movie>,
+    content: &[String],
+) -> Result<(), HttpResponse> {
+    match missing_movies(movie_collection, content).await {
+        Ok(missing) if missing.is_empty() => Ok(()),
+        Ok(missing) => Err(HttpResponse::BadRequest().json(json!({
+            "error": "Some list items do not reference existing movies.",
+            "missing": missing,
+        }))),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+/// Normalizes a list's genre to its taxonomy slug, rejecting unknown genres.
+async fn check_genre(
+    genre_collection: &Collection<Genre>,
+    genre: Option<String>,
+) -> Result<Option<String>, HttpResponse> {
+    let Some(slug) = genre.as_deref().map(slugify) else {
+        return Ok(None);
+    };
+    match unknown_genres(genre_collection, std::slice::from_ref(&slug)).await {
+        Ok(unknown) if unknown.is_empty() => Ok(Some(slug)),
+        Ok(_) => Err(HttpResponse::BadRequest().body(format!("Unknown genre: {}", slug))),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+/// Orders the looked-up movies as they appear in `list.content`, applies the
+/// maturity cap and localizes the list and its items.
+fn expand_list(
+    mut list: List,
+    items: Vec<MovieSummary>,
+    max_age: Option<u32>,
+    locales: &[String],
+) -> ExpandedList {
+    list.localize(locales);
+    let mut by_id: HashMap<String, MovieSummary> = items
+        .into_iter()
+        .filter_map(|mut movie| {
+            movie.localize(locales);
+            Some((movie.id?.to_hex(), movie))
+        })
+        .collect();
+
+    let items = list
+        .content
+        .iter()
+        .filter_map(|id| by_id.remove(id))
+        .filter(|movie| within_age(movie.limit.as_deref(), max_age))
+        .collect();
+
+    ExpandedList { list, items }
+}
+
+/// Stages joining a list's content with the movies that match `movie_filter`,
+/// as `items` read with `projection`.
+fn items_lookup(movie_filter: Document, projection: Document) -> Vec<Document> {
+    vec![
+        // Joining on the ObjectId form lets the lookup use the movies' `_id` index.
+        doc! {
+            "$addFields": {
+                "content_oids": { "$map": {
+                    "input": "$content",
+                    "in": { "$convert": { "input": "$$this", "to": "objectId", "onError": null } },
+                } },
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "movies",
+                "localField": "content_oids",
+                "foreignField": "_id",
+                "pipeline": [
+                    { "$match": movie_filter },
+                    { "$project": projection },
+                ],
+                "as": "items",
+            }
+        },
+        doc! { "$unset": "content_oids" },
+    ]
+}
+
+/// Drops the ids of titles the viewer may not see from a list's content, so
+/// bare-id rows do not reveal titles unavailable in the caller's region.
+fn retain_visible(list: &mut List, items: &[Document]) {
+    let visible: HashSet<String> = items
+        .iter()
+        .filter_map(|item| item.get_object_id("_id").ok())
+        .map(|oid| oid.to_hex())
+        .collect();
+    list.content.retain(|id| visible.contains(id));
+}
+
+/// The lists matching `query` as `viewer` sees them. Each list's content is
+/// joined with the movies the viewer may watch in a single `$lookup`, either
+/// to inline their summaries (`?expand=content`) or to drop the others' ids.
+async fn find_lists(
+    list_collection: &Collection<List>,
+    query: &ListQuery,
+    viewer: &Viewer,
+    locales: &[String],
+) -> HttpResponse {
+    let expand = query.expand.as_deref() == Some("content");
+    let projection = if expand {
+        MovieSummary::projection()
+    } else {
+        doc! { "_id": 1 }
+    };
+    let mut pipeline = list_pipeline(query, viewer);
+    pipeline.extend(items_lookup(viewer.movie_filter(), projection));
+
+    let rows = match list_collection.aggregate(pipeline).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(rows) => rows,
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let mut lists = Vec::with_capacity(rows.len());
+    let mut expanded = Vec::with_capacity(rows.len());
+    for mut row in rows {
+        let items = row.remove("items").unwrap_or(Bson::Array(Vec::new()));
+        let mut list = match bson::from_document::<List>(row) {
+            Ok(list) => list,
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        };
+        if expand {
+            let items = match bson::from_bson::<Vec<MovieSummary>>(items) {
+                Ok(items) => items,
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            };
+            expanded.push(expand_list(list, items, query.max_age, locales));
+        } else {
+            let items = match bson::from_bson::<Vec<Document>>(items) {
+                Ok(items) => items,
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            };
+            retain_visible(&mut list, &items);
+            list.localize(locales);
+            lists.push(list);
+        }
+    }
+
+    if expand {
+        HttpResponse::Ok().json(expanded)
+    } else {
+        HttpResponse::Ok().json(lists)
+    }
 }
 
 // ── Handlers ──────────────────────────────────────────────────────────────────
@@ -25,59 +374,672 @@ pub async fn create_list(
     req: HttpRequest,
     list_data: web::Json<List>,
     list_collection: web::Data<Collection<List>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    genre_collection: web::Data<Collection<Genre>>,
 ) -> HttpResponse {
     if let Err(res) = require_admin(req).await {
         return res;
     }
 
-    match list_collection.insert_one(list_data.into_inner()).await {
+    // New lists start as drafts and go live through `POST /lists/{id}/status`.
+    let mut list = list_data.into_inner();
+    list.status = Some(ContentStatus::Draft);
+    list.version = 0;
+    list.chart = None;
+    list.translations.clear();
+    list.genre = match check_genre(&genre_collection, list.genre).await {
+        Ok(genre) => genre,
+        Err(res) => return res,
+    };
+    match &list.rule {
+        // Smart lists are resolved up front; the refresher keeps them current.
+        Some(rule) => {
+            if let Err(msg) = validate_rule(rule) {
+                return HttpResponse::BadRequest().body(msg);
+            }
+            list.content = match resolve_content(&movie_collection, rule).await {
+                Ok(content) => content,
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            };
+            list.resolved_at = Some(DateTime::now());
+        }
+        None => {
+            if let Err(res) = check_references(&movie_collection, &list.content).await {
+                return res;
+            }
+        }
+    }
+
+    match list_collection.insert_one(list).await {
         Ok(result) => HttpResponse::Created().json(result.inserted_id),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
 }
 
 /// DELETE /lists/{id}  — admin only
+///
+/// Moves the list to the trash; see `/api/trash` to restore it.
 pub async fn delete_list(
     req: HttpRequest,
     list_id: web::Path<String>,
     list_collection: web::Data<Collection<List>>,
 ) -> HttpResponse {
-    if let Err(res) = require_admin(req).await {
-        return res;
-    }
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let list_id = match parse_list_id(list_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    let trash = doc! {
+        "$set": { "deleted_at": DateTime::now(), "deleted_by": actor },
+        "$inc": { "version": 1_i64 },
+    };
 
     match list_collection
-        .delete_one(doc! { "_id": list_id.into_inner() })
+        .update_one(and(doc! { "_id": list_id }, not_deleted()), trash)
         .await
     {
-        Ok(result) if result.deleted_count == 0 => HttpResponse::NotFound().body("List not found"),
-        Ok(_) => HttpResponse::Ok().body("The list has been deleted"),
+        Ok(result) if result.matched_count == 0 => HttpResponse::NotFound().body("List not found"),
+        Ok(_) => HttpResponse::Ok().body("The list has been moved to the trash"),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
 }
 
-/// GET /lists?type=movie&genre=action  — any authenticated user
+/// GET /lists?type=movie&genre=action&sample=5&expand=content&max_age=13  — any authenticated user
+///
+/// Admins also see scheduled lists and titles, unless previewing with `?region=`.
+/// Titles are in the caller's language.
 pub async fn get_lists(
     req: HttpRequest,
     query: web::Query<ListQuery>,
     list_collection: web::Data<Collection<List>>,
+    region_resolver: web::Data<RegionResolver>,
 ) -> HttpResponse {
-    if let Err(res) = require_auth(req).await {
+    let claims = match require_auth(req.clone()).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    let is_admin = claims_is_admin(&claims);
+    let viewer = viewer(&req, &region_resolver, is_admin, query.region.as_deref());
+    let locales = preferred_locales(&req, &claims, None);
+
+    find_lists(&list_collection, &query, &viewer, &locales).await
+}
+
+/// PUT /lists/{id}  — admin only
+///
+/// Replaces title, type, genre and either the static content or the smart-list
+/// rule. The body's `version` must match the stored one.
+pub async fn replace_list(
+    req: HttpRequest,
+    list_id: web::Path<String>,
+    list_data: web::Json<List>,
+    list_collection: web::Data<Collection<List>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    genre_collection: web::Data<Collection<Genre>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
         return res;
     }
 
-    let filter = match (&query.media_type, &query.genre) {
-        (Some(t), Some(g)) => doc! { "type": t, "genre": g },
-        (Some(t), None) => doc! { "type": t },
-        (None, Some(g)) => doc! { "genre": g },
-        (None, None) => doc! {},
+    let list_id = match parse_list_id(list_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(res) => return res,
     };
 
-    match list_collection.find(filter).await {
-        Ok(cursor) => match cursor.try_collect::<Vec<List>>().await {
-            Ok(lists) => HttpResponse::Ok().json(lists),
-            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
-        },
+    let list = list_data.into_inner();
+    let genre = match check_genre(&genre_collection, list.genre).await {
+        Ok(genre) => genre,
+        Err(res) => return res,
+    };
+    let mut set = doc! {
+        "title": list.title,
+        "type_list": list.type_list,
+        "genre": genre,
+    };
+
+    let unset = match &list.rule {
+        Some(rule) => {
+            if let Err(msg) = validate_rule(rule) {
+                return HttpResponse::BadRequest().body(msg);
+            }
+            let content = match resolve_content(&movie_collection, rule).await {
+                Ok(content) => content,
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            };
+            let rule = match bson::to_bson(rule) {
+                Ok(bson) => bson,
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            };
+            set.insert("rule", rule);
+            set.insert("content", content);
+            set.insert("resolved_at", DateTime::now());
+            Document::new()
+        }
+        None => {
+            if let Err(res) = check_references(&movie_collection, &list.content).await {
+                return res;
+            }
+            set.insert("content", list.content);
+            doc! { "rule": "", "resolved_at": "" }
+        }
+    };
+    let mut update = doc! { "$set": set };
+    if !unset.is_empty() {
+        update.insert("$unset", unset);
+    }
+
+    versioned_update(&list_collection, list_id, list.version, doc! {}, update, "").await
+}
+
+/// PATCH /lists/{id}  — admin only
+///
+/// Updates only the metadata fields present in the body.
+pub async fn patch_list(
+    req: HttpRequest,
+    list_id: web::Path<String>,
+    patch: web::Json<ListPatch>,
+    list_collection: web::Data<Collection<List>>,
+    genre_collection: web::Data<Collection<Genre>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let list_id = match parse_list_id(list_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    let patch = patch.into_inner();
+    let mut set = Document::new();
+    if let Some(title) = patch.title {
+        set.insert("title", title);
+    }
+    if let Some(type_list) = patch.type_list {
+        set.insert("type_list", type_list);
+    }
+    match check_genre(&genre_collection, patch.genre).await {
+        Ok(Some(genre)) => {
+            set.insert("genre", genre);
+        }
+        Ok(None) => {}
+        Err(res) => return res,
+    }
+
+    versioned_update(
+        &list_collection,
+        list_id,
+        patch.version,
+        doc! {},
+        doc! { "$set": set },
+        "",
+    )
+    .await
+}
+
+/// POST /lists/{id}/items  — admin only
+pub async fn add_list_item(
+    req: HttpRequest,
+    list_id: web::Path<String>,
+    input: web::Json<AddItemInput>,
+    list_collection: web::Data<Collection<List>>,
+    movie_collection: web::Data<Collection<Movie>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let list_id = match parse_list_id(list_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    if let Err(res) =
+        check_references(&movie_collection, std::slice::from_ref(&input.movie_id)).await
+    {
+        return res;
+    }
+
+    let mut push = doc! { "$each": [&input.movie_id] };
+    if let Some(position) = input.position {
+        push.insert("$position", position);
+    }
+
+    versioned_update(
+        &list_collection,
+        list_id,
+        input.version,
+        doc! { "content": { "$ne": &input.movie_id }, "rule": null },
+        doc! { "$push": { "content": push } },
+        "The movie is already in the list, or the list is rule-based.",
+    )
+    .await
+}
+
+/// DELETE /lists/{id}/items/{movie_id}?version=  — admin only
+pub async fn remove_list_item(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<VersionQuery>,
+    list_collection: web::Data<Collection<List>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let (list_id, movie_id) = path.into_inner();
+    let list_id = match parse_list_id(list_id) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    versioned_update(
+        &list_collection,
+        list_id,
+        query.version,
+        doc! { "content": &movie_id, "rule": null },
+        doc! { "$pull": { "content": &movie_id } },
+        "The movie is not in the list, or the list is rule-based.",
+    )
+    .await
+}
+
+/// PUT /lists/{id}/items/{movie_id}  — admin only
+///
+/// Swaps one title for another in place, keeping its position.
+pub async fn replace_list_item(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    input: web::Json<ReplaceItemInput>,
+    list_collection: web::Data<Collection<List>>,
+    movie_collection: web::Data<Collection<Movie>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let (list_id, movie_id) = path.into_inner();
+    let list_id = match parse_list_id(list_id) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    if let Err(res) =
+        check_references(&movie_collection, std::slice::from_ref(&input.movie_id)).await
+    {
+        return res;
+    }
+
+    let list = match list_collection
+        .find_one(version_filter(list_id, input.version))
+        .await
+    {
+        Ok(Some(list)) => list,
+        Ok(None) => return explain_miss(&list_collection, list_id, input.version, "").await,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    if list.rule.is_some() {
+        return HttpResponse::Conflict().body("Rule-based lists cannot be edited by hand.");
+    }
+    if list.content.contains(&input.movie_id) {
+        return HttpResponse::Conflict().body("The replacement is already in the list.");
+    }
+    let Some(index) = list.content.iter().position(|id| id == &movie_id) else {
+        return HttpResponse::Conflict().body("The movie is not in the list.");
+    };
+
+    // The index was read at `version`, which the write checks again, so it
+    // still points at `movie_id` when the update lands.
+    let field = format!("content.{}", index);
+    versioned_update(
+        &list_collection,
+        list_id,
+        input.version,
+        doc! { &field: &movie_id },
+        doc! { "$set": { &field: &input.movie_id } },
+        "",
+    )
+    .await
+}
+
+/// POST /lists/{id}/items/move  — admin only
+///
+/// Moves a title to index `to`. The new order is computed from the version
+/// the caller read, so the version check keeps the write atomic.
+pub async fn move_list_item(
+    req: HttpRequest,
+    list_id: web::Path<String>,
+    input: web::Json<MoveItemInput>,
+    list_collection: web::Data<Collection<List>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let list_id = match parse_list_id(list_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    let list = match list_collection
+        .find_one(version_filter(list_id, input.version))
+        .await
+    {
+        Ok(Some(list)) => list,
+        Ok(None) => return explain_miss(&list_collection, list_id, input.version, "").await,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    if list.rule.is_some() {
+        return HttpResponse::Conflict().body("Rule-based lists cannot be reordered by hand.");
+    }
+
+    let mut content = list.content;
+    let from = match content.iter().position(|id| id == &input.movie_id) {
+        Some(index) => index,
+        None => return HttpResponse::Conflict().body("The movie is not in the list."),
+    };
+    if input.to >= content.len() {
+        return HttpResponse::BadRequest().body("Target position is out of range.");
+    }
+    let item = content.remove(from);
+    content.insert(input.to, item);
+
+    versioned_update(
+        &list_collection,
+        list_id,
+        input.version,
+        doc! {},
+        doc! { "$set": { "content": content } },
+        "",
+    )
+    .await
+}
+
+/// POST /lists/preview  — admin only
+///
+/// Runs a smart-list rule without saving it and returns the titles it would contain.
+pub async fn preview_smart_list(
+    req: HttpRequest,
+    rule: web::Json<ListRule>,
+    movie_collection: web::Data<Collection<Movie>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    if let Err(msg) = validate_rule(&rule) {
+        return HttpResponse::BadRequest().body(msg);
+    }
+
+    match resolve_rule(&movie_collection, &rule).await {
+        Ok(movies) => HttpResponse::Ok().json(movies),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_database;
+    use actix_web::body::to_bytes;
+    use serde_json::Value;
+
+    const TYPES: [Option<&str>; 2] = [None, Some("movie")];
+    const GENRES: [Option<&str>; 2] = [None, Some("Action")];
+    const SAMPLES: [Option<u32>; 2] = [None, Some(2)];
+    const EXPANDS: [Option<&str>; 2] = [None, Some("content")];
+    const MAX_AGES: [Option<u32>; 2] = [None, Some(13)];
+
+    /// Every combination of the `ListQuery` parameters.
+    fn all_queries() -> Vec<ListQuery> {
+        let mut queries = Vec::new();
+        for media_type in TYPES {
+            for genre in GENRES {
+                for sample in SAMPLES {
+                    for expand in EXPANDS {
+                        for max_age in MAX_AGES {
+                            queries.push(ListQuery {
+                                media_type: media_type.map(str::to_string),
+                                genre: genre.map(str::to_string),
+                                expand: expand.map(str::to_string),
+                                max_age,
+                                sample,
+                                region: None,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        queries
+    }
+
+    fn user() -> Viewer {
+        Viewer {
+            region: Some("BR".to_string()),
+            unrestricted: false,
+        }
+    }
+
+    #[test]
+    fn filter_uses_the_stored_type_field() {
+        for query in all_queries() {
+            let filter = list_filter(&query);
+            assert!(!filter.contains_key("type"), "filters on the old field");
+            assert_eq!(
+                filter.get_str("type_list").ok(),
+                query.media_type.as_deref()
+            );
+            match &query.genre {
+                Some(genre) => assert_eq!(
+                    filter.get_document("genre").unwrap(),
+                    &doc! { "$in": [genre, "action"] }
+                ),
+                None => assert!(!filter.contains_key("genre")),
+            }
+        }
+    }
+
+    #[test]
+    fn pipeline_matches_then_samples() {
+        let viewer = user();
+        for query in all_queries() {
+            let pipeline = list_pipeline(&query, &viewer);
+            let expected_match = and(list_filter(&query), viewer.list_filter());
+            let matched = pipeline[0].get_document("$match").unwrap();
+            // The visibility filter carries the current time; compare its shape.
+            assert_eq!(
+                matched.keys().collect::<Vec<_>>(),
+                expected_match.keys().collect::<Vec<_>>()
+            );
+            match query.sample {
+                Some(size) => {
+                    assert_eq!(pipeline.len(), 2);
+                    assert_eq!(pipeline[1], doc! { "$sample": { "size": size as i64 } });
+                }
+                None => assert_eq!(pipeline.len(), 1),
+            }
+        }
+    }
+
+    #[test]
+    fn sample_size_is_clamped() {
+        let mut query = all_queries().remove(0);
+        for (asked, used) in [(0, 1), (7, 7), (500, MAX_SAMPLE as i64)] {
+            query.sample = Some(asked);
+            let pipeline = list_pipeline(&query, &user());
+            assert_eq!(pipeline[1], doc! { "$sample": { "size": used } });
+        }
+    }
+
+    #[test]
+    fn lookup_joins_on_object_ids_and_restricts_movies() {
+        let stages = items_lookup(doc! { "deleted_at": null }, doc! { "_id": 1 });
+        assert!(stages[0].get_document("$addFields").is_ok());
+        let lookup = stages[1].get_document("$lookup").unwrap();
+        assert_eq!(lookup.get_str("from").unwrap(), "movies");
+        assert_eq!(lookup.get_str("localField").unwrap(), "content_oids");
+        assert_eq!(lookup.get_str("foreignField").unwrap(), "_id");
+        assert_eq!(lookup.get_str("as").unwrap(), "items");
+        let inner = lookup.get_array("pipeline").unwrap();
+        assert_eq!(
+            inner[0],
+            Bson::Document(doc! { "$match": { "deleted_at": null } })
+        );
+        assert_eq!(inner[1], Bson::Document(doc! { "$project": { "_id": 1 } }));
+        assert_eq!(stages[2], doc! { "$unset": "content_oids" });
+    }
+
+    #[test]
+    fn bare_rows_keep_only_visible_ids() {
+        let (a, b, c) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        let mut list: List = bson::from_document(doc! {
+            "title": "Row",
+            "content": [a.to_hex(), b.to_hex(), c.to_hex()],
+        })
+        .unwrap();
+        retain_visible(&mut list, &[doc! { "_id": c }, doc! { "_id": a }]);
+        assert_eq!(list.content, vec![a.to_hex(), c.to_hex()]);
+    }
+
+    fn summary(id: ObjectId, limit: Option<&str>) -> MovieSummary {
+        bson::from_document(doc! { "_id": id, "title": id.to_hex(), "limit": limit }).unwrap()
+    }
+
+    #[test]
+    fn expansion_keeps_list_order_and_applies_the_age_cap() {
+        let (a, b, c) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        let list: List = bson::from_document(doc! {
+            "title": "Row",
+            "content": [c.to_hex(), a.to_hex(), b.to_hex()],
+        })
+        .unwrap();
+        let items = vec![
+            summary(a, Some("PG")),
+            summary(b, Some("R")),
+            summary(c, None),
+        ];
+
+        let all = expand_list(list.clone(), items.clone(), None, &[]);
+        let ids: Vec<ObjectId> = all.items.iter().filter_map(|movie| movie.id).collect();
+        assert_eq!(ids, vec![c, a, b]);
+
+        let kids = expand_list(list, items, Some(13), &[]);
+        let ids: Vec<ObjectId> = kids.items.iter().filter_map(|movie| movie.id).collect();
+        assert_eq!(ids, vec![a]);
+    }
+
+    /// Runs every parameter combination through `find_lists` against the
+    /// server at `TEST_MONGODB_URL`.
+    #[actix_web::test]
+    #[ignore = "needs MongoDB at TEST_MONGODB_URL; run with --ignored"]
+    async fn every_query_combination_against_mongodb() {
+        let db = test_database().await;
+        let movies = db.collection::<Document>("movies");
+        let lists = db.collection::<List>("lists");
+
+        let (pg, r, draft) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        movies
+            .insert_many([
+                doc! { "_id": pg, "title": "PG", "limit": "PG" },
+                doc! { "_id": r, "title": "R", "limit": "R" },
+                doc! { "_id": draft, "title": "Draft", "limit": "G", "status": "draft" },
+            ])
+            .await
+            .unwrap();
+        let content = vec![pg.to_hex(), r.to_hex(), draft.to_hex()];
+        let row = |title: &str, type_list: &str, genre: &str| {
+            doc! { "title": title, "type_list": type_list, "genre": genre, "content": &content }
+        };
+        let mut old_field = row("series-action", "series", "action");
+        // The old filter read `type`; only `type_list` may decide the match.
+        old_field.insert("type", "movie");
+        let mut draft_list = row("hidden", "movie", "action");
+        draft_list.insert("status", "draft");
+        lists
+            .clone_with_type::<Document>()
+            .insert_many([
+                row("movie-action", "movie", "action"),
+                row("movie-legacy", "movie", "Action"),
+                row("movie-drama", "movie", "drama"),
+                old_field,
+                draft_list,
+            ])
+            .await
+            .unwrap();
+
+        for query in all_queries() {
+            let res = find_lists(&lists, &query, &user(), &[]).await;
+            assert!(res.status().is_success());
+            let body: Vec<Value> =
+                serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+
+            let expected: Vec<&str> = [
+                "movie-action",
+                "movie-legacy",
+                "movie-drama",
+                "series-action",
+            ]
+            .into_iter()
+            .filter(|title| query.media_type.is_none() || title.starts_with("movie"))
+            .filter(|title| query.genre.is_none() || !title.ends_with("drama"))
+            .collect();
+            let titles: Vec<&str> = body
+                .iter()
+                .map(|list| list["title"].as_str().unwrap())
+                .collect();
+            match query.sample {
+                Some(size) => {
+                    assert_eq!(titles.len(), expected.len().min(size as usize));
+                    assert!(titles.iter().all(|title| expected.contains(title)));
+                }
+                None => {
+                    let mut sorted = titles.clone();
+                    sorted.sort_unstable();
+                    let mut wanted = expected.clone();
+                    wanted.sort_unstable();
+                    assert_eq!(sorted, wanted);
+                }
+            }
+
+            for list in &body {
+                match query.expand {
+                    Some(_) => {
+                        let items: Vec<&str> = list["items"]
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .map(|item| item["title"].as_str().unwrap())
+                            .collect();
+                        let wanted = if query.max_age.is_some() {
+                            vec!["PG"]
+                        } else {
+                            vec!["PG", "R"]
+                        };
+                        assert_eq!(items, wanted);
+                    }
+                    None => {
+                        assert!(list.get("items").is_none());
+                        // The draft title's id must not leak through bare rows.
+                        let ids: Vec<&str> = list["content"]
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .map(|id| id.as_str().unwrap())
+                            .collect();
+                        assert_eq!(ids, vec![pg.to_hex(), r.to_hex()]);
+                    }
+                }
+            }
+        }
+
+        db.drop().await.unwrap();
+    }
+}
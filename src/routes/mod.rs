@@ -1,5 +1,25 @@
 // Define the routes for the authentication, lists, movies and users
+pub mod assets;
 pub mod auth;
+pub mod charts;
+pub mod credits;
+pub mod export;
+pub mod genres;
+pub mod images;
+pub mod import;
 pub mod lists;
+pub mod ratings;
 pub mod movies;
-pub mod users;
\ No newline at end of file
+pub mod people;
+pub mod recommendations;
+pub mod reviews;
+pub mod revisions;
+pub mod search;
+pub mod similar;
+pub mod streams;
+pub mod subtitles;
+pub mod trash;
+pub mod translations;
+pub mod users;
+pub mod watchlist;
+pub mod workflow;
\ No newline at end of file
@@ -1,35 +1,175 @@
-use crate::routes::auth::{require_admin, require_auth};
-use crate::models::movie::Movie;
+use crate::catalog::{and, not_deleted};
+use crate::credits::movie_credits;
+use crate::featured::{Candidate, FeaturedPolicy};
+use crate::genres::{movie_genre_filter, unknown_genres};
+use crate::geo::{normalize_regions, user_viewer, viewer, RegionResolver};
+use crate::locale::preferred_locales;
+use crate::routes::auth::{
+    claims_email, claims_is_admin, claims_role, require_admin, require_auth,
+};
+use crate::models::list::List;
+use crate::models::featured::FeaturedImpression;
+use crate::models::genre::Genre;
+use crate::models::movie::{within_age, Movie, METADATA_FIELDS};
+use crate::models::person::{CastMember, Credit, Person};
+use crate::models::rating::RatingSummary;
+use crate::models::recommendation::ItemModel;
+use crate::models::revision::MovieRevision;
+use crate::models::workflow::{ContentStatus, EditorialRole};
+use crate::revisions::{diff, record_revision, write_movie, RevisionError};
+use crate::search::movie_search_keys;
+use crate::smart_lists::invalidate_smart_lists;
 use actix_web::{web, HttpRequest, HttpResponse};
 use futures_util::TryStreamExt;
-use mongodb::{bson::doc, Collection};
-use serde::Deserialize;
+use mongodb::bson::{doc, from_document, oid::ObjectId, to_document, DateTime, Document};
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
 
 // ── Query param extractor ─────────────────────────────────────────────────────
 
 #[derive(Deserialize)]
-pub struct MovieTypeQuery {
-    /// ?type=series  or  ?type=movie
+pub struct FeaturedQuery {
+    /// ?type=series  or  ?type=movie  — movies when omitted
     #[serde(rename = "type")]
     media_type: Option<String>,
+
+    /// ?genre=action
+    genre: Option<String>,
+
+    /// ?max_age=13  — drop titles rated for older viewers or not rated at all
+    max_age: Option<u32>,
+
+    /// ?count=5  — return this many distinct titles as an array (at most 10)
+    count: Option<usize>,
+
+    /// ?region=BR  — admins only: preview as a user from that region
+    region: Option<String>,
+}
+
+/// Largest `?count=` honoured for featured titles.
+const MAX_FEATURED: usize = 10;
+
+/// Titles drawn into the featured pool from each of the newest, the boosted,
+/// the popular and a random sample.
+const FEATURED_POOL: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct RegionQuery {
+    /// ?region=BR  — admins only: preview as a user from that region
+    region: Option<String>,
+}
+
+/// The movie detail: the title with its cast and crew.
+#[derive(Serialize)]
+pub struct MovieDetail {
+    #[serde(flatten)]
+    movie: Movie,
+
+    credits: Vec<CastMember>,
+}
+
+// ── Genre references ──────────────────────────────────────────────────────────
+
+/// Rejects genre slugs that are not in the taxonomy.
+async fn check_genres(
+    genre_collection: &Collection<Genre>,
+    genres: &[String],
+) -> Result<(), HttpResponse> {
+    match unknown_genres(genre_collection, genres).await {
+        Ok(unknown) if unknown.is_empty() => Ok(()),
+        Ok(unknown) => Err(HttpResponse::BadRequest().json(json!({
+            "error": "Some genres are not in the taxonomy.",
+            "unknown": unknown,
+        }))),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+// ── Region codes ──────────────────────────────────────────────────────────────
+
+/// Normalizes the licensing region lists in place, so `us` matches a
+/// resolved `US`; invalid codes are rejected.
+fn check_regions(movie: &mut Movie) -> Result<(), HttpResponse> {
+    let normalize = |codes: &mut Vec<String>| -> Result<(), HttpResponse> {
+        *codes = normalize_regions(std::mem::take(codes))
+            .map_err(|e| HttpResponse::BadRequest().body(e))?;
+        Ok(())
+    };
+    normalize(&mut movie.allowed_regions)?;
+    normalize(&mut movie.blocked_regions)
 }
 
 // ── Handlers ──────────────────────────────────────────────────────────────────
 
 /// POST /movies  — admin only
+///
+/// New titles start as drafts and go live through `POST /movies/{id}/status`.
 pub async fn create_movie(
     req: HttpRequest,
     movie_data: web::Json<Movie>,
     movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+    genre_collection: web::Data<Collection<Genre>>,
 ) -> HttpResponse {
-    if let Err(res) = require_admin(req).await {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    // Smart lists with "added since" rules rely on this timestamp.
+    let mut movie = movie_data.into_inner();
+    if let Err(res) = check_regions(&mut movie) {
+        return res;
+    }
+    if let Err(res) = check_genres(&genre_collection, &movie.genres).await {
         return res;
     }
+    movie.created_at.get_or_insert_with(DateTime::now);
+    movie.status = Some(ContentStatus::Draft);
+    movie.revision = 1;
+    movie.ratings = RatingSummary::default();
+    // Translations are managed through /admin/translations.
+    movie.translations.clear();
 
-    match movie_collection.insert_one(movie_data.into_inner()).await {
-        Ok(result) => HttpResponse::Created().json(result.inserted_id),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    let created = match to_document(&movie) {
+        Ok(document) => document,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let mut stored = created.clone();
+    stored.insert("search_keys", movie_search_keys(&created));
+
+    let inserted_id = match movie_collection
+        .clone_with_type::<Document>()
+        .insert_one(stored)
+        .await
+    {
+        Ok(result) => result.inserted_id,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    // Without transactions, a title whose first revision cannot be recorded is
+    // taken back out so the history never misses its creation.
+    if let Some(oid) = inserted_id.as_object_id() {
+        let changes = diff(&Document::new(), &created);
+        if let Err(e) =
+            record_revision(&revision_collection, oid.to_hex(), 1, changes, &actor).await
+        {
+            if let Err(e) = movie_collection.delete_one(doc! { "_id": oid }).await {
+                log::warn!("Failed to remove movie {} without a revision: {}", oid, e);
+            }
+            return HttpResponse::InternalServerError()
+                .body(format!("Failed to record movie revision: {}", e));
+        }
     }
+
+    if let Err(e) = invalidate_smart_lists(&list_collection).await {
+        log::warn!("Failed to invalidate smart lists: {}", e);
+    }
+
+    HttpResponse::Created().json(inserted_id)
 }
 
 /// GET /movies  — admin only
@@ -41,7 +181,7 @@ pub async fn get_all_movies(
         return res;
     }
 
-    match movie_collection.find(doc! {}).await {
+    match movie_collection.find(not_deleted()).await {
         Ok(cursor) => match cursor.try_collect::<Vec<Movie>>().await {
             Ok(movies) => HttpResponse::Ok().json(movies),
             Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
@@ -50,48 +190,374 @@ pub async fn get_all_movies(
     }
 }
 
-/// GET /movies/{id}  — any authenticated user
+/// GET /movies/{id}?region=  — any authenticated user
+///
+/// Titles outside their availability window or not licensed in the caller's
+/// region are only visible to admins. Credits are embedded, whole-title ones
+/// first and then each episode's. Text is in the caller's language.
 pub async fn get_movie(
     req: HttpRequest,
     movie_id: web::Path<String>,
+    query: web::Query<RegionQuery>,
     movie_collection: web::Data<Collection<Movie>>,
+    person_collection: web::Data<Collection<Person>>,
+    credit_collection: web::Data<Collection<Credit>>,
+    region_resolver: web::Data<RegionResolver>,
 ) -> HttpResponse {
-    if let Err(res) = require_auth(req).await {
-        return res;
-    }
+    let claims = match require_auth(req.clone()).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    let is_admin = claims_is_admin(&claims);
+    let viewer = viewer(&req, &region_resolver, is_admin, query.region.as_deref());
+    let locales = preferred_locales(&req, &claims, None);
+
+    let oid = match ObjectId::parse_str(movie_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
 
-    let filter = doc! { "_id": movie_id.into_inner() };
+    let filter = and(doc! { "_id": oid }, viewer.movie_filter());
 
-    match movie_collection.find_one(filter).await {
-        Ok(Some(movie)) => HttpResponse::Ok().json(movie),
-        Ok(None) => HttpResponse::NotFound().finish(),
+    let mut movie = match movie_collection.find_one(filter).await {
+        Ok(Some(movie)) => movie,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    movie.localize(&locales);
+
+    match movie_credits(&credit_collection, &person_collection, &oid.to_hex()).await {
+        Ok(credits) => HttpResponse::Ok().json(MovieDetail { movie, credits }),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
 }
 
-/// GET /movies/random?type=series  — any authenticated user
+/// GET /movies/random?type=&genre=&max_age=&count=&region=  — any authenticated user
+///
+/// Featured billboard: titles drawn at random, weighted by recency, popularity
+/// and editorial boost, skipping those shown to the caller recently. Returns
+/// one title, or an array of `count` distinct titles when `count` is given.
+/// Always filtered as for a user; admins can pick the region with `?region=`.
 pub async fn get_random_movie(
     req: HttpRequest,
-    query: web::Query<MovieTypeQuery>,
+    query: web::Query<FeaturedQuery>,
     movie_collection: web::Data<Collection<Movie>>,
+    model_collection: web::Data<Collection<ItemModel>>,
+    impression_collection: web::Data<Collection<FeaturedImpression>>,
+    featured_policy: web::Data<FeaturedPolicy>,
+    region_resolver: web::Data<RegionResolver>,
 ) -> HttpResponse {
-    if let Err(res) = require_auth(req).await {
-        return res;
+    let claims = match require_auth(req.clone()).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    let (user, is_admin) = (claims_email(&claims), claims_is_admin(&claims));
+    let locales = preferred_locales(&req, &claims, None);
+    let preview_region = query.region.as_deref().filter(|_| is_admin);
+    let viewer = user_viewer(&req, &region_resolver, preview_region);
+    let count = query.count.unwrap_or(1).clamp(1, MAX_FEATURED);
+
+    let mut filter = doc! { "is_series": query.media_type.as_deref() == Some("series") };
+    if let Some(genre) = &query.genre {
+        filter.extend(movie_genre_filter(genre));
     }
+    let filter = and(filter, viewer.movie_filter());
+    let projection = doc! {
+        "_id": 1, "limit": 1, "available_from": 1, "created_at": 1, "featured_boost": 1,
+    };
 
-    let is_series = query.media_type.as_deref() == Some("series");
+    // The titles most popular lately, as far as this viewer may see them.
+    let popular_ids: Vec<ObjectId> = match model_collection
+        .clone_with_type::<Document>()
+        .find(doc! { "popularity": { "$gt": 0.0 } })
+        .sort(doc! { "popularity": -1 })
+        .projection(doc! { "_id": 1 })
+        .limit(FEATURED_POOL)
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(rows) => rows
+                .iter()
+                .filter_map(|row| ObjectId::parse_str(row.get_str("_id").ok()?).ok())
+                .collect(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
 
+    // Weighing the whole catalog per request does not scale, so the draw is
+    // over a pool holding every title likely to weigh much (the newest, the
+    // boosted, the popular) plus a random sample of the long tail.
     let pipeline = vec![
-        doc! { "$match": { "is_series": is_series } },
-        doc! { "$sample": { "size": 1 } },
+        doc! { "$match": filter },
+        doc! { "$project": projection },
+        doc! { "$facet": {
+            "popular": [
+                { "$match": { "_id": { "$in": popular_ids } } },
+            ],
+            "newest": [
+                { "$addFields": {
+                    "released_at": { "$ifNull": ["$available_from", "$created_at"] },
+                } },
+                { "$sort": { "released_at": -1 } },
+                { "$limit": FEATURED_POOL },
+                { "$project": { "released_at": 0 } },
+            ],
+            "boosted": [
+                { "$match": { "featured_boost": { "$gt": 0.0 } } },
+                { "$sort": { "featured_boost": -1 } },
+                { "$limit": FEATURED_POOL },
+            ],
+            "sample": [
+                { "$sample": { "size": FEATURED_POOL } },
+            ],
+        } },
     ];
-
-    match movie_collection.aggregate(pipeline).await {
+    let facets: Document = match movie_collection.aggregate(pipeline).await {
         Ok(mut cursor) => match cursor.try_next().await {
-            Ok(Some(doc)) => HttpResponse::Ok().json(doc),
-            Ok(None) => HttpResponse::NotFound().finish(),
-            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            Ok(facets) => facets.unwrap_or_default(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let mut pool: HashMap<ObjectId, &Document> = HashMap::new();
+    for facet in ["popular", "newest", "boosted", "sample"] {
+        for movie in facets.get_array(facet).into_iter().flatten() {
+            if let Some(movie) = movie.as_document() {
+                if let Ok(id) = movie.get_object_id("_id") {
+                    pool.insert(id, movie);
+                }
+            }
+        }
+    }
+    let eligible: Vec<&Document> = pool
+        .into_values()
+        .filter(|movie| within_age(movie.get_str("limit").ok(), query.max_age))
+        .collect();
+
+    let ids: Vec<String> = eligible
+        .iter()
+        .filter_map(|movie| movie.get_object_id("_id").ok().map(|id| id.to_hex()))
+        .collect();
+    let popularity: HashMap<String, f64> = match model_collection
+        .clone_with_type::<Document>()
+        .find(doc! { "_id": { "$in": &ids } })
+        .projection(doc! { "popularity": 1 })
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(rows) => rows
+                .iter()
+                .filter_map(|row| {
+                    Some((row.get_str("_id").ok()?.to_string(), row.get_f64("popularity").ok()?))
+                })
+                .collect(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let recently_shown: HashSet<String> = match impression_collection
+        .find(doc! { "user": &user })
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<FeaturedImpression>>().await {
+            Ok(shown) => shown.into_iter().map(|shown| shown.movie_id).collect(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let (fresh, repeats): (Vec<Candidate>, Vec<Candidate>) = eligible
+        .iter()
+        .filter_map(|movie| {
+            let id = movie.get_object_id("_id").ok()?;
+            let released_at = movie
+                .get_datetime("available_from")
+                .or_else(|_| movie.get_datetime("created_at"))
+                .ok()
+                .copied();
+            Some(Candidate {
+                id,
+                released_at,
+                popularity: popularity.get(&id.to_hex()).copied().unwrap_or(0.0),
+                boost: movie.get_f64("featured_boost").unwrap_or(0.0),
+            })
+        })
+        .partition(|candidate| !recently_shown.contains(&candidate.id.to_hex()));
+
+    // Recently shown titles only fill in when too few fresh ones are left.
+    let mut picks = featured_policy.pick(&fresh, count);
+    if picks.len() < count {
+        picks.extend(featured_policy.pick(&repeats, count - picks.len()));
+    }
+    if picks.is_empty() {
+        return match query.count {
+            Some(_) => HttpResponse::Ok().json(Vec::<Movie>::new()),
+            None => HttpResponse::NotFound().finish(),
+        };
+    }
+
+    let mut movies: HashMap<ObjectId, Movie> = match movie_collection
+        .find(doc! { "_id": { "$in": &picks } })
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<Movie>>().await {
+            Ok(movies) => movies
+                .into_iter()
+                .filter_map(|mut movie| {
+                    movie.localize(&locales);
+                    Some((movie.id?, movie))
+                })
+                .collect(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let featured: Vec<Movie> = picks.iter().filter_map(|id| movies.remove(id)).collect();
+
+    let now = DateTime::now();
+    for movie_id in featured.iter().filter_map(|movie| movie.id) {
+        if let Err(e) = impression_collection
+            .update_one(
+                doc! { "user": &user, "movie_id": movie_id.to_hex() },
+                doc! { "$set": { "shown_at": now } },
+            )
+            .upsert(true)
+            .await
+        {
+            log::warn!("Failed to record featured impression: {}", e);
+        }
+    }
+
+    match query.count {
+        Some(_) => HttpResponse::Ok().json(featured),
+        None => match featured.into_iter().next() {
+            Some(movie) => HttpResponse::Ok().json(movie),
+            None => HttpResponse::NotFound().finish(),
         },
+    }
+}
+
+/// PUT /movies/{id}  — admin only
+///
+/// Replaces the title's editable metadata (`METADATA_FIELDS`); fields left
+/// out of the body are cleared. The change is recorded as a revision.
+/// Editors may only edit titles that are not published; a publisher moves a
+/// live title back to draft, and the edit goes through review again.
+pub async fn update_movie(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    movie_data: web::Json<Movie>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+    genre_collection: web::Data<Collection<Genre>>,
+) -> HttpResponse {
+    let (actor, role) = match require_admin(req).await {
+        Ok(claims) => (claims_email(&claims), claims_role(&claims)),
+        Err(res) => return res,
+    };
+
+    let oid = match ObjectId::parse_str(movie_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    let mut movie = movie_data.into_inner();
+    if let Err(res) = check_regions(&mut movie) {
+        return res;
+    }
+    if let Err(res) = check_genres(&genre_collection, &movie.genres).await {
+        return res;
+    }
+    let input = match to_document(&movie) {
+        Ok(document) => document,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let mut set = Document::new();
+    let mut unset = Document::new();
+    for field in METADATA_FIELDS {
+        match input.get(*field) {
+            Some(value) => set.insert(*field, value.clone()),
+            None => unset.insert(*field, ""),
+        };
+    }
+
+    let movies = movie_collection.clone_with_type::<Document>();
+    let mut filter = and(doc! { "_id": oid }, not_deleted());
+    if role == EditorialRole::Editor {
+        // A missing status counts as published.
+        filter = and(filter, doc! { "status": { "$nin": ["published", null] } });
+    }
+    let mut update = doc! { "$set": set };
+    if !unset.is_empty() {
+        update.insert("$unset", unset);
+    }
+
+    let updated = match write_movie(&movies, &revision_collection, filter, update, &actor).await {
+        Ok(Some(updated)) => updated,
+        Ok(None) if role == EditorialRole::Editor => {
+            let exists = and(doc! { "_id": oid }, not_deleted());
+            return match movies.count_documents(exists).await {
+                Ok(0) => HttpResponse::NotFound().body("Movie not found"),
+                Ok(_) => HttpResponse::Forbidden().body(
+                    "Editors cannot edit published titles; a publisher must unpublish it first.",
+                ),
+                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            };
+        }
+        Ok(None) => return HttpResponse::NotFound().body("Movie not found"),
+        Err(e @ RevisionError::Conflict) => return HttpResponse::Conflict().body(e.to_string()),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    if let Err(e) = invalidate_smart_lists(&list_collection).await {
+        log::warn!("Failed to invalidate smart lists: {}", e);
+    }
+
+    match from_document::<Movie>(updated) {
+        Ok(movie) => HttpResponse::Ok().json(movie),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
-}
\ No newline at end of file
+}
+
+/// DELETE /movies/{id}  — admin only
+///
+/// Moves the title to the trash. Watchlist entries and list items pointing at
+/// it are kept (reads hide them) so a restore brings everything back; they
+/// are cleaned up when the trash is purged.
+pub async fn delete_movie(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let oid = match ObjectId::parse_str(movie_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    let movies = movie_collection.clone_with_type::<Document>();
+    let filter = and(doc! { "_id": oid }, not_deleted());
+    let trash = doc! { "$set": { "deleted_at": DateTime::now(), "deleted_by": &actor } };
+
+    match write_movie(&movies, &revision_collection, filter, trash, &actor).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Movie not found"),
+        Err(e @ RevisionError::Conflict) => return HttpResponse::Conflict().body(e.to_string()),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    if let Err(e) = invalidate_smart_lists(&list_collection).await {
+        log::warn!("Failed to invalidate smart lists: {}", e);
+    }
+
+    HttpResponse::Ok().body("The movie has been moved to the trash")
+}
@@ -0,0 +1,253 @@
+use crate::catalog::and;
+use crate::credits::person_credits;
+use crate::geo::{viewer, RegionResolver};
+use crate::locale::preferred_locales;
+use crate::models::movie::{Movie, MovieSummary};
+use crate::models::person::{Credit, FilmographyEntry, Person, PersonDetail};
+use crate::routes::auth::{claims_is_admin, require_admin, require_auth};
+use crate::search::search_keys;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, to_document, DateTime, Document};
+use mongodb::options::ReturnDocument;
+use mongodb::Collection;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct PersonQuery {
+    /// ?region=BR  — admins only: list the filmography as seen from that region
+    region: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PersonInput {
+    pub name: String,
+    pub photo: Option<String>,
+    pub bio: Option<String>,
+}
+
+fn parse_person_id(person_id: &str) -> Result<ObjectId, HttpResponse> {
+    ObjectId::parse_str(person_id)
+        .map_err(|_| HttpResponse::BadRequest().body("Invalid person ID format."))
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /people/{id}?region=  — any authenticated user
+///
+/// The person with a filmography of the titles the caller may watch, newest
+/// first.
+pub async fn get_person(
+    req: HttpRequest,
+    person_id: web::Path<String>,
+    query: web::Query<PersonQuery>,
+    person_collection: web::Data<Collection<Person>>,
+    credit_collection: web::Data<Collection<Credit>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let claims = match require_auth(req.clone()).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    let is_admin = claims_is_admin(&claims);
+    let viewer = viewer(&req, &region_resolver, is_admin, query.region.as_deref());
+    let locales = preferred_locales(&req, &claims, None);
+
+    let person_id = person_id.into_inner();
+    let oid = match parse_person_id(&person_id) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+    let person = match person_collection.find_one(doc! { "_id": oid }).await {
+        Ok(Some(person)) => person,
+        Ok(None) => return HttpResponse::NotFound().body("Person not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let credits = match person_credits(&credit_collection, &person_id).await {
+        Ok(credits) => credits,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let movie_oids: Vec<ObjectId> = credits
+        .iter()
+        .filter_map(|credit| ObjectId::parse_str(&credit.movie_id).ok())
+        .collect();
+    let movies: HashMap<String, MovieSummary> = match movie_collection
+        .clone_with_type::<MovieSummary>()
+        .find(and(
+            doc! { "_id": { "$in": movie_oids } },
+            viewer.movie_filter(),
+        ))
+        .projection(MovieSummary::projection())
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<MovieSummary>>().await {
+            Ok(movies) => movies
+                .into_iter()
+                .filter_map(|mut movie| {
+                    movie.localize(&locales);
+                    Some((movie.id?.to_hex(), movie))
+                })
+                .collect(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let mut filmography: Vec<FilmographyEntry> = credits
+        .into_iter()
+        .filter_map(|credit| {
+            Some(FilmographyEntry {
+                movie: movies.get(&credit.movie_id)?.clone(),
+                role: credit.role,
+                character: credit.character,
+                episode: credit.episode,
+                order: credit.order,
+            })
+        })
+        .collect();
+    filmography.sort_by(|a, b| {
+        b.movie
+            .year
+            .cmp(&a.movie.year)
+            .then_with(|| a.movie.title.cmp(&b.movie.title))
+            .then_with(|| (&a.episode, a.role).cmp(&(&b.episode, b.role)))
+    });
+
+    HttpResponse::Ok().json(PersonDetail {
+        person,
+        filmography,
+    })
+}
+
+/// POST /people  — admin only
+pub async fn create_person(
+    req: HttpRequest,
+    input: web::Json<PersonInput>,
+    person_collection: web::Data<Collection<Person>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let input = input.into_inner();
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().body("name must not be empty.");
+    }
+
+    let now = DateTime::now();
+    let person = Person {
+        id: None,
+        name,
+        photo: input.photo,
+        bio: input.bio,
+        created_at: Some(now),
+        updated_at: Some(now),
+    };
+
+    let mut document = match to_document(&person) {
+        Ok(document) => document,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    document.insert("search_keys", search_keys([person.name.as_str()]));
+
+    match person_collection
+        .clone_with_type::<Document>()
+        .insert_one(document)
+        .await
+    {
+        Ok(result) => HttpResponse::Created().json(result.inserted_id),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// PUT /people/{id}  — admin only
+pub async fn update_person(
+    req: HttpRequest,
+    person_id: web::Path<String>,
+    input: web::Json<PersonInput>,
+    person_collection: web::Data<Collection<Person>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let oid = match parse_person_id(&person_id) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+    let input = input.into_inner();
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().body("name must not be empty.");
+    }
+
+    let mut set = doc! {
+        "search_keys": search_keys([name.as_str()]),
+        "name": name,
+        "updated_at": DateTime::now(),
+    };
+    let mut unset = Document::new();
+    for (field, value) in [("photo", input.photo), ("bio", input.bio)] {
+        match value {
+            Some(value) => set.insert(field, value),
+            None => unset.insert(field, ""),
+        };
+    }
+    let mut update = doc! { "$set": set };
+    if !unset.is_empty() {
+        update.insert("$unset", unset);
+    }
+
+    match person_collection
+        .find_one_and_update(doc! { "_id": oid }, update)
+        .return_document(ReturnDocument::After)
+        .await
+    {
+        Ok(Some(person)) => HttpResponse::Ok().json(person),
+        Ok(None) => HttpResponse::NotFound().body("Person not found"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// DELETE /people/{id}  — admin only
+///
+/// Removes the person along with their credits on every title.
+pub async fn delete_person(
+    req: HttpRequest,
+    person_id: web::Path<String>,
+    person_collection: web::Data<Collection<Person>>,
+    credit_collection: web::Data<Collection<Credit>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let person_id = person_id.into_inner();
+    let oid = match parse_person_id(&person_id) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    // Credits go first so a failure never leaves credits pointing at nobody.
+    if let Err(e) = credit_collection
+        .delete_many(doc! { "person_id": &person_id })
+        .await
+    {
+        return HttpResponse::InternalServerError().body(e.to_string());
+    }
+
+    match person_collection.delete_one(doc! { "_id": oid }).await {
+        Ok(result) if result.deleted_count == 0 => {
+            HttpResponse::NotFound().body("Person not found")
+        }
+        Ok(_) => HttpResponse::Ok().body("Person deleted"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
@@ -0,0 +1,168 @@
+use crate::catalog::and;
+use crate::geo::{user_viewer, RegionResolver};
+use crate::models::movie::Movie;
+use crate::models::rating::{Rating, Thumb};
+use crate::ratings::{apply_rating_delta, RatingDelta};
+use crate::reviews::is_duplicate_key;
+use crate::routes::auth::require_user;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use mongodb::options::ReturnDocument;
+use mongodb::Collection;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct RatingInput {
+    /// `down`, `up` or `love`.
+    thumb: Thumb,
+}
+
+/// Moves the movie's aggregate counts along with one user's rating. A failure
+/// is repaired by the periodic recount (`ratings::spawn_rating_recounter`).
+async fn update_aggregates(
+    movie_collection: &Collection<Movie>,
+    movie_id: ObjectId,
+    from: Option<Thumb>,
+    to: Option<Thumb>,
+) {
+    let movies = movie_collection.clone_with_type::<Document>();
+    if let Err(e) = apply_rating_delta(&movies, movie_id, RatingDelta::change(from, to)).await {
+        log::warn!(
+            "Failed to update rating counts of movie {}: {}",
+            movie_id,
+            e
+        );
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// PUT /me/ratings/{movie_id}  — any authenticated user
+///
+/// Idempotent: sets the caller's thumb for the title, replacing any earlier one.
+/// Only titles the caller can watch may be rated.
+pub async fn rate_movie(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    input: web::Json<RatingInput>,
+    rating_collection: web::Data<Collection<Rating>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let viewer = user_viewer(&req, &region_resolver, None);
+    let user = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    let movie_id = movie_id.into_inner();
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    match movie_collection
+        .count_documents(and(doc! { "_id": oid }, viewer.movie_filter()))
+        .await
+    {
+        Ok(0) => return HttpResponse::NotFound().body("Movie not found"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let thumb = input.thumb;
+    let now = DateTime::now();
+    // The previous rating comes back from the same atomic write, so the
+    // counts move by exactly what this request changed. Two first ratings
+    // racing both try to insert; the loser hits the unique index and retries
+    // as an update of the winner's rating.
+    let mut attempts = 0;
+    let previous = loop {
+        attempts += 1;
+        let written = rating_collection
+            .find_one_and_update(
+                doc! { "user": &user, "movie_id": &movie_id },
+                doc! { "$set": { "thumb": thumb.counter(), "rated_at": now } },
+            )
+            .upsert(true)
+            .return_document(ReturnDocument::Before)
+            .await;
+        match written {
+            Ok(previous) => break previous,
+            Err(e) if is_duplicate_key(&e) && attempts < 2 => continue,
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        }
+    };
+
+    let from = previous.as_ref().map(|rating| rating.thumb);
+    update_aggregates(&movie_collection, oid, from, Some(thumb)).await;
+
+    let rating = Rating {
+        id: previous.and_then(|rating| rating.id),
+        user,
+        movie_id,
+        thumb,
+        rated_at: now,
+    };
+    match from {
+        None => HttpResponse::Created().json(rating),
+        Some(_) => HttpResponse::Ok().json(rating),
+    }
+}
+
+/// DELETE /me/ratings/{movie_id}  — any authenticated user
+pub async fn clear_rating(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    rating_collection: web::Data<Collection<Rating>>,
+    movie_collection: web::Data<Collection<Movie>>,
+) -> HttpResponse {
+    let user = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    let movie_id = movie_id.into_inner();
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    match rating_collection
+        .find_one_and_delete(doc! { "user": &user, "movie_id": &movie_id })
+        .await
+    {
+        Ok(Some(previous)) => {
+            update_aggregates(&movie_collection, oid, Some(previous.thumb), None).await;
+            HttpResponse::Ok().body("Rating cleared")
+        }
+        Ok(None) => HttpResponse::NotFound().body("You have not rated this title"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /me/ratings  — any authenticated user
+///
+/// The caller's ratings, most recent first.
+pub async fn get_my_ratings(
+    req: HttpRequest,
+    rating_collection: web::Data<Collection<Rating>>,
+) -> HttpResponse {
+    let user = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    match rating_collection
+        .find(doc! { "user": &user })
+        .sort(doc! { "rated_at": -1 })
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<Rating>>().await {
+            Ok(ratings) => HttpResponse::Ok().json(ratings),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
@@ -0,0 +1,194 @@
+use crate::catalog::{and, not_deleted};
+use crate::geo::{user_viewer, RegionResolver};
+use crate::locale::preferred_locales;
+use crate::models::movie::{within_age, Movie, MovieSummary};
+use crate::models::rating::{Rating, Thumb};
+use crate::models::recommendation::ItemModel;
+use crate::models::view::View;
+use crate::recommendations::{blend, personalize, user_history, Candidate};
+use crate::routes::auth::{claims_email, require_auth};
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Most recent liked or watched titles used as seeds.
+const MAX_SEEDS: usize = 50;
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct RecommendationQuery {
+    /// ?limit=20  — capped at 50
+    limit: Option<usize>,
+
+    /// ?max_age=13  — drop titles rated for older viewers or not rated at all
+    max_age: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct Recommendation {
+    #[serde(flatten)]
+    movie: MovieSummary,
+
+    /// Relevance, 0 to 1.
+    score: f64,
+
+    /// Why the title was picked, e.g. "Because you watched Dark".
+    reason: String,
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /me/recommendations?limit=&max_age=  — any authenticated user
+///
+/// Titles similar to what the caller watched or liked, from the periodically
+/// rebuilt item-item model, blended with popular titles; users without any
+/// history get popular titles only.
+pub async fn get_recommendations(
+    req: HttpRequest,
+    query: web::Query<RecommendationQuery>,
+    view_collection: web::Data<Collection<View>>,
+    rating_collection: web::Data<Collection<Rating>>,
+    model_collection: web::Data<Collection<ItemModel>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let viewer = user_viewer(&req, &region_resolver, None);
+    let claims = match require_auth(req.clone()).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    let user = claims_email(&claims);
+    let locales = preferred_locales(&req, &claims, None);
+    let limit = query.limit.unwrap_or(20).clamp(1, 50);
+
+    let history = match user_history(&view_collection, &rating_collection, &user).await {
+        Ok(history) => history,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let mut seeds: Vec<(&String, i64)> = history
+        .iter()
+        .filter(|(_, interaction)| interaction.weight().is_some())
+        .map(|(movie_id, interaction)| (movie_id, interaction.at))
+        .collect();
+    seeds.sort_by_key(|&(_, at)| Reverse(at));
+    seeds.truncate(MAX_SEEDS);
+    let seed_ids: Vec<&String> = seeds.into_iter().map(|(movie_id, _)| movie_id).collect();
+
+    let personal: Vec<Candidate> = if seed_ids.is_empty() {
+        Vec::new()
+    } else {
+        match model_collection
+            .find(doc! { "_id": { "$in": &seed_ids } })
+            .await
+        {
+            Ok(cursor) => match cursor.try_collect::<Vec<ItemModel>>().await {
+                Ok(rows) => personalize(&history, &rows),
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            },
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        }
+    };
+
+    // Enough popular titles to fill the page even if some are filtered out below.
+    let seen: Vec<&String> = history.keys().collect();
+    let popular: Vec<ItemModel> = match model_collection
+        .find(doc! { "_id": { "$nin": &seen }, "popularity": { "$gt": 0.0 } })
+        .sort(doc! { "popularity": -1 })
+        .limit((limit * 3) as i64)
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect().await {
+            Ok(popular) => popular,
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let ranked = blend(personal, popular);
+
+    let ids: Vec<ObjectId> = ranked
+        .iter()
+        .filter_map(|(id, _, _)| ObjectId::parse_str(id).ok())
+        .collect();
+    let summaries: Vec<MovieSummary> = match movie_collection
+        .clone_with_type::<MovieSummary>()
+        .find(and(doc! { "_id": { "$in": ids } }, viewer.movie_filter()))
+        .projection(MovieSummary::projection())
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect().await {
+            Ok(summaries) => summaries,
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let mut by_id: HashMap<String, MovieSummary> = summaries
+        .into_iter()
+        .filter(|movie| within_age(movie.limit.as_deref(), query.max_age))
+        .filter_map(|mut movie| {
+            movie.localize(&locales);
+            Some((movie.id?.to_hex(), movie))
+        })
+        .collect();
+
+    let picks: Vec<(MovieSummary, f64, Option<String>)> = ranked
+        .into_iter()
+        .filter_map(|(id, score, because)| Some((by_id.remove(&id)?, score, because)))
+        .take(limit)
+        .collect();
+
+    // Titles of the seeds named in the explanations.
+    let because_ids: Vec<ObjectId> = picks
+        .iter()
+        .filter_map(|(_, _, because)| ObjectId::parse_str(because.as_ref()?).ok())
+        .collect();
+    let seed_titles: HashMap<String, String> = match movie_collection
+        .clone_with_type::<MovieSummary>()
+        .find(and(doc! { "_id": { "$in": because_ids } }, not_deleted()))
+        .projection(MovieSummary::projection())
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<MovieSummary>>().await {
+            Ok(seeds) => seeds
+                .into_iter()
+                .filter_map(|mut seed| {
+                    seed.localize(&locales);
+                    Some((seed.id?.to_hex(), seed.title))
+                })
+                .collect(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let recommendations: Vec<Recommendation> = picks
+        .into_iter()
+        .map(|(movie, score, because)| {
+            let reason = match because {
+                None => "Popular right now".to_string(),
+                Some(seed) => match (seed_titles.get(&seed), history.get(&seed)) {
+                    (Some(title), Some(interaction))
+                        if matches!(interaction.thumb, Some(Thumb::Up | Thumb::Love)) =>
+                    {
+                        format!("Because you liked {}", title)
+                    }
+                    (Some(title), _) => format!("Because you watched {}", title),
+                    (None, _) => "Based on your viewing history".to_string(),
+                },
+            };
+            Recommendation {
+                movie,
+                score,
+                reason,
+            }
+        })
+        .collect();
+
+    HttpResponse::Ok().json(recommendations)
+}
@@ -0,0 +1,517 @@
+use crate::catalog::{and, not_deleted};
+use crate::models::movie::Movie;
+use crate::models::review::{Review, ReviewStatus, ReviewVote};
+use crate::models::users::Users;
+use crate::reviews::{is_duplicate_key, ReviewPolicy};
+use crate::routes::auth::{claims_email, claims_is_admin, require_admin, require_auth};
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, to_bson, DateTime, Document};
+use mongodb::options::ReturnDocument;
+use mongodb::Collection;
+use serde::Deserialize;
+use serde_json::json;
+
+// ── Request bodies & query params ─────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ReviewInput {
+    text: String,
+
+    /// Stars, 1 to 5.
+    rating: u8,
+}
+
+#[derive(Deserialize)]
+pub struct ReviewPageQuery {
+    /// ?sort=helpful|recent  — defaults to helpful
+    sort: Option<String>,
+
+    /// ?page=1  — 1-based
+    page: Option<u64>,
+
+    /// ?limit=20  — capped at 100
+    limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct QueueQuery {
+    /// ?status=pending|approved|rejected  — defaults to pending
+    status: Option<ReviewStatus>,
+
+    page: Option<u64>,
+
+    limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct ModerationInput {
+    /// `approved` or `rejected`.
+    status: ReviewStatus,
+
+    note: Option<String>,
+}
+
+fn parse_ids(movie_id: &str, review_id: &str) -> Result<ObjectId, HttpResponse> {
+    if ObjectId::parse_str(movie_id).is_err() {
+        return Err(HttpResponse::BadRequest().body("Invalid movie ID format."));
+    }
+    ObjectId::parse_str(review_id)
+        .map_err(|_| HttpResponse::BadRequest().body("Invalid review ID format."))
+}
+
+/// Trimmed text and a rating within range, or the 400 to return.
+fn validate(input: ReviewInput, policy: &ReviewPolicy) -> Result<(String, u8), HttpResponse> {
+    let text = input.text.trim().to_string();
+    if text.is_empty() {
+        return Err(HttpResponse::BadRequest().body("Review text must not be empty"));
+    }
+    if text.chars().count() > policy.max_chars {
+        return Err(HttpResponse::BadRequest().body(format!(
+            "Reviews are limited to {} characters",
+            policy.max_chars
+        )));
+    }
+    if !(1..=5).contains(&input.rating) {
+        return Err(HttpResponse::BadRequest().body("rating must be between 1 and 5"));
+    }
+    Ok((text, input.rating))
+}
+
+/// Logs the write and returns the 429 if the user has written too many
+/// reviews lately.
+async fn check_rate_limit(policy: &ReviewPolicy, user: &str) -> Result<(), HttpResponse> {
+    match policy.admit(user).await {
+        Ok(None) => Ok(()),
+        Ok(Some(secs)) => Err(HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, secs.to_string()))
+            .body("You are writing reviews too quickly; try again later")),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// POST /movies/{id}/reviews  — any authenticated user
+///
+/// One review per user and title. Reviews the content filter flags, or all
+/// of them under premoderation, wait in the moderation queue.
+pub async fn create_review(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    input: web::Json<ReviewInput>,
+    policy: web::Data<ReviewPolicy>,
+    review_collection: web::Data<Collection<Review>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    users_collection: web::Data<Collection<Users>>,
+) -> HttpResponse {
+    let user = match require_auth(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let movie_id = movie_id.into_inner();
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+    let (text, rating) = match validate(input.into_inner(), &policy) {
+        Ok(valid) => valid,
+        Err(res) => return res,
+    };
+
+    match movie_collection
+        .count_documents(and(doc! { "_id": oid }, not_deleted()))
+        .await
+    {
+        Ok(0) => return HttpResponse::NotFound().body("Movie not found"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    if let Err(res) = check_rate_limit(&policy, &user).await {
+        return res;
+    }
+
+    let author = match users_collection
+        .clone_with_type::<Document>()
+        .find_one(doc! { "email": &user })
+        .projection(doc! { "username": 1 })
+        .await
+    {
+        Ok(found) => found.and_then(|u| u.get_str("username").ok().map(str::to_string)),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let flags = policy.check(&text);
+    let now = DateTime::now();
+    let mut review = Review {
+        id: None,
+        movie_id,
+        user: Some(user),
+        author,
+        text,
+        rating,
+        status: policy.status_for(&flags, None),
+        flags,
+        helpful_count: 0,
+        moderated_by: None,
+        moderation_note: None,
+        moderated_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    match review_collection.insert_one(&review).await {
+        Ok(result) => {
+            review.id = result.inserted_id.as_object_id();
+            HttpResponse::Created().json(review)
+        }
+        Err(e) if is_duplicate_key(&e) => {
+            HttpResponse::Conflict().body("You have already reviewed this title; edit it instead")
+        }
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /movies/{id}/reviews?sort=helpful|recent&page=&limit=  — any authenticated user
+///
+/// Approved reviews only.
+pub async fn get_reviews(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    query: web::Query<ReviewPageQuery>,
+    review_collection: web::Data<Collection<Review>>,
+) -> HttpResponse {
+    if let Err(res) = require_auth(req).await {
+        return res;
+    }
+
+    let sort = match query.sort.as_deref() {
+        None | Some("helpful") => doc! { "helpful_count": -1, "created_at": -1 },
+        Some("recent") => doc! { "created_at": -1 },
+        Some(_) => return HttpResponse::BadRequest().body("sort must be helpful or recent"),
+    };
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let filter = doc! { "movie_id": movie_id.into_inner(), "status": "approved" };
+
+    let total = match review_collection.count_documents(filter.clone()).await {
+        Ok(total) => total,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    match review_collection
+        .find(filter)
+        .projection(doc! { "user": 0, "flags": 0, "moderated_by": 0, "moderation_note": 0 })
+        .sort(sort)
+        .skip((page - 1) * limit)
+        .limit(limit as i64)
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<Review>>().await {
+            Ok(items) => HttpResponse::Ok().json(json!({
+                "page": page,
+                "limit": limit,
+                "total": total,
+                "items": items,
+            })),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// PUT /movies/{id}/reviews/{review_id}  — the review's author
+///
+/// The new text goes through the content filter again.
+pub async fn update_review(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    input: web::Json<ReviewInput>,
+    policy: web::Data<ReviewPolicy>,
+    review_collection: web::Data<Collection<Review>>,
+) -> HttpResponse {
+    let user = match require_auth(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (movie_id, review_id) = path.into_inner();
+    let oid = match parse_ids(&movie_id, &review_id) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+    let (text, rating) = match validate(input.into_inner(), &policy) {
+        Ok(valid) => valid,
+        Err(res) => return res,
+    };
+
+    let own = doc! { "_id": oid, "movie_id": &movie_id, "user": &user };
+    let existing = match review_collection.find_one(own.clone()).await {
+        Ok(Some(review)) => review,
+        Ok(None) => return HttpResponse::NotFound().body("Review not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    if let Err(res) = check_rate_limit(&policy, &user).await {
+        return res;
+    }
+
+    let flags = policy.check(&text);
+    let status = match to_bson(&policy.status_for(&flags, Some(existing.status))) {
+        Ok(status) => status,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let update = doc! {
+        "$set": {
+            "text": text,
+            "rating": i32::from(rating),
+            "status": status,
+            "flags": flags,
+            "updated_at": DateTime::now(),
+        }
+    };
+
+    match review_collection
+        .find_one_and_update(own, update)
+        .return_document(ReturnDocument::After)
+        .await
+    {
+        Ok(Some(review)) => HttpResponse::Ok().json(review),
+        Ok(None) => HttpResponse::NotFound().body("Review not found"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// DELETE /movies/{id}/reviews/{review_id}  — the review's author or an admin
+pub async fn delete_review(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    review_collection: web::Data<Collection<Review>>,
+    vote_collection: web::Data<Collection<ReviewVote>>,
+) -> HttpResponse {
+    let claims = match require_auth(req).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+
+    let (movie_id, review_id) = path.into_inner();
+    let oid = match parse_ids(&movie_id, &review_id) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    let mut filter = doc! { "_id": oid, "movie_id": &movie_id };
+    if !claims_is_admin(&claims) {
+        filter.insert("user", claims_email(&claims));
+    }
+
+    match review_collection.delete_one(filter).await {
+        Ok(result) if result.deleted_count == 0 => {
+            return HttpResponse::NotFound().body("Review not found")
+        }
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    if let Err(e) = vote_collection.delete_many(doc! { "review_id": oid }).await {
+        log::warn!("Failed to delete votes of review {}: {}", oid, e);
+    }
+
+    HttpResponse::Ok().body("The review has been deleted")
+}
+
+/// POST /movies/{id}/reviews/{review_id}/helpful  — any authenticated user
+///
+/// Idempotent; authors cannot vote on their own reviews.
+pub async fn mark_helpful(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    review_collection: web::Data<Collection<Review>>,
+    vote_collection: web::Data<Collection<ReviewVote>>,
+) -> HttpResponse {
+    let user = match require_auth(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (movie_id, review_id) = path.into_inner();
+    let oid = match parse_ids(&movie_id, &review_id) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    let votable = doc! {
+        "_id": oid,
+        "movie_id": &movie_id,
+        "status": "approved",
+        "user": { "$ne": &user },
+    };
+    match review_collection.count_documents(votable).await {
+        Ok(0) => return HttpResponse::NotFound().body("Review not found"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let added = match vote_collection
+        .update_one(
+            doc! { "review_id": oid, "user": &user },
+            doc! { "$setOnInsert": { "at": DateTime::now() } },
+        )
+        .upsert(true)
+        .await
+    {
+        Ok(result) => result.upserted_id.is_some(),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    if added {
+        let bump = doc! { "$inc": { "helpful_count": 1_i64 } };
+        if let Err(e) = review_collection
+            .update_one(doc! { "_id": oid }, bump)
+            .await
+        {
+            return HttpResponse::InternalServerError().body(e.to_string());
+        }
+    }
+    HttpResponse::Ok().finish()
+}
+
+/// DELETE /movies/{id}/reviews/{review_id}/helpful  — any authenticated user
+pub async fn unmark_helpful(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    review_collection: web::Data<Collection<Review>>,
+    vote_collection: web::Data<Collection<ReviewVote>>,
+) -> HttpResponse {
+    let user = match require_auth(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (movie_id, review_id) = path.into_inner();
+    let oid = match parse_ids(&movie_id, &review_id) {
+        Ok(oid) => oid,
+        Err(res) => return res,
+    };
+
+    match vote_collection
+        .delete_one(doc! { "review_id": oid, "user": &user })
+        .await
+    {
+        Ok(result) if result.deleted_count == 0 => {
+            HttpResponse::NotFound().body("You have not marked this review helpful")
+        }
+        Ok(_) => {
+            let drop = doc! { "$inc": { "helpful_count": -1_i64 } };
+            match review_collection
+                .update_one(doc! { "_id": oid }, drop)
+                .await
+            {
+                Ok(_) => HttpResponse::Ok().finish(),
+                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            }
+        }
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /admin/reviews?status=pending&page=&limit=  — admin only
+///
+/// The moderation queue, oldest first.
+pub async fn get_review_queue(
+    req: HttpRequest,
+    query: web::Query<QueueQuery>,
+    review_collection: web::Data<Collection<Review>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let status = match to_bson(&query.status.unwrap_or(ReviewStatus::Pending)) {
+        Ok(status) => status,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let filter = doc! { "status": status };
+
+    let total = match review_collection.count_documents(filter.clone()).await {
+        Ok(total) => total,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    match review_collection
+        .find(filter)
+        .sort(doc! { "updated_at": 1 })
+        .skip((page - 1) * limit)
+        .limit(limit as i64)
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<Review>>().await {
+            Ok(items) => HttpResponse::Ok().json(json!({
+                "page": page,
+                "limit": limit,
+                "total": total,
+                "items": items,
+            })),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// POST /admin/reviews/{id}/moderate  — admin only
+///
+/// Body: `{ "status": "approved" | "rejected", "note": "..." }`.
+pub async fn moderate_review(
+    req: HttpRequest,
+    review_id: web::Path<String>,
+    input: web::Json<ModerationInput>,
+    review_collection: web::Data<Collection<Review>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let oid = match ObjectId::parse_str(review_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid review ID format."),
+    };
+    let input = input.into_inner();
+    if input.status == ReviewStatus::Pending {
+        return HttpResponse::BadRequest().body("status must be approved or rejected");
+    }
+    let status = match to_bson(&input.status) {
+        Ok(status) => status,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let mut set = doc! {
+        "status": status,
+        "moderated_by": actor,
+        "moderated_at": DateTime::now(),
+    };
+    let mut update = Document::new();
+    match input.note.filter(|note| !note.trim().is_empty()) {
+        Some(note) => {
+            set.insert("moderation_note", note);
+        }
+        None => {
+            update.insert("$unset", doc! { "moderation_note": "" });
+        }
+    }
+    update.insert("$set", set);
+
+    match review_collection
+        .find_one_and_update(doc! { "_id": oid }, update)
+        .return_document(ReturnDocument::After)
+        .await
+    {
+        Ok(Some(review)) => HttpResponse::Ok().json(review),
+        Ok(None) => HttpResponse::NotFound().body("Review not found"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
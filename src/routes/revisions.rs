@@ -0,0 +1,107 @@
+use crate::models::list::List;
+use crate::models::movie::Movie;
+use crate::models::revision::MovieRevision;
+use crate::revisions::{restore_update, write_movie, RevisionError};
+use crate::routes::auth::{claims_email, require_admin};
+use crate::smart_lists::invalidate_smart_lists;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, from_document, oid::ObjectId, Document};
+use mongodb::Collection;
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /movies/{id}/revisions  — admin only
+///
+/// Newest first. Each revision lists the fields that changed with their
+/// before and after values.
+pub async fn get_movie_revisions(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let revisions = revision_collection
+        .find(doc! { "movie_id": movie_id.into_inner() })
+        .sort(doc! { "rev": -1 })
+        .await;
+
+    match revisions {
+        Ok(cursor) => match cursor.try_collect::<Vec<MovieRevision>>().await {
+            Ok(revisions) => HttpResponse::Ok().json(revisions),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// POST /movies/{id}/revisions/{rev}/restore  — admin only
+///
+/// Rolls the title's metadata and translations back to how they were right
+/// after revision `rev` by undoing every later change. Status, subtitles and
+/// trash state are left alone. The restore itself is recorded as a new revision.
+pub async fn restore_movie_revision(
+    req: HttpRequest,
+    path: web::Path<(String, i64)>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (movie_id, rev) = path.into_inner();
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    match revision_collection
+        .count_documents(doc! { "movie_id": &movie_id, "rev": rev })
+        .await
+    {
+        Ok(0) => return HttpResponse::NotFound().body("Revision not found"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let later: Vec<MovieRevision> = match revision_collection
+        .find(doc! { "movie_id": &movie_id, "rev": { "$gt": rev } })
+        .sort(doc! { "rev": 1 })
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect().await {
+            Ok(later) => later,
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let Some(update) = restore_update(&later) else {
+        return HttpResponse::Ok().body("The movie already matches that revision");
+    };
+
+    let movies = movie_collection.clone_with_type::<Document>();
+    let filter = doc! { "_id": oid, "deleted_at": null };
+
+    let restored = match write_movie(&movies, &revision_collection, filter, update, &actor).await {
+        Ok(Some(restored)) => restored,
+        Ok(None) => return HttpResponse::NotFound().body("Movie not found"),
+        Err(e @ RevisionError::Conflict) => return HttpResponse::Conflict().body(e.to_string()),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    if let Err(e) = invalidate_smart_lists(&list_collection).await {
+        log::warn!("Failed to invalidate smart lists: {}", e);
+    }
+
+    match from_document::<Movie>(restored) {
+        Ok(movie) => HttpResponse::Ok().json(movie),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
@@ -0,0 +1,96 @@
+use crate::catalog::and;
+use crate::geo::{viewer, RegionResolver};
+use crate::locale::preferred_locales;
+use crate::models::movie::{Movie, MovieSummary};
+use crate::models::person::Person;
+use crate::routes::auth::{claims_is_admin, require_auth};
+use crate::search::{display_title_key, prefix_filter};
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::doc;
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    /// ?q=keanu  — at least two characters
+    q: String,
+
+    /// ?limit=20  — per result kind, capped at 50
+    limit: Option<i64>,
+
+    /// ?region=BR  — admins only: search as a user from that region
+    region: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SearchResults {
+    movies: Vec<MovieSummary>,
+    people: Vec<Person>,
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /search?q=&limit=&region=  — any authenticated user
+///
+/// Titles the caller may watch with a word of the original or a translated
+/// title starting with `q`, sorted by the title shown to the caller, and
+/// people with a word of their name starting with `q`.
+pub async fn search(
+    req: HttpRequest,
+    query: web::Query<SearchQuery>,
+    movie_collection: web::Data<Collection<Movie>>,
+    person_collection: web::Data<Collection<Person>>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let claims = match require_auth(req.clone()).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    let is_admin = claims_is_admin(&claims);
+    let viewer = viewer(&req, &region_resolver, is_admin, query.region.as_deref());
+    let locales = preferred_locales(&req, &claims, None);
+
+    let q = query.q.trim();
+    if q.chars().count() < 2 {
+        return HttpResponse::BadRequest().body("q must be at least two characters.");
+    }
+    let limit = query.limit.unwrap_or(20).clamp(1, 50);
+    let Some(prefix) = prefix_filter(q) else {
+        return HttpResponse::BadRequest().body("q must contain letters or digits.");
+    };
+
+    let pipeline = vec![
+        doc! { "$match": and(prefix.clone(), viewer.movie_filter()) },
+        doc! { "$addFields": { "display_title": display_title_key(&locales) } },
+        doc! { "$sort": { "display_title": 1, "_id": 1 } },
+        doc! { "$limit": limit },
+        doc! { "$project": MovieSummary::projection() },
+    ];
+    let movies = movie_collection
+        .aggregate(pipeline)
+        .with_type::<MovieSummary>();
+    let people = person_collection
+        .find(prefix)
+        .sort(doc! { "name": 1 })
+        .limit(limit);
+
+    let (movies, people) = match (movies.await, people.await) {
+        (Ok(movies), Ok(people)) => (movies, people),
+        (Err(e), _) | (_, Err(e)) => {
+            return HttpResponse::InternalServerError().body(e.to_string())
+        }
+    };
+    match (
+        movies.try_collect::<Vec<MovieSummary>>().await,
+        people.try_collect().await,
+    ) {
+        (Ok(mut movies), Ok(people)) => {
+            movies.iter_mut().for_each(|movie| movie.localize(&locales));
+            HttpResponse::Ok().json(SearchResults { movies, people })
+        }
+        (Err(e), _) | (_, Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
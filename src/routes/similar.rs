@@ -0,0 +1,137 @@
+use crate::catalog::and;
+use crate::geo::{viewer, RegionResolver};
+use crate::locale::preferred_locales;
+use crate::models::movie::{within_age, Movie, MovieSummary};
+use crate::routes::auth::{claims_is_admin, require_auth};
+use crate::similar::SimilarityIndex;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct SimilarQuery {
+    /// ?limit=12  — capped at 50
+    limit: Option<usize>,
+
+    /// ?max_age=13  — drop titles rated for older viewers or not rated at all
+    max_age: Option<u32>,
+
+    /// ?region=BR  — admins only: preview as a user from that region
+    region: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SimilarTitle {
+    #[serde(flatten)]
+    movie: MovieSummary,
+
+    /// Similarity to the source title, 0 to 1.
+    score: f64,
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /movies/{id}/similar?limit=&max_age=&region=  — any authenticated user
+///
+/// "More like this": titles the caller may watch, ranked by description
+/// (TF-IDF), genre, cast and crew, year and series/movie type similarity to
+/// the source.
+pub async fn get_similar_movies(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    query: web::Query<SimilarQuery>,
+    movie_collection: web::Data<Collection<Movie>>,
+    region_resolver: web::Data<RegionResolver>,
+    similarity_index: web::Data<SimilarityIndex>,
+) -> HttpResponse {
+    let claims = match require_auth(req.clone()).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    let is_admin = claims_is_admin(&claims);
+    let viewer = viewer(&req, &region_resolver, is_admin, query.region.as_deref());
+    let locales = preferred_locales(&req, &claims, None);
+    let limit = query.limit.unwrap_or(12).clamp(1, 50);
+
+    let movie_id = movie_id.into_inner();
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    let movies = movie_collection.clone_with_type::<Document>();
+    match movies
+        .count_documents(and(doc! { "_id": oid }, viewer.movie_filter()))
+        .await
+    {
+        Ok(0) => return HttpResponse::NotFound().finish(),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    // Rank from the index, then check visibility only on the best few; if
+    // too many of those are hidden from this viewer, look further down.
+    let corpus = similarity_index.snapshot();
+    let mut fetch = limit * 4;
+    let mut checked = 0;
+    let mut top: Vec<(String, f64)> = Vec::new();
+    let mut by_id: HashMap<String, MovieSummary> = HashMap::new();
+    loop {
+        // A title added since the last index refresh has no features yet.
+        let Some(ranked) = corpus.rank(&movie_id, fetch) else {
+            return HttpResponse::Ok().json(Vec::<SimilarTitle>::new());
+        };
+        let batch = &ranked[checked.min(ranked.len())..];
+        let ids: Vec<ObjectId> = batch
+            .iter()
+            .filter_map(|(id, _)| ObjectId::parse_str(id).ok())
+            .collect();
+
+        let summaries: Vec<MovieSummary> = match movie_collection
+            .clone_with_type::<MovieSummary>()
+            .find(and(doc! { "_id": { "$in": ids } }, viewer.movie_filter()))
+            .projection(MovieSummary::projection())
+            .await
+        {
+            Ok(cursor) => match cursor.try_collect().await {
+                Ok(summaries) => summaries,
+                Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+            },
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        };
+        for mut movie in summaries {
+            if !within_age(movie.limit.as_deref(), query.max_age) {
+                continue;
+            }
+            movie.localize(&locales);
+            if let Some(id) = movie.id {
+                by_id.insert(id.to_hex(), movie);
+            }
+        }
+        top.extend(
+            batch
+                .iter()
+                .filter(|(id, _)| by_id.contains_key(id))
+                .cloned(),
+        );
+
+        checked = ranked.len();
+        if top.len() >= limit || ranked.len() < fetch {
+            break;
+        }
+        fetch *= 4;
+    }
+    top.truncate(limit);
+
+    let similar: Vec<SimilarTitle> = top
+        .into_iter()
+        .filter_map(|(id, score)| by_id.remove(&id).map(|movie| SimilarTitle { movie, score }))
+        .collect();
+
+    HttpResponse::Ok().json(similar)
+}
@@ -0,0 +1,268 @@
+use crate::activity::ViewLog;
+use crate::catalog::and;
+use crate::geo::{user_viewer, RegionResolver};
+use crate::models::movie::Movie;
+use crate::models::stream::{stream_cap, StreamLease};
+use crate::models::users::Users;
+use crate::reviews::is_duplicate_key;
+use crate::routes::auth::require_user;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime, Document};
+use mongodb::Collection;
+use serde::Deserialize;
+use serde_json::json;
+use std::env;
+
+// ── Lease lifetime ────────────────────────────────────────────────────────────
+
+/// Seconds a lease survives without a heartbeat (`STREAM_LEASE_TTL_SECS`, default 90).
+pub fn lease_ttl_secs() -> u64 {
+    env::var("STREAM_LEASE_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(90)
+}
+
+/// Heartbeats older than this belong to dead leases.
+fn stale_cutoff() -> DateTime {
+    DateTime::from_millis(DateTime::now().timestamp_millis() - lease_ttl_secs() as i64 * 1000)
+}
+
+/// Filter matching the caller's leases that are still alive.
+/// The TTL monitor only runs about once a minute, so reads check freshness too.
+fn active_filter(user: &str) -> Document {
+    doc! { "user": user, "heartbeat_at": { "$gte": stale_cutoff() } }
+}
+
+/// Inserts `lease` into the first of the `cap` slots that is free or held by
+/// a dead lease. The unique `(user, slot)` index makes the claim atomic, so
+/// parallel starts can never hold more than `cap` slots between them.
+/// `None` when every slot is held by a live lease.
+async fn claim_slot(
+    stream_collection: &Collection<StreamLease>,
+    mut lease: StreamLease,
+    cap: u64,
+) -> mongodb::error::Result<Option<Bson>> {
+    for slot in 0..cap as u32 {
+        lease.slot = Some(slot);
+        match stream_collection.insert_one(&lease).await {
+            Ok(result) => return Ok(Some(result.inserted_id)),
+            Err(e) if is_duplicate_key(&e) => {}
+            Err(e) => return Err(e),
+        }
+
+        // The slot is taken; free it if its lease stopped heartbeating before
+        // the TTL monitor got to it, then try once more.
+        let stale = doc! {
+            "user": &lease.user,
+            "slot": slot as i64,
+            "heartbeat_at": { "$lt": stale_cutoff() },
+        };
+        if stream_collection.delete_one(stale).await?.deleted_count == 0 {
+            continue;
+        }
+        match stream_collection.insert_one(&lease).await {
+            Ok(result) => return Ok(Some(result.inserted_id)),
+            Err(e) if is_duplicate_key(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+/// The 409 returned when the account is already at its plan's stream cap.
+async fn at_cap(stream_collection: &Collection<StreamLease>, user: &str, cap: u64) -> HttpResponse {
+    let active = match stream_collection.find(active_filter(user)).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<StreamLease>>().await {
+            Ok(leases) => leases,
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    HttpResponse::Conflict().json(json!({
+        "error": format!("Your plan allows {} simultaneous stream(s).", cap),
+        "max_streams": cap,
+        "active_streams": active,
+    }))
+}
+
+// ── Input structs ─────────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct StartStreamInput {
+    pub movie_id: String,
+    pub device: String,
+}
+
+#[derive(Deserialize)]
+pub struct HeartbeatInput {
+    pub position_secs: Option<u64>,
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// POST /me/streams  — any authenticated user
+///
+/// Registers a playback lease, or rejects with 409 and the list of active
+/// devices when the account is already at its plan's stream cap.
+pub async fn start_stream(
+    req: HttpRequest,
+    input: web::Json<StartStreamInput>,
+    stream_collection: web::Data<Collection<StreamLease>>,
+    users_collection: web::Data<Collection<Users>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    view_log: web::Data<ViewLog>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let viewer = user_viewer(&req, &region_resolver, None);
+    let user = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    if input.device.is_empty() {
+        return HttpResponse::BadRequest().body("device is required.");
+    }
+
+    let movie_oid = match ObjectId::parse_str(&input.movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    // Only titles inside their licence window and licensed in the caller's region can be streamed.
+    let playable = and(doc! { "_id": movie_oid }, viewer.movie_filter());
+    match movie_collection.count_documents(playable).await {
+        Ok(0) => return HttpResponse::NotFound().body("Movie not available"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let plan = match users_collection.find_one(doc! { "email": &user }).await {
+        Ok(Some(account)) => account.plan,
+        Ok(None) => return HttpResponse::Unauthorized().finish(),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let cap = stream_cap(plan.as_deref());
+
+    // Counting live leases also catches leases above the cap after a plan
+    // downgrade, which hold slots the claim below never looks at.
+    match stream_collection.count_documents(active_filter(&user)).await {
+        Ok(active) if active >= cap => return at_cap(&stream_collection, &user, cap).await,
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let now = DateTime::now();
+    let lease = StreamLease {
+        id: None,
+        user: user.clone(),
+        movie_id: input.movie_id.clone(),
+        device: input.device.clone(),
+        slot: None,
+        position_secs: None,
+        started_at: now,
+        heartbeat_at: now,
+    };
+
+    let lease_id = match claim_slot(&stream_collection, lease, cap).await {
+        Ok(Some(lease_id)) => lease_id,
+        Ok(None) => return at_cap(&stream_collection, &user, cap).await,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    // Watch history feeds recommendations and charts; a failure here must not stop playback.
+    if let Err(e) = view_log
+        .record(&user, &input.movie_id, viewer.region.clone())
+        .await
+    {
+        log::warn!("Failed to record view of movie {}: {}", input.movie_id, e);
+    }
+
+    HttpResponse::Created().json(lease_id)
+}
+
+/// POST /me/streams/{id}/heartbeat  — any authenticated user
+///
+/// Keeps the lease alive and records playback progress. Returns 410 once the
+/// lease has expired or was stopped remotely, telling the player to halt.
+pub async fn heartbeat_stream(
+    req: HttpRequest,
+    lease_id: web::Path<String>,
+    input: web::Json<HeartbeatInput>,
+    stream_collection: web::Data<Collection<StreamLease>>,
+) -> HttpResponse {
+    let user = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    let lease_id = match ObjectId::parse_str(lease_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid stream ID format."),
+    };
+
+    let mut filter = active_filter(&user);
+    filter.insert("_id", lease_id);
+
+    let mut set = doc! { "heartbeat_at": DateTime::now() };
+    if let Some(position) = input.position_secs {
+        set.insert("position_secs", position as i64);
+    }
+
+    match stream_collection.update_one(filter, doc! { "$set": set }).await {
+        Ok(result) if result.matched_count == 0 => {
+            HttpResponse::Gone().body("Stream was stopped or has expired.")
+        }
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /me/streams  — any authenticated user
+pub async fn get_streams(
+    req: HttpRequest,
+    stream_collection: web::Data<Collection<StreamLease>>,
+) -> HttpResponse {
+    let user = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    match stream_collection.find(active_filter(&user)).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<StreamLease>>().await {
+            Ok(leases) => HttpResponse::Ok().json(leases),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// DELETE /me/streams/{id}  — any authenticated user
+///
+/// Remote "stop": drops the lease so the device's next heartbeat fails.
+pub async fn stop_stream(
+    req: HttpRequest,
+    lease_id: web::Path<String>,
+    stream_collection: web::Data<Collection<StreamLease>>,
+) -> HttpResponse {
+    let user = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    let lease_id = match ObjectId::parse_str(lease_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid stream ID format."),
+    };
+
+    match stream_collection
+        .delete_one(doc! { "_id": lease_id, "user": &user })
+        .await
+    {
+        Ok(result) if result.deleted_count == 0 => HttpResponse::NotFound().body("Stream not found"),
+        Ok(_) => HttpResponse::Ok().body("The stream has been stopped"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
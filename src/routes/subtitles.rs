@@ -0,0 +1,412 @@
+use crate::catalog::and;
+use crate::geo::{viewer, RegionResolver};
+use crate::models::movie::Movie;
+use crate::models::revision::MovieRevision;
+use crate::models::subtitle::{AudioTrack, Subtitle, SubtitleTrack};
+use crate::revisions::{write_movie, RevisionError};
+use crate::routes::auth::{claims_email, claims_is_admin, require_admin, require_auth};
+use crate::subtitles::{
+    duration_ms, parse_cues, render_master_playlist, render_subtitle_playlist, to_webvtt,
+};
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use mongodb::bson::{doc, oid::ObjectId, to_bson, DateTime, Document};
+use mongodb::options::ReplaceOptions;
+use mongodb::Collection;
+use serde::Deserialize;
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct SubtitleQuery {
+    /// ?episode=s01e02  — omit for a movie
+    episode: Option<String>,
+
+    /// ?label=English (SDH)  — upload only
+    label: Option<String>,
+
+    /// ?region=BR  — admins only: fetch as a user from that region
+    region: Option<String>,
+}
+
+/// Accepts BCP 47-style tags such as `en`, `pt-BR` or `zh-Hant`.
+fn is_valid_lang(lang: &str) -> bool {
+    (2..=35).contains(&lang.len()) && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters, for a
+/// query parameter value.
+fn encode_query_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// URL of a subtitle as WebVTT (`ext` = `vtt`) or as an HLS media playlist (`m3u8`).
+fn subtitle_url(movie_id: &str, lang: &str, episode: Option<&str>, ext: &str) -> String {
+    match episode {
+        Some(ep) => format!(
+            "/api/movies/{}/subtitles/{}.{}?episode={}",
+            movie_id,
+            lang,
+            ext,
+            encode_query_value(ep)
+        ),
+        None => format!("/api/movies/{}/subtitles/{}.{}", movie_id, lang, ext),
+    }
+}
+
+/// The movie, if the caller may watch it: subtitles and playlists follow the
+/// same availability and region rules as the title itself.
+async fn playable_movie(
+    req: &HttpRequest,
+    movie_id: &str,
+    region: Option<&str>,
+    movie_collection: &Collection<Movie>,
+    region_resolver: &RegionResolver,
+) -> Result<Movie, HttpResponse> {
+    let claims = require_auth(req.clone()).await?;
+    let viewer = viewer(req, region_resolver, claims_is_admin(&claims), region);
+
+    let oid = ObjectId::parse_str(movie_id)
+        .map_err(|_| HttpResponse::BadRequest().body("Invalid movie ID format."))?;
+    match movie_collection
+        .find_one(and(doc! { "_id": oid }, viewer.movie_filter()))
+        .await
+    {
+        Ok(Some(movie)) => Ok(movie),
+        Ok(None) => Err(HttpResponse::NotFound().body("Movie not available")),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+/// The stored subtitle for a language and episode.
+async fn find_subtitle(
+    subtitle_collection: &Collection<Subtitle>,
+    movie_id: &str,
+    lang: &str,
+    episode: &Option<String>,
+) -> Result<Subtitle, HttpResponse> {
+    let filter = doc! { "movie_id": movie_id, "lang": lang, "episode": episode };
+    match subtitle_collection.find_one(filter).await {
+        Ok(Some(subtitle)) => Ok(subtitle),
+        Ok(None) => Err(HttpResponse::NotFound().finish()),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// PUT /movies/{id}/subtitles/{lang}?episode=&label=  — admin only
+///
+/// Body is the raw SRT or WebVTT file. SRT is converted to WebVTT; both are
+/// checked for well-formed, ordered timings before anything is stored.
+pub async fn upload_subtitle(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<SubtitleQuery>,
+    body: web::Bytes,
+    movie_collection: web::Data<Collection<Movie>>,
+    subtitle_collection: web::Data<Collection<Subtitle>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (movie_id, lang) = path.into_inner();
+    if !is_valid_lang(&lang) {
+        return HttpResponse::BadRequest().body("Invalid language tag.");
+    }
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    let (vtt, cue_count) = match to_webvtt(&body) {
+        Ok(converted) => converted,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    match movie_collection
+        .count_documents(doc! { "_id": oid, "deleted_at": null })
+        .await
+    {
+        Ok(0) => return HttpResponse::NotFound().body("Movie not found"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let subtitle = Subtitle {
+        id: None,
+        movie_id: movie_id.clone(),
+        episode: query.episode.clone(),
+        lang: lang.clone(),
+        vtt,
+        cue_count: cue_count as u32,
+        created_at: Some(DateTime::now()),
+        uploaded_by: Some(actor.clone()),
+    };
+    let key = doc! { "movie_id": &movie_id, "lang": &lang, "episode": &query.episode };
+    let upsert = ReplaceOptions::builder().upsert(true).build();
+    if let Err(e) = subtitle_collection
+        .replace_one(key, subtitle)
+        .with_options(upsert)
+        .await
+    {
+        return HttpResponse::InternalServerError().body(e.to_string());
+    }
+
+    let track = SubtitleTrack {
+        url: subtitle_url(&movie_id, &lang, query.episode.as_deref(), "vtt"),
+        lang: lang.clone(),
+        label: query.label.clone(),
+        episode: query.episode.clone(),
+    };
+    let track = match to_bson(&track) {
+        Ok(bson) => bson,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    // Replaces any previous track for this language in a single write, so the
+    // swap is recorded as one revision.
+    let same_track = doc! {
+        "$and": [
+            { "$eq": ["$$this.lang", &lang] },
+            { "$eq": [{ "$ifNull": ["$$this.episode", null] }, &query.episode] },
+        ]
+    };
+    let others = doc! {
+        "$filter": {
+            "input": { "$ifNull": ["$subtitles", []] },
+            "cond": { "$not": [same_track] },
+        }
+    };
+    let replace = vec![doc! {
+        "$set": { "subtitles": { "$concatArrays": [others, { "$literal": [track] }] } }
+    }];
+
+    let movies = movie_collection.clone_with_type::<Document>();
+    let filter = doc! { "_id": oid, "deleted_at": null };
+
+    match write_movie(&movies, &revision_collection, filter, replace, &actor).await {
+        Ok(Some(_)) => HttpResponse::Ok().body(format!("Stored {} cues", cue_count)),
+        Ok(None) => HttpResponse::NotFound().body("Movie not found"),
+        Err(e @ RevisionError::Conflict) => HttpResponse::Conflict().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /movies/{id}/subtitles/{lang}.vtt?episode=&region=  — any authenticated user
+pub async fn get_subtitle(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<SubtitleQuery>,
+    movie_collection: web::Data<Collection<Movie>>,
+    subtitle_collection: web::Data<Collection<Subtitle>>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let (movie_id, lang) = path.into_inner();
+    let region = query.region.as_deref();
+    if let Err(res) =
+        playable_movie(&req, &movie_id, region, &movie_collection, &region_resolver).await
+    {
+        return res;
+    }
+
+    match find_subtitle(&subtitle_collection, &movie_id, &lang, &query.episode).await {
+        // Private: availability depends on the caller's region and the time.
+        Ok(subtitle) => HttpResponse::Ok()
+            .content_type("text/vtt; charset=utf-8")
+            .insert_header((header::CACHE_CONTROL, "private, max-age=3600"))
+            .body(subtitle.vtt),
+        Err(res) => res,
+    }
+}
+
+/// GET /movies/{id}/subtitles/{lang}.m3u8?episode=&region=  — any authenticated user
+///
+/// The subtitle as a one-segment HLS media playlist, as referenced from the
+/// master playlist.
+pub async fn get_subtitle_playlist(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<SubtitleQuery>,
+    movie_collection: web::Data<Collection<Movie>>,
+    subtitle_collection: web::Data<Collection<Subtitle>>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let (movie_id, lang) = path.into_inner();
+    let region = query.region.as_deref();
+    if let Err(res) =
+        playable_movie(&req, &movie_id, region, &movie_collection, &region_resolver).await
+    {
+        return res;
+    }
+
+    let subtitle = match find_subtitle(&subtitle_collection, &movie_id, &lang, &query.episode).await
+    {
+        Ok(subtitle) => subtitle,
+        Err(res) => return res,
+    };
+    // Stored documents were validated on upload, so this only fails on corrupt data.
+    let duration = match parse_cues(&subtitle.vtt) {
+        Ok(cues) => duration_ms(&cues),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let vtt_url = subtitle_url(&movie_id, &lang, query.episode.as_deref(), "vtt");
+    HttpResponse::Ok()
+        .content_type("application/vnd.apple.mpegurl")
+        .insert_header((header::CACHE_CONTROL, "private, max-age=3600"))
+        .body(render_subtitle_playlist(&vtt_url, duration))
+}
+
+/// GET /movies/{id}/master.m3u8?episode=&region=  — any authenticated user
+///
+/// HLS master playlist for the title's video, with its audio tracks and
+/// subtitles as alternative renditions. `?episode=` picks that episode's tracks.
+pub async fn get_master_playlist(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    query: web::Query<SubtitleQuery>,
+    movie_collection: web::Data<Collection<Movie>>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let movie_id = movie_id.into_inner();
+    let region = query.region.as_deref();
+    let movie =
+        match playable_movie(&req, &movie_id, region, &movie_collection, &region_resolver).await {
+            Ok(movie) => movie,
+            Err(res) => return res,
+        };
+    let Some(video) = movie.video.as_deref() else {
+        return HttpResponse::NotFound().body("The title has no video");
+    };
+
+    let audio_tracks: Vec<&AudioTrack> = movie
+        .audio_tracks
+        .iter()
+        .filter(|track| track.episode == query.episode)
+        .collect();
+    let subtitles: Vec<(&SubtitleTrack, String)> = movie
+        .subtitles
+        .iter()
+        .filter(|track| track.episode == query.episode)
+        .map(|track| {
+            let playlist = subtitle_url(&movie_id, &track.lang, track.episode.as_deref(), "m3u8");
+            (track, playlist)
+        })
+        .collect();
+
+    HttpResponse::Ok()
+        .content_type("application/vnd.apple.mpegurl")
+        .insert_header((header::CACHE_CONTROL, "private, no-cache"))
+        .body(render_master_playlist(video, &audio_tracks, &subtitles))
+}
+
+/// DELETE /movies/{id}/subtitles/{lang}?episode=  — admin only
+pub async fn delete_subtitle(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<SubtitleQuery>,
+    movie_collection: web::Data<Collection<Movie>>,
+    subtitle_collection: web::Data<Collection<Subtitle>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (movie_id, lang) = path.into_inner();
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    // The track comes off the movie first, so a failure never leaves the
+    // movie listing a subtitle that is gone.
+    let movies = movie_collection.clone_with_type::<Document>();
+    let filter = doc! { "_id": oid };
+    let pull = doc! { "$pull": { "subtitles": { "lang": &lang, "episode": &query.episode } } };
+    match write_movie(&movies, &revision_collection, filter, pull, &actor).await {
+        Ok(_) => {}
+        Err(e @ RevisionError::Conflict) => return HttpResponse::Conflict().body(e.to_string()),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    let filter = doc! { "movie_id": &movie_id, "lang": &lang, "episode": &query.episode };
+    match subtitle_collection.delete_one(filter).await {
+        Ok(result) if result.deleted_count == 0 => {
+            HttpResponse::NotFound().body("Subtitle not found")
+        }
+        Ok(_) => HttpResponse::Ok().body("The subtitle has been deleted"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// PUT /movies/{id}/audio-tracks  — admin only
+///
+/// Replaces the movie's audio-track list.
+pub async fn set_audio_tracks(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    tracks: web::Json<Vec<AudioTrack>>,
+    movie_collection: web::Data<Collection<Movie>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let oid = match ObjectId::parse_str(movie_id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    if let Some(bad) = tracks.iter().find(|t| !is_valid_lang(&t.lang)) {
+        return HttpResponse::BadRequest().body(format!("Invalid language tag: {}", bad.lang));
+    }
+
+    let tracks = match to_bson(&tracks.into_inner()) {
+        Ok(bson) => bson,
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let movies = movie_collection.clone_with_type::<Document>();
+    let filter = doc! { "_id": oid, "deleted_at": null };
+    let update = doc! { "$set": { "audio_tracks": tracks } };
+
+    match write_movie(&movies, &revision_collection, filter, update, &actor).await {
+        Ok(Some(_)) => HttpResponse::Ok().body("Audio tracks updated"),
+        Ok(None) => HttpResponse::NotFound().body("Movie not found"),
+        Err(e @ RevisionError::Conflict) => HttpResponse::Conflict().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtitle_url_encodes_the_episode() {
+        assert_eq!(
+            subtitle_url("m1", "pt-BR", Some("s01 e02&x=1"), "vtt"),
+            "/api/movies/m1/subtitles/pt-BR.vtt?episode=s01%20e02%26x%3D1"
+        );
+        assert_eq!(
+            subtitle_url("m1", "en", None, "m3u8"),
+            "/api/movies/m1/subtitles/en.m3u8"
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use crate::catalog::{and, not_deleted};
+use crate::locale::{normalize_locale, DEFAULT_LOCALE};
+use crate::models::genre::Genre;
+use crate::models::list::{List, ListTranslation};
+use crate::models::movie::{Movie, MovieTranslation};
+use crate::models::revision::MovieRevision;
+use crate::revisions::{write_movie, RevisionError};
+use crate::routes::auth::{claims_email, require_admin};
+use crate::translations::coverage;
+use actix_web::{web, HttpRequest, HttpResponse};
+use mongodb::bson::{self, doc, oid::ObjectId, DateTime, Document};
+use mongodb::Collection;
+use serde::Deserialize;
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct CoverageQuery {
+    /// ?locales=pt-BR,es  — defaults to every locale with at least one translation
+    locales: Option<String>,
+}
+
+/// The translated text for one locale. Movies take `title`, `desc` and
+/// `img_title`, lists `title` and genres `name`.
+#[derive(Deserialize)]
+pub struct TranslationInput {
+    pub title: Option<String>,
+    pub desc: Option<String>,
+    pub img_title: Option<String>,
+    pub name: Option<String>,
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Trims `value`, treating blank text as absent.
+fn text(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn parse_oid(id: &str) -> Result<ObjectId, HttpResponse> {
+    ObjectId::parse_str(id).map_err(|_| HttpResponse::BadRequest().body("Invalid ID format."))
+}
+
+/// The canonical locale from the path. English is the untranslated text of
+/// movies and lists, so only genres accept it.
+fn parse_locale(kind: &str, locale: &str) -> Result<String, HttpResponse> {
+    let Some(locale) = normalize_locale(locale) else {
+        return Err(HttpResponse::BadRequest().body(format!("Invalid locale: {}", locale)));
+    };
+    if locale == DEFAULT_LOCALE && kind != "genres" {
+        return Err(HttpResponse::BadRequest()
+            .body("English is the original text; edit the item itself instead."));
+    }
+    Ok(locale)
+}
+
+/// The `$set` value of a translation of `kind`, rejecting fields that kind
+/// does not have.
+fn translation_value(kind: &str, input: TranslationInput) -> Result<bson::Bson, HttpResponse> {
+    let TranslationInput {
+        title,
+        desc,
+        img_title,
+        name,
+    } = input;
+    let (title, desc, img_title, name) = (text(title), text(desc), text(img_title), text(name));
+
+    let value = match kind {
+        "movies" if name.is_none() => {
+            if title.is_none() && desc.is_none() && img_title.is_none() {
+                return Err(HttpResponse::BadRequest()
+                    .body("A movie translation needs a title, desc or img_title."));
+            }
+            bson::to_bson(&MovieTranslation {
+                title,
+                desc,
+                img_title,
+            })
+        }
+        "lists" if name.is_none() && desc.is_none() && img_title.is_none() => match title {
+            Some(title) => bson::to_bson(&ListTranslation { title: Some(title) }),
+            None => {
+                return Err(HttpResponse::BadRequest().body("A list translation needs a title."))
+            }
+        },
+        "genres" if title.is_none() && desc.is_none() && img_title.is_none() => match name {
+            Some(name) => Ok(bson::Bson::String(name)),
+            None => {
+                return Err(HttpResponse::BadRequest().body("A genre translation needs a name."))
+            }
+        },
+        "movies" | "lists" | "genres" => {
+            return Err(HttpResponse::BadRequest().body(
+                "Movies take title, desc and img_title; lists take title; genres take name.",
+            ))
+        }
+        _ => return Err(HttpResponse::NotFound().body("Unknown translation kind")),
+    };
+    value.map_err(|e| HttpResponse::InternalServerError().body(e.to_string()))
+}
+
+/// Applies `update` to a movie outside the trash, recording it as a revision.
+async fn write_movie_translation(
+    movie_collection: &Collection<Movie>,
+    revision_collection: &Collection<MovieRevision>,
+    oid: ObjectId,
+    update: Document,
+    actor: &str,
+) -> Result<(), HttpResponse> {
+    let movies = movie_collection.clone_with_type::<Document>();
+    let filter = and(doc! { "_id": oid }, not_deleted());
+
+    match write_movie(&movies, revision_collection, filter, update, actor).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::NotFound().body("Movie not found")),
+        Err(e @ RevisionError::Conflict) => Err(HttpResponse::Conflict().body(e.to_string())),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+/// Applies `update` to a list or genre, answering 404 when `filter` matches nothing.
+async fn write_translation<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    update: Document,
+    not_found: &'static str,
+) -> Result<(), HttpResponse>
+where
+    T: Send + Sync,
+{
+    match collection.update_one(filter, update).await {
+        Ok(result) if result.matched_count == 0 => Err(HttpResponse::NotFound().body(not_found)),
+        Ok(_) => Ok(()),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /admin/translations/coverage?locales=  — admin only
+///
+/// Per locale, how much of the catalog a viewer of that locale sees
+/// translated, with the ids still missing a translation.
+pub async fn get_translation_coverage(
+    req: HttpRequest,
+    query: web::Query<CoverageQuery>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    genre_collection: web::Data<Collection<Genre>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let mut locales = Vec::new();
+    for raw in query.locales.as_deref().unwrap_or("").split(',') {
+        if raw.trim().is_empty() {
+            continue;
+        }
+        match normalize_locale(raw) {
+            Some(locale) => locales.push(locale),
+            None => return HttpResponse::BadRequest().body(format!("Invalid locale: {}", raw)),
+        }
+    }
+
+    match coverage(
+        &movie_collection,
+        &list_collection,
+        &genre_collection,
+        locales,
+    )
+    .await
+    {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /admin/translations/{kind}/{id}  — admin only
+///
+/// `kind` is `movies` or `lists` (by ID), or `genres` (by slug). Returns the
+/// translations per locale; for genres, the names per locale.
+pub async fn get_translations(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    genre_collection: web::Data<Collection<Genre>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let (kind, id) = path.into_inner();
+    let found = match kind.as_str() {
+        "genres" => {
+            return match genre_collection.find_one(doc! { "slug": &id }).await {
+                Ok(Some(genre)) => HttpResponse::Ok().json(genre.names),
+                Ok(None) => HttpResponse::NotFound().body("Genre not found"),
+                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            }
+        }
+        "movies" | "lists" => {
+            let oid = match parse_oid(&id) {
+                Ok(oid) => oid,
+                Err(res) => return res,
+            };
+            let filter = and(doc! { "_id": oid }, not_deleted());
+            let projection = doc! { "translations": 1 };
+            if kind == "movies" {
+                movie_collection
+                    .clone_with_type::<Document>()
+                    .find_one(filter)
+                    .projection(projection)
+                    .await
+            } else {
+                list_collection
+                    .clone_with_type::<Document>()
+                    .find_one(filter)
+                    .projection(projection)
+                    .await
+            }
+        }
+        _ => return HttpResponse::NotFound().body("Unknown translation kind"),
+    };
+
+    match found {
+        Ok(Some(document)) => {
+            let translations = document.get_document("translations").cloned();
+            HttpResponse::Ok().json(translations.unwrap_or_default())
+        }
+        Ok(None) => HttpResponse::NotFound().body("Not found"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// PUT /admin/translations/{kind}/{id}/{locale}  — admin only
+///
+/// Replaces the item's translation for one locale.
+pub async fn set_translation(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    input: web::Json<TranslationInput>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    genre_collection: web::Data<Collection<Genre>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (kind, id, locale) = path.into_inner();
+    let value = match translation_value(&kind, input.into_inner()) {
+        Ok(value) => value,
+        Err(res) => return res,
+    };
+    let locale = match parse_locale(&kind, &locale) {
+        Ok(locale) => locale,
+        Err(res) => return res,
+    };
+
+    let written = match kind.as_str() {
+        "genres" => {
+            let update = doc! {
+                "$set": { format!("names.{}", locale): value, "updated_at": DateTime::now() },
+            };
+            write_translation(
+                &genre_collection,
+                doc! { "slug": &id },
+                update,
+                "Genre not found",
+            )
+            .await
+        }
+        _ => {
+            let oid = match parse_oid(&id) {
+                Ok(oid) => oid,
+                Err(res) => return res,
+            };
+            let field = format!("translations.{}", locale);
+            if kind == "movies" {
+                let update = doc! { "$set": { field: value } };
+                write_movie_translation(
+                    &movie_collection,
+                    &revision_collection,
+                    oid,
+                    update,
+                    &actor,
+                )
+                .await
+            } else {
+                // A translation is an edit: clients holding the old version must re-read.
+                let update = doc! {
+                    "$set": { field: value, "updated_at": DateTime::now() },
+                    "$inc": { "version": 1_i64 },
+                };
+                let filter = and(doc! { "_id": oid }, not_deleted());
+                write_translation(&list_collection, filter, update, "List not found").await
+            }
+        }
+    };
+
+    match written {
+        Ok(()) => HttpResponse::Ok().body("Translation saved"),
+        Err(res) => res,
+    }
+}
+
+/// DELETE /admin/translations/{kind}/{id}/{locale}  — admin only
+///
+/// Removes the item's translation for one locale. The English name of a
+/// genre cannot be removed.
+pub async fn delete_translation(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    genre_collection: web::Data<Collection<Genre>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (kind, id, locale) = path.into_inner();
+    if !matches!(kind.as_str(), "movies" | "lists" | "genres") {
+        return HttpResponse::NotFound().body("Unknown translation kind");
+    }
+    let locale = match parse_locale(&kind, &locale) {
+        Ok(locale) => locale,
+        Err(res) => return res,
+    };
+
+    let removed = match kind.as_str() {
+        "genres" => {
+            if locale == DEFAULT_LOCALE {
+                return HttpResponse::BadRequest().body("An English (en) name is required.");
+            }
+            let update = doc! {
+                "$unset": { format!("names.{}", locale): "" },
+                "$set": { "updated_at": DateTime::now() },
+            };
+            write_translation(
+                &genre_collection,
+                doc! { "slug": &id },
+                update,
+                "Genre not found",
+            )
+            .await
+        }
+        _ => {
+            let oid = match parse_oid(&id) {
+                Ok(oid) => oid,
+                Err(res) => return res,
+            };
+            let field = format!("translations.{}", locale);
+            if kind == "movies" {
+                let update = doc! { "$unset": { field: "" } };
+                write_movie_translation(
+                    &movie_collection,
+                    &revision_collection,
+                    oid,
+                    update,
+                    &actor,
+                )
+                .await
+            } else {
+                let update = doc! {
+                    "$unset": { field: "" },
+                    "$set": { "updated_at": DateTime::now() },
+                    "$inc": { "version": 1_i64 },
+                };
+                let filter = and(doc! { "_id": oid }, not_deleted());
+                write_translation(&list_collection, filter, update, "List not found").await
+            }
+        }
+    };
+
+    match removed {
+        Ok(()) => HttpResponse::Ok().body("Translation removed"),
+        Err(res) => res,
+    }
+}
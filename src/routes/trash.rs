@@ -0,0 +1,146 @@
+use crate::models::list::List;
+use crate::models::movie::Movie;
+use crate::models::revision::MovieRevision;
+use crate::models::users::Users;
+use crate::revisions::{write_movie, RevisionError};
+use crate::routes::auth::{claims_email, require_admin};
+use crate::smart_lists::invalidate_smart_lists;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use mongodb::Collection;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Everything in `collection` that has been moved to the trash, most recent first.
+async fn trashed<T>(collection: &Collection<T>) -> HttpResponse
+where
+    T: DeserializeOwned + Serialize + Send + Sync,
+{
+    let cursor = collection
+        .find(doc! { "deleted_at": { "$ne": null } })
+        .sort(doc! { "deleted_at": -1 })
+        .await;
+
+    match cursor {
+        Ok(cursor) => match cursor.try_collect::<Vec<T>>().await {
+            Ok(items) => HttpResponse::Ok().json(items),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Takes one document out of the trash; `extra` is merged into the update.
+async fn restore<T>(
+    collection: &Collection<T>,
+    id: &str,
+    extra: Document,
+) -> Result<(), HttpResponse>
+where
+    T: Send + Sync,
+{
+    let oid = ObjectId::parse_str(id)
+        .map_err(|_| HttpResponse::BadRequest().body("Invalid ID format."))?;
+
+    let mut update = doc! {
+        "$unset": { "deleted_at": "", "deleted_by": "" },
+        "$set": { "updated_at": DateTime::now() },
+    };
+    update.extend(extra);
+
+    match collection
+        .update_one(doc! { "_id": oid, "deleted_at": { "$ne": null } }, update)
+        .await
+    {
+        Ok(result) if result.matched_count == 0 => {
+            Err(HttpResponse::NotFound().body("Not found in the trash"))
+        }
+        Ok(_) => Ok(()),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+/// Takes a movie out of the trash, recording the restore as a revision.
+async fn restore_movie(
+    movie_collection: &Collection<Movie>,
+    revision_collection: &Collection<MovieRevision>,
+    id: &str,
+    actor: &str,
+) -> Result<(), HttpResponse> {
+    let oid = ObjectId::parse_str(id)
+        .map_err(|_| HttpResponse::BadRequest().body("Invalid ID format."))?;
+
+    let movies = movie_collection.clone_with_type::<Document>();
+    let filter = doc! { "_id": oid, "deleted_at": { "$ne": null } };
+    let update = doc! { "$unset": { "deleted_at": "", "deleted_by": "" } };
+
+    match write_movie(&movies, revision_collection, filter, update, actor).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::NotFound().body("Not found in the trash")),
+        Err(e @ RevisionError::Conflict) => Err(HttpResponse::Conflict().body(e.to_string())),
+        Err(e) => Err(HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// GET /trash/{kind}  — admin only
+///
+/// `kind` is `movies`, `lists` or `users`.
+pub async fn get_trash(
+    req: HttpRequest,
+    kind: web::Path<String>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    users_collection: web::Data<Collection<Users>>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    match kind.as_str() {
+        "movies" => trashed(&movie_collection).await,
+        "lists" => trashed(&list_collection).await,
+        "users" => trashed(&users_collection).await,
+        _ => HttpResponse::NotFound().body("Unknown trash kind"),
+    }
+}
+
+/// POST /trash/{kind}/{id}/restore  — admin only
+pub async fn restore_from_trash(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    movie_collection: web::Data<Collection<Movie>>,
+    list_collection: web::Data<Collection<List>>,
+    users_collection: web::Data<Collection<Users>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let (kind, id) = path.into_inner();
+
+    let restored = match kind.as_str() {
+        "movies" => restore_movie(&movie_collection, &revision_collection, &id, &actor).await,
+        // Restoring is an edit: clients holding the trashed version must re-read.
+        "lists" => restore(&list_collection, &id, doc! { "$inc": { "version": 1_i64 } }).await,
+        "users" => restore(&users_collection, &id, doc! {}).await,
+        _ => return HttpResponse::NotFound().body("Unknown trash kind"),
+    };
+    if let Err(res) = restored {
+        return res;
+    }
+
+    if kind == "movies" {
+        if let Err(e) = invalidate_smart_lists(&list_collection).await {
+            log::warn!("Failed to invalidate smart lists: {}", e);
+        }
+    }
+
+    HttpResponse::Ok().body("Restored from the trash")
+}
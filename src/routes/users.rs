@@ -1,8 +1,40 @@
-use crate::routes::auth::{require_admin, require_auth};
+use crate::routes::auth::{claims_email, claims_role, require_admin, require_auth};
 use crate::models::users::Users;
+use crate::models::workflow::EditorialRole;
 use actix_web::{web, HttpRequest, HttpResponse};
 use futures_util::TryStreamExt;
-use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use mongodb::options::ReturnDocument;
+use serde::Deserialize;
+
+// ── Input types ───────────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct RoleInput {
+    /// `editor` or `publisher`.
+    pub role: String,
+}
+
+// ── Roles ─────────────────────────────────────────────────────────────────────
+
+/// Makes every admin a publisher the first time roles are used, so admins
+/// from before the editorial workflow keep their rights. Afterwards admins
+/// without a role are editors until a publisher promotes them.
+pub async fn assign_initial_roles(
+    users_collection: &mongodb::Collection<Users>,
+) -> mongodb::error::Result<()> {
+    let with_role = doc! { "is_admin": true, "role": { "$exists": true } };
+    if users_collection.count_documents(with_role).await? > 0 {
+        return Ok(());
+    }
+    users_collection
+        .update_many(
+            doc! { "is_admin": true, "deleted_at": null },
+            doc! { "$set": { "role": EditorialRole::Publisher.name() } },
+        )
+        .await
+        .map(|_| ())
+}
 
 // ── Handlers ──────────────────────────────────────────────────────────────────
 
@@ -21,7 +53,10 @@ pub async fn get_user(
         Err(_) => return HttpResponse::BadRequest().body("Invalid user ID format."),
     };
 
-    match users_collection.find_one(doc! { "_id": user_id }).await {
+    match users_collection
+        .find_one(doc! { "_id": user_id, "deleted_at": null })
+        .await
+    {
         Ok(Some(user)) => HttpResponse::Ok().json(user),
         Ok(None) => HttpResponse::NotFound().body("User not found."), // was InternalServerError
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
@@ -37,11 +72,96 @@ pub async fn get_all_users(
         return res;
     }
 
-    match users_collection.find(doc! {}).await {
+    match users_collection.find(doc! { "deleted_at": null }).await {
         Ok(cursor) => match cursor.try_collect::<Vec<Users>>().await {
             Ok(users) => HttpResponse::Ok().json(users),
             Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
         },
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
-}
\ No newline at end of file
+}
+
+/// DELETE /users/{id}  — admin only
+///
+/// Moves the account to the trash; it can no longer log in until restored.
+pub async fn delete_user(
+    req: HttpRequest,
+    id: web::Path<String>,
+    users_collection: web::Data<mongodb::Collection<Users>>,
+) -> HttpResponse {
+    let actor = match require_admin(req).await {
+        Ok(claims) => claims_email(&claims),
+        Err(res) => return res,
+    };
+
+    let user_id = match ObjectId::parse_str(id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid user ID format."),
+    };
+
+    let trash = doc! { "$set": { "deleted_at": DateTime::now(), "deleted_by": actor } };
+
+    match users_collection
+        .update_one(doc! { "_id": user_id, "deleted_at": null }, trash)
+        .await
+    {
+        Ok(result) if result.matched_count == 0 => HttpResponse::NotFound().body("User not found."),
+        Ok(_) => HttpResponse::Ok().body("The user has been moved to the trash"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// PUT /users/{id}/role  — publishers only
+///
+/// Sets the editorial role of an admin. Takes effect on their next request.
+/// Publishers cannot demote themselves, so one always remains.
+pub async fn set_user_role(
+    req: HttpRequest,
+    id: web::Path<String>,
+    input: web::Json<RoleInput>,
+    users_collection: web::Data<mongodb::Collection<Users>>,
+) -> HttpResponse {
+    let claims = match require_admin(req).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    if claims_role(&claims) != EditorialRole::Publisher {
+        return HttpResponse::Forbidden().body("Only publishers can assign roles.");
+    }
+
+    let user_id = match ObjectId::parse_str(id.into_inner()) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid user ID format."),
+    };
+    let Some(role) = EditorialRole::parse(&input.role) else {
+        return HttpResponse::BadRequest().body("role must be editor or publisher.");
+    };
+
+    let filter = doc! { "_id": user_id, "deleted_at": null };
+    let target = match users_collection.find_one(filter.clone()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::NotFound().body("User not found."),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    if !target.is_admin {
+        return HttpResponse::BadRequest().body("Only admins have an editorial role.");
+    }
+    if role != EditorialRole::Publisher
+        && target.email.as_deref() == claims.get("sub").map(String::as_str)
+    {
+        return HttpResponse::Conflict().body("Publishers cannot demote themselves.");
+    }
+
+    match users_collection
+        .find_one_and_update(
+            filter,
+            doc! { "$set": { "role": role.name(), "updated_at": DateTime::now() } },
+        )
+        .return_document(ReturnDocument::After)
+        .await
+    {
+        Ok(Some(user)) => HttpResponse::Ok().json(user),
+        Ok(None) => HttpResponse::NotFound().body("User not found."),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
@@ -0,0 +1,192 @@
+use crate::geo::{user_viewer, RegionResolver};
+use crate::locale::preferred_locales;
+use crate::models::movie::{Movie, MovieSummary};
+use crate::models::watchlist::{WatchlistEntry, WatchlistItem};
+use crate::routes::auth::{claims_email, require_auth, require_user};
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime, Document};
+use mongodb::Collection;
+use serde::Deserialize;
+use serde_json::json;
+
+// ── Query param extractor ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    /// ?page=1  — 1-based
+    page: Option<u64>,
+
+    /// ?limit=20  — capped at 100
+    limit: Option<u64>,
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// POST /me/list/{movie_id}  — any authenticated user
+///
+/// Idempotent: adding a title that is already on the list keeps its original date.
+pub async fn add_to_watchlist(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    watchlist_collection: web::Data<Collection<WatchlistEntry>>,
+    movie_collection: web::Data<Collection<Movie>>,
+) -> HttpResponse {
+    let user = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    let movie_id = movie_id.into_inner();
+    let oid = match ObjectId::parse_str(&movie_id) {
+        Ok(oid) => oid,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid movie ID format."),
+    };
+
+    match movie_collection
+        .count_documents(doc! { "_id": oid, "deleted_at": null })
+        .await
+    {
+        Ok(0) => return HttpResponse::NotFound().body("Movie not found"),
+        Ok(_) => {}
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    }
+
+    match watchlist_collection
+        .update_one(
+            doc! { "user": &user, "movie_id": &movie_id },
+            doc! { "$setOnInsert": { "added_at": DateTime::now() } },
+        )
+        .upsert(true)
+        .await
+    {
+        Ok(result) if result.upserted_id.is_some() => HttpResponse::Created().finish(),
+        Ok(_) => HttpResponse::Ok().body("Already on your list"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// DELETE /me/list/{movie_id}  — any authenticated user
+pub async fn remove_from_watchlist(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    watchlist_collection: web::Data<Collection<WatchlistEntry>>,
+) -> HttpResponse {
+    let user = match require_user(req).await {
+        Ok(email) => email,
+        Err(res) => return res,
+    };
+
+    match watchlist_collection
+        .delete_one(doc! { "user": &user, "movie_id": movie_id.into_inner() })
+        .await
+    {
+        Ok(result) if result.deleted_count == 0 => {
+            HttpResponse::NotFound().body("Not on your list")
+        }
+        Ok(_) => HttpResponse::Ok().body("Removed from your list"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /me/list?page=1&limit=20  — any authenticated user
+///
+/// Most recently added first, each entry joined with its movie summary.
+pub async fn get_watchlist(
+    req: HttpRequest,
+    query: web::Query<PageQuery>,
+    watchlist_collection: web::Data<Collection<WatchlistEntry>>,
+    region_resolver: web::Data<RegionResolver>,
+) -> HttpResponse {
+    let viewer = user_viewer(&req, &region_resolver, None);
+    let claims = match require_auth(req.clone()).await {
+        Ok(claims) => claims,
+        Err(res) => return res,
+    };
+    let user = claims_email(&claims);
+    let locales = preferred_locales(&req, &claims, None);
+
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let pipeline = vec![
+        doc! { "$match": { "user": &user } },
+        doc! { "$sort": { "added_at": -1 } },
+        // Joining on the ObjectId form lets the lookup use the movies' `_id` index.
+        doc! {
+            "$addFields": {
+                "movie_oid": {
+                    "$convert": { "input": "$movie_id", "to": "objectId", "onError": null },
+                },
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "movies",
+                "localField": "movie_oid",
+                "foreignField": "_id",
+                "pipeline": [
+                    { "$match": viewer.movie_filter() },
+                    { "$project": MovieSummary::projection() },
+                ],
+                "as": "movie",
+            }
+        },
+        // Entries whose movie is gone or unavailable here are dropped, not
+        // returned half-empty, and do not count towards the total either.
+        doc! { "$unwind": "$movie" },
+        doc! {
+            "$facet": {
+                "items": [
+                    { "$skip": ((page - 1) * limit) as i64 },
+                    { "$limit": limit as i64 },
+                ],
+                "total": [{ "$count": "count" }],
+            }
+        },
+    ];
+
+    let mut result = match watchlist_collection.aggregate(pipeline).await {
+        Ok(mut cursor) => match cursor.try_next().await {
+            Ok(result) => result.unwrap_or_default(),
+            Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+    let total = result
+        .get_array("total")
+        .ok()
+        .and_then(|total| total.first())
+        .and_then(|count| count.as_document())
+        .and_then(|count| {
+            let count = count.get("count")?;
+            count.as_i32().map(i64::from).or_else(|| count.as_i64())
+        })
+        .unwrap_or(0);
+    let rows: Vec<Document> = match result.remove("items") {
+        Some(Bson::Array(items)) => items
+            .into_iter()
+            .filter_map(|item| item.as_document().cloned())
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    match rows
+        .into_iter()
+        .map(bson::from_document::<WatchlistItem>)
+        .collect::<Result<Vec<WatchlistItem>, _>>()
+    {
+        Ok(mut items) => {
+            items
+                .iter_mut()
+                .for_each(|item| item.movie.localize(&locales));
+            HttpResponse::Ok().json(json!({
+                "page": page,
+                "limit": limit,
+                "total": total,
+                "items": items,
+            }))
+        }
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
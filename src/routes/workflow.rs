@@ -0,0 +1,251 @@
+use crate::events::{CatalogEvent, CatalogEvents};
+use crate::models::list::List;
+use crate::models::movie::Movie;
+use crate::models::revision::MovieRevision;
+use crate::models::workflow::{can_transition, ContentStatus, EditorialRole, StatusChange};
+use crate::revisions::{write_movie, RevisionError};
+use crate::routes::auth::{claims_email, claims_role, require_admin};
+use actix_web::{web, HttpRequest, HttpResponse};
+use bson::{doc, oid::ObjectId, Bson, DateTime, Document};
+use futures_util::TryStreamExt;
+use mongodb::Collection;
+use serde::Deserialize;
+
+// ── Input types ───────────────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct StatusInput {
+    pub status: ContentStatus,
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Reads a document's editorial status; a missing status means published.
+fn current_status(document: &Document) -> Result<ContentStatus, HttpResponse> {
+    match document.get("status") {
+        None | Some(Bson::Null) => Ok(ContentStatus::Published),
+        Some(status) => bson::from_bson(status.clone())
+            .map_err(|e| HttpResponse::InternalServerError().body(e.to_string())),
+    }
+}
+
+/// Moves one movie or list to `to`, recording who did it.
+///
+/// The update only applies if the status is still the one that was checked,
+/// so two admins racing on the same entry cannot both succeed. Movie writes
+/// pass `revisions` so the change also lands in the movie's revision history.
+async fn transition(
+    req: HttpRequest,
+    entity: &str,
+    id: String,
+    to: ContentStatus,
+    collection: Collection<Document>,
+    history: &Collection<StatusChange>,
+    revisions: Option<&Collection<MovieRevision>>,
+) -> Result<StatusChange, HttpResponse> {
+    let claims = require_admin(req).await?;
+    let actor = claims_email(&claims);
+    let role = claims_role(&claims);
+
+    let oid = ObjectId::parse_str(&id)
+        .map_err(|_| HttpResponse::BadRequest().body(format!("Invalid {} ID format.", entity)))?;
+
+    let document = match collection
+        .find_one(doc! { "_id": oid, "deleted_at": null })
+        .projection(doc! { "status": 1 })
+        .await
+    {
+        Ok(Some(document)) => document,
+        Ok(None) => {
+            return Err(HttpResponse::NotFound().body(format!("The {} does not exist", entity)))
+        }
+        Err(e) => return Err(HttpResponse::InternalServerError().body(e.to_string())),
+    };
+    let from = current_status(&document)?;
+
+    if !can_transition(from, to, EditorialRole::Publisher) {
+        return Err(HttpResponse::Conflict()
+            .body(format!("Cannot move a {} from {} to {}", entity, from, to)));
+    }
+    if !can_transition(from, to, role) {
+        return Err(HttpResponse::Forbidden().body(format!(
+            "Editors cannot move a {} from {} to {}",
+            entity, from, to
+        )));
+    }
+
+    let status_filter = match document.get("status") {
+        None | Some(Bson::Null) => Bson::Null,
+        Some(status) => status.clone(),
+    };
+    let mut update = doc! {
+        "$set": { "status": to.to_string(), "updated_at": DateTime::now() },
+    };
+    // Lists are edited under optimistic concurrency; a status change is an edit too.
+    if entity == "list" {
+        update.insert("$inc", doc! { "version": 1_i64 });
+    }
+
+    // The audit entry goes in first and is taken back if the change does not
+    // apply, so no status moves without a record of who moved it.
+    let change = StatusChange {
+        id: Some(ObjectId::new()),
+        entity: entity.to_string(),
+        entity_id: id,
+        from,
+        to,
+        actor: actor.clone(),
+        at: DateTime::now(),
+    };
+    if let Err(e) = history.insert_one(&change).await {
+        return Err(HttpResponse::InternalServerError()
+            .body(format!("Failed to record status change: {}", e)));
+    }
+
+    let filter = doc! { "_id": oid, "status": status_filter, "deleted_at": null };
+    let applied = match revisions {
+        Some(revisions) => {
+            match write_movie(&collection, revisions, filter, update, &actor).await {
+                Ok(updated) => Ok(updated.is_some()),
+                Err(RevisionError::Conflict) => Ok(false),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        None => collection
+            .update_one(filter, update)
+            .await
+            .map(|result| result.matched_count > 0)
+            .map_err(|e| e.to_string()),
+    };
+    if applied != Ok(true) {
+        if let Err(e) = history.delete_one(doc! { "_id": change.id }).await {
+            log::warn!("Failed to withdraw status change {:?}: {}", change.id, e);
+        }
+    }
+    match applied {
+        Ok(false) => {
+            return Err(HttpResponse::Conflict()
+                .body(format!("The {} status was changed by someone else", entity)))
+        }
+        Ok(true) => {}
+        Err(e) => return Err(HttpResponse::InternalServerError().body(e)),
+    }
+
+    Ok(change)
+}
+
+async fn status_history(
+    req: HttpRequest,
+    entity: &str,
+    id: String,
+    history: &Collection<StatusChange>,
+) -> HttpResponse {
+    if let Err(res) = require_admin(req).await {
+        return res;
+    }
+
+    let changes = history
+        .find(doc! { "entity": entity, "entity_id": id })
+        .sort(doc! { "at": -1 })
+        .await;
+
+    match changes {
+        Ok(cursor) => match cursor.try_collect::<Vec<StatusChange>>().await {
+            Ok(changes) => HttpResponse::Ok().json(changes),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        },
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+// ── Handlers ──────────────────────────────────────────────────────────────────
+
+/// POST /movies/{id}/status  — admin only
+///
+/// Editors may submit drafts for review or pull them back; publishing,
+/// unpublishing and archiving need a publisher.
+pub async fn set_movie_status(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    input: web::Json<StatusInput>,
+    movie_collection: web::Data<Collection<Movie>>,
+    status_collection: web::Data<Collection<StatusChange>>,
+    revision_collection: web::Data<Collection<MovieRevision>>,
+    events: web::Data<CatalogEvents>,
+) -> HttpResponse {
+    let movies = movie_collection.clone_with_type::<Document>();
+    let change = match transition(
+        req,
+        "movie",
+        movie_id.into_inner(),
+        input.status,
+        movies,
+        &status_collection,
+        Some(&revision_collection),
+    )
+    .await
+    {
+        Ok(change) => change,
+        Err(res) => return res,
+    };
+
+    if change.to == ContentStatus::Published {
+        events.emit(CatalogEvent::MovieLive(change.entity_id.clone()));
+    } else if change.from == ContentStatus::Published {
+        events.emit(CatalogEvent::MovieExpired(change.entity_id.clone()));
+    }
+
+    HttpResponse::Ok().json(change)
+}
+
+/// POST /lists/{id}/status  — admin only
+pub async fn set_list_status(
+    req: HttpRequest,
+    list_id: web::Path<String>,
+    input: web::Json<StatusInput>,
+    list_collection: web::Data<Collection<List>>,
+    status_collection: web::Data<Collection<StatusChange>>,
+    events: web::Data<CatalogEvents>,
+) -> HttpResponse {
+    let lists = list_collection.clone_with_type::<Document>();
+    let change = match transition(
+        req,
+        "list",
+        list_id.into_inner(),
+        input.status,
+        lists,
+        &status_collection,
+        None,
+    )
+    .await
+    {
+        Ok(change) => change,
+        Err(res) => return res,
+    };
+
+    if change.to == ContentStatus::Published {
+        events.emit(CatalogEvent::ListVisible(change.entity_id.clone()));
+    } else if change.from == ContentStatus::Published {
+        events.emit(CatalogEvent::ListHidden(change.entity_id.clone()));
+    }
+
+    HttpResponse::Ok().json(change)
+}
+
+/// GET /movies/{id}/status-history  — admin only
+pub async fn get_movie_status_history(
+    req: HttpRequest,
+    movie_id: web::Path<String>,
+    status_collection: web::Data<Collection<StatusChange>>,
+) -> HttpResponse {
+    status_history(req, "movie", movie_id.into_inner(), &status_collection).await
+}
+
+/// GET /lists/{id}/status-history  — admin only
+pub async fn get_list_status_history(
+    req: HttpRequest,
+    list_id: web::Path<String>,
+    status_collection: web::Data<Collection<StatusChange>>,
+) -> HttpResponse {
+    status_history(req, "list", list_id.into_inner(), &status_collection).await
+}
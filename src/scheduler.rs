@@ -0,0 +1,359 @@
+use crate::catalog::{and, published};
+use crate::events::{CatalogEvent, CatalogEvents};
+use crate::models::list::List;
+use crate::models::movie::Movie;
+use crate::models::rating::Thumb;
+use crate::ratings::{apply_rating_delta, RatingDelta};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId, DateTime, Document};
+use mongodb::Collection;
+use std::env;
+use std::time::Duration;
+
+// ── Publishing windows ────────────────────────────────────────────────────────
+
+/// How often publishing windows are checked (`AVAILABILITY_POLL_SECS`, default 60).
+fn availability_poll_secs() -> u64 {
+    env::var("AVAILABILITY_POLL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60)
+}
+
+/// Ids of published documents whose `field` fell inside `(since, now]`.
+/// Unpublished ones go live through the editorial workflow instead.
+async fn crossed(
+    collection: &Collection<Document>,
+    field: &str,
+    since: DateTime,
+    now: DateTime,
+) -> mongodb::error::Result<Vec<String>> {
+    let docs: Vec<Document> = collection
+        .find(and(
+            doc! { field: { "$gt": since, "$lte": now } },
+            published(),
+        ))
+        .projection(doc! { "_id": 1 })
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(docs
+        .iter()
+        .filter_map(|d| d.get_object_id("_id").ok())
+        .map(|oid| oid.to_hex())
+        .collect())
+}
+
+/// Emits an event for every movie or list whose window opened or closed in `(since, now]`.
+async fn emit_crossings(
+    movies: &Collection<Document>,
+    lists: &Collection<Document>,
+    events: &CatalogEvents,
+    since: DateTime,
+    now: DateTime,
+) -> mongodb::error::Result<()> {
+    for id in crossed(movies, "available_from", since, now).await? {
+        events.emit(CatalogEvent::MovieLive(id));
+    }
+    for id in crossed(movies, "available_until", since, now).await? {
+        events.emit(CatalogEvent::MovieExpired(id));
+    }
+    for id in crossed(lists, "visible_from", since, now).await? {
+        events.emit(CatalogEvent::ListVisible(id));
+    }
+    for id in crossed(lists, "visible_until", since, now).await? {
+        events.emit(CatalogEvent::ListHidden(id));
+    }
+    Ok(())
+}
+
+/// Spawns a background task that watches `available_*` / `visible_*`
+/// windows and emits a `CatalogEvent` whenever a title goes live or expires.
+pub fn spawn_availability_watcher(
+    movie_collection: Collection<Movie>,
+    list_collection: Collection<List>,
+    events: CatalogEvents,
+) {
+    let movies = movie_collection.clone_with_type::<Document>();
+    let lists = list_collection.clone_with_type::<Document>();
+
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(availability_poll_secs()));
+        let mut since = DateTime::now();
+
+        loop {
+            interval.tick().await;
+            let now = DateTime::now();
+            match emit_crossings(&movies, &lists, &events, since, now).await {
+                // Only advance on success so a failed poll is retried next tick.
+                Ok(()) => since = now,
+                Err(e) => log::warn!("Availability check failed: {}", e),
+            }
+        }
+    });
+}
+
+// ── Trash purge ───────────────────────────────────────────────────────────────
+
+/// How often the trash is checked for expired entries.
+const PURGE_INTERVAL_SECS: u64 = 3600;
+
+/// Days a trashed movie, list or user is kept before being deleted for good
+/// (`TRASH_RETENTION_DAYS`, default 30).
+fn trash_retention_days() -> i64 {
+    env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30)
+}
+
+/// `_id` and `field` of every document trashed before `cutoff`.
+async fn expired(
+    collection: &Collection<Document>,
+    field: &str,
+    cutoff: DateTime,
+) -> mongodb::error::Result<Vec<Document>> {
+    collection
+        .find(doc! { "deleted_at": { "$lt": cutoff } })
+        .projection(doc! { "_id": 1, field: 1 })
+        .await?
+        .try_collect()
+        .await
+}
+
+/// Everything the trash purge deletes from, untyped since it only filters and
+/// deletes. Built once at startup and moved into the purge task.
+pub struct PurgeCollections {
+    pub movies: Collection<Document>,
+    pub lists: Collection<Document>,
+    pub users: Collection<Document>,
+    pub watchlist: Collection<Document>,
+    pub revisions: Collection<Document>,
+    pub ratings: Collection<Document>,
+    pub reviews: Collection<Document>,
+    pub review_votes: Collection<Document>,
+    pub views: Collection<Document>,
+    pub view_events: Collection<Document>,
+    pub chart_overrides: Collection<Document>,
+    pub credits: Collection<Document>,
+    pub streams: Collection<Document>,
+    pub subtitles: Collection<Document>,
+}
+
+/// Deletes everything trashed before `cutoff`, along with what still points at it.
+/// References are removed before the documents so a failed run is simply retried.
+async fn purge_trash(c: &PurgeCollections, cutoff: DateTime) -> mongodb::error::Result<()> {
+    let movie_oids: Vec<ObjectId> = expired(&c.movies, "_id", cutoff)
+        .await?
+        .iter()
+        .filter_map(|d| d.get_object_id("_id").ok())
+        .collect();
+    if !movie_oids.is_empty() {
+        let ids: Vec<String> = movie_oids.iter().map(|oid| oid.to_hex()).collect();
+        c.watchlist
+            .delete_many(doc! { "movie_id": { "$in": &ids } })
+            .await?;
+        c.lists
+            .update_many(
+                doc! { "content": { "$in": &ids } },
+                doc! {
+                    "$pull": { "content": { "$in": &ids } },
+                    "$set": { "updated_at": DateTime::now() },
+                    "$inc": { "version": 1_i64 },
+                },
+            )
+            .await?;
+        c.revisions
+            .delete_many(doc! { "movie_id": { "$in": &ids } })
+            .await?;
+        c.ratings
+            .delete_many(doc! { "movie_id": { "$in": &ids } })
+            .await?;
+        c.views
+            .delete_many(doc! { "movie_id": { "$in": &ids } })
+            .await?;
+        c.view_events
+            .delete_many(doc! { "movie_id": { "$in": &ids } })
+            .await?;
+        c.chart_overrides
+            .delete_many(doc! { "movie_id": { "$in": &ids } })
+            .await?;
+        c.credits
+            .delete_many(doc! { "movie_id": { "$in": &ids } })
+            .await?;
+        c.subtitles
+            .delete_many(doc! { "movie_id": { "$in": &ids } })
+            .await?;
+        delete_reviews(c, doc! { "movie_id": { "$in": &ids } }).await?;
+        c.movies
+            .delete_many(doc! { "_id": { "$in": &movie_oids } })
+            .await?;
+        log::info!("Purged {} movies from the trash", movie_oids.len());
+    }
+
+    let purged_lists = c
+        .lists
+        .delete_many(doc! { "deleted_at": { "$lt": cutoff } })
+        .await?;
+    if purged_lists.deleted_count > 0 {
+        log::info!("Purged {} lists from the trash", purged_lists.deleted_count);
+    }
+
+    let expired_users = expired(&c.users, "email", cutoff).await?;
+    if !expired_users.is_empty() {
+        let emails: Vec<&str> = expired_users
+            .iter()
+            .filter_map(|d| d.get_str("email").ok())
+            .collect();
+        let user_oids: Vec<ObjectId> = expired_users
+            .iter()
+            .filter_map(|d| d.get_object_id("_id").ok())
+            .collect();
+        c.watchlist
+            .delete_many(doc! { "user": { "$in": &emails } })
+            .await?;
+        c.views
+            .delete_many(doc! { "user": { "$in": &emails } })
+            .await?;
+        c.streams
+            .delete_many(doc! { "user": { "$in": &emails } })
+            .await?;
+        // Subtitles are catalog assets; they stay, only the uploader goes.
+        c.subtitles
+            .update_many(
+                doc! { "uploaded_by": { "$in": &emails } },
+                doc! { "$unset": { "uploaded_by": "" } },
+            )
+            .await?;
+        retract_ratings(&c.movies, &c.ratings, &emails).await?;
+        retract_helpful_votes(&c.reviews, &c.review_votes, &emails).await?;
+        delete_reviews(c, doc! { "user": { "$in": &emails } }).await?;
+        c.users
+            .delete_many(doc! { "_id": { "$in": &user_oids } })
+            .await?;
+        log::info!("Purged {} users from the trash", user_oids.len());
+    }
+
+    Ok(())
+}
+
+/// Deletes the users' ratings and takes them out of the movies' counts.
+async fn retract_ratings(
+    movies: &Collection<Document>,
+    ratings: &Collection<Document>,
+    emails: &[&str],
+) -> mongodb::error::Result<()> {
+    let tallies: Vec<Document> = ratings
+        .aggregate(vec![
+            doc! { "$match": { "user": { "$in": emails } } },
+            doc! { "$group": {
+                "_id": { "movie_id": "$movie_id", "thumb": "$thumb" },
+                "count": { "$sum": 1_i64 },
+            } },
+        ])
+        .await?
+        .try_collect()
+        .await?;
+
+    for tally in tallies {
+        let Ok(key) = tally.get_document("_id") else {
+            continue;
+        };
+        let movie_id = key
+            .get_str("movie_id")
+            .ok()
+            .and_then(|id| ObjectId::parse_str(id).ok());
+        let thumb = key
+            .get("thumb")
+            .and_then(|thumb| mongodb::bson::from_bson::<Thumb>(thumb.clone()).ok());
+        let (Some(movie_id), Some(thumb)) = (movie_id, thumb) else {
+            continue;
+        };
+        let mut delta = RatingDelta::default();
+        *delta.counter(thumb) = -tally.get_i64("count").unwrap_or(0);
+        apply_rating_delta(movies, movie_id, delta).await?;
+    }
+
+    ratings
+        .delete_many(doc! { "user": { "$in": emails } })
+        .await?;
+    Ok(())
+}
+
+/// Deletes the reviews matching `filter` and the helpful votes cast on them.
+async fn delete_reviews(c: &PurgeCollections, filter: Document) -> mongodb::error::Result<()> {
+    let review_ids: Vec<ObjectId> = c
+        .reviews
+        .find(filter.clone())
+        .projection(doc! { "_id": 1 })
+        .await?
+        .try_collect::<Vec<Document>>()
+        .await?
+        .iter()
+        .filter_map(|d| d.get_object_id("_id").ok())
+        .collect();
+    if review_ids.is_empty() {
+        return Ok(());
+    }
+    c.review_votes
+        .delete_many(doc! { "review_id": { "$in": &review_ids } })
+        .await?;
+    c.reviews
+        .delete_many(doc! { "_id": { "$in": &review_ids } })
+        .await?;
+    Ok(())
+}
+
+/// Deletes the users' helpful votes and takes them out of the reviews' counts.
+async fn retract_helpful_votes(
+    reviews: &Collection<Document>,
+    votes: &Collection<Document>,
+    emails: &[&str],
+) -> mongodb::error::Result<()> {
+    let tallies: Vec<Document> = votes
+        .aggregate(vec![
+            doc! { "$match": { "user": { "$in": emails } } },
+            doc! { "$group": { "_id": "$review_id", "count": { "$sum": 1_i64 } } },
+        ])
+        .await?
+        .try_collect()
+        .await?;
+
+    for tally in tallies {
+        let (Ok(review_id), Ok(count)) = (tally.get_object_id("_id"), tally.get_i64("count"))
+        else {
+            continue;
+        };
+        reviews
+            .update_one(
+                doc! { "_id": review_id },
+                doc! { "$inc": { "helpful_count": -count } },
+            )
+            .await?;
+    }
+
+    votes
+        .delete_many(doc! { "user": { "$in": emails } })
+        .await?;
+    Ok(())
+}
+
+/// Spawns a background task that permanently deletes trashed movies, lists
+/// and users once they are older than the retention period.
+pub fn spawn_trash_purger(collections: PurgeCollections) {
+    let retention_millis = trash_retention_days() * 24 * 60 * 60 * 1000;
+
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(PURGE_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+            let cutoff =
+                DateTime::from_millis(DateTime::now().timestamp_millis() - retention_millis);
+            if let Err(e) = purge_trash(&collections, cutoff).await {
+                log::warn!("Trash purge failed: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,224 @@
+use crate::locale::DEFAULT_LOCALE;
+use crate::models::movie::Movie;
+use crate::models::person::Person;
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, Bson, Document, Regex};
+use mongodb::Collection;
+
+// ── Search keys ───────────────────────────────────────────────────────────────
+
+/// Lower-case words of `text`, split on anything that is not a letter or digit.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Every tail of each name starting at a word, lower-cased: "The Matrix"
+/// gives `the matrix` and `matrix`. Stored as `search_keys` with an index,
+/// so an anchored prefix regex finds names by the start of any word.
+pub fn search_keys<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for name in names {
+        let words = words(name);
+        for start in 0..words.len() {
+            let key = words[start..].join(" ");
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    keys
+}
+
+/// The keys of a movie document: its title and every translated title.
+pub fn movie_search_keys(movie: &Document) -> Vec<String> {
+    let mut names: Vec<&str> = movie.get_str("title").into_iter().collect();
+    if let Ok(translations) = movie.get_document("translations") {
+        names.extend(
+            translations
+                .values()
+                .filter_map(|translation| translation.as_document()?.get_str("title").ok()),
+        );
+    }
+    search_keys(names)
+}
+
+/// Matches documents with a name starting with the words of `q` at a word
+/// boundary; `None` if `q` has no letters or digits. The words hold no regex
+/// metacharacters, and the pattern is case-sensitive so the index bounds it.
+pub fn prefix_filter(q: &str) -> Option<Document> {
+    let prefix = words(q).join(" ");
+    if prefix.is_empty() {
+        return None;
+    }
+    Some(doc! {
+        "search_keys": Regex {
+            pattern: format!("^{prefix}"),
+            options: String::new(),
+        }
+    })
+}
+
+/// Aggregation expression for the title `localize` shows for `locales`,
+/// lower-cased, to sort search results the way the caller reads them.
+pub fn display_title_key(locales: &[String]) -> Bson {
+    let mut titles: Vec<Bson> = locales
+        .iter()
+        .take_while(|tag| tag.as_str() != DEFAULT_LOCALE)
+        .map(|tag| Bson::String(format!("$translations.{}.title", tag)))
+        .collect();
+    let title = if titles.is_empty() {
+        Bson::String("$title".to_string())
+    } else {
+        titles.push(Bson::String("$title".to_string()));
+        Bson::Document(doc! { "$ifNull": titles })
+    };
+    Bson::Document(doc! { "$toLower": title })
+}
+
+// ── Upkeep ────────────────────────────────────────────────────────────────────
+
+fn stored_keys(document: &Document) -> Option<Vec<&str>> {
+    document
+        .get_array("search_keys")
+        .ok()?
+        .iter()
+        .map(Bson::as_str)
+        .collect()
+}
+
+/// Brings the movie's `search_keys` in line with its titles. Called after
+/// every write; does nothing if they already match.
+pub async fn index_movie(
+    movies: &Collection<Document>,
+    movie: &Document,
+) -> mongodb::error::Result<()> {
+    let Ok(oid) = movie.get_object_id("_id") else {
+        return Ok(());
+    };
+    let keys = movie_search_keys(movie);
+    if stored_keys(movie).is_some_and(|stored| stored == keys) {
+        return Ok(());
+    }
+    movies
+        .update_one(
+            doc! { "_id": oid },
+            doc! { "$set": { "search_keys": keys } },
+        )
+        .await?;
+    Ok(())
+}
+
+/// Fills in `search_keys` on movies and people stored before they existed.
+async fn backfill_search_keys(
+    movie_collection: &Collection<Movie>,
+    person_collection: &Collection<Person>,
+) -> mongodb::error::Result<()> {
+    let movies = movie_collection.clone_with_type::<Document>();
+    let mut cursor = movies
+        .find(doc! { "search_keys": { "$exists": false } })
+        .projection(doc! { "title": 1, "translations": 1 })
+        .await?;
+    while let Some(movie) = cursor.try_next().await? {
+        index_movie(&movies, &movie).await?;
+    }
+
+    let people = person_collection.clone_with_type::<Document>();
+    let mut cursor = people
+        .find(doc! { "search_keys": { "$exists": false } })
+        .projection(doc! { "name": 1 })
+        .await?;
+    while let Some(person) = cursor.try_next().await? {
+        let (Ok(oid), Ok(name)) = (person.get_object_id("_id"), person.get_str("name")) else {
+            continue;
+        };
+        people
+            .update_one(
+                doc! { "_id": oid },
+                doc! { "$set": { "search_keys": search_keys([name]) } },
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Runs the backfill once in the background, so startup does not wait on it.
+pub fn spawn_search_backfill(
+    movie_collection: Collection<Movie>,
+    person_collection: Collection<Person>,
+) {
+    actix_rt::spawn(async move {
+        if let Err(e) = backfill_search_keys(&movie_collection, &person_collection).await {
+            log::warn!("Failed to backfill search keys: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_lower_case_tails_from_each_word() {
+        assert_eq!(
+            search_keys(["The Matrix: Reloaded", "Matrix"]),
+            vec![
+                "the matrix reloaded",
+                "matrix reloaded",
+                "reloaded",
+                "matrix"
+            ]
+        );
+        assert!(search_keys([" - "]).is_empty());
+    }
+
+    #[test]
+    fn movie_keys_cover_translated_titles() {
+        let movie = doc! {
+            "title": "City of God",
+            "translations": {
+                "pt-BR": { "title": "Cidade de Deus" },
+                "de": { "desc": "no title" },
+            },
+        };
+        let keys = movie_search_keys(&movie);
+        assert!(keys.contains(&"city of god".to_string()));
+        assert!(keys.contains(&"deus".to_string()));
+        assert_eq!(keys.len(), 6);
+    }
+
+    #[test]
+    fn prefix_filter_anchors_the_normalized_query() {
+        let filter = prefix_filter("  Matrix: re").unwrap();
+        let regex = filter.get("search_keys").unwrap();
+        assert_eq!(
+            regex,
+            &Bson::RegularExpression(Regex {
+                pattern: "^matrix re".to_string(),
+                options: String::new(),
+            })
+        );
+        assert!(prefix_filter("?!").is_none());
+    }
+
+    #[test]
+    fn display_title_key_follows_locales_up_to_the_default() {
+        let locales = ["pt-BR", "pt", "en", "de"].map(String::from);
+        assert_eq!(
+            display_title_key(&locales),
+            Bson::Document(doc! {
+                "$toLower": { "$ifNull": [
+                    "$translations.pt-BR.title",
+                    "$translations.pt.title",
+                    "$title",
+                ] }
+            })
+        );
+        assert_eq!(
+            display_title_key(&["en".to_string()]),
+            Bson::Document(doc! { "$toLower": "$title" })
+        );
+    }
+}
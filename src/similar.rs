@@ -0,0 +1,419 @@
+use crate::catalog::not_deleted;
+use crate::credits::people_by_title;
+use crate::genres::{slugify, split_genres};
+use crate::models::movie::Movie;
+use crate::models::person::Credit;
+use actix_web::web;
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, Bson, DateTime, Document};
+use mongodb::Collection;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+// ── Features ──────────────────────────────────────────────────────────────────
+
+/// Weights of each signal in the similarity score; they sum to 1.
+const DESC_WEIGHT: f64 = 0.40;
+const GENRE_WEIGHT: f64 = 0.25;
+const PEOPLE_WEIGHT: f64 = 0.15;
+const YEAR_WEIGHT: f64 = 0.12;
+const TYPE_WEIGHT: f64 = 0.08;
+
+/// Years apart at which the year signal reaches zero.
+const YEAR_SPAN: f64 = 10.0;
+
+/// Common words that carry no meaning for similarity.
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "against", "all", "also", "and", "are", "as", "but", "by", "can",
+    "for", "from", "has", "have", "her", "his", "into", "its", "not", "one", "only", "out", "she",
+    "that", "the", "their", "them", "then", "they", "this", "through", "when", "where", "which",
+    "while", "who", "will", "with", "your",
+];
+
+/// What a title is compared on.
+struct TitleFeatures {
+    genres: HashSet<String>,
+    /// Ids of everyone credited, cast and crew.
+    people: HashSet<String>,
+    year: Option<i32>,
+    is_series: bool,
+    /// L2-normalised TF-IDF weights of the description terms.
+    terms: HashMap<String, f64>,
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() >= 3 && !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// The title's genre slugs; titles not migrated to the taxonomy yet use the
+/// slugs of their legacy text.
+fn genres(movie: &Document) -> HashSet<String> {
+    match movie.get_array("genres") {
+        Ok(slugs) if !slugs.is_empty() => slugs
+            .iter()
+            .filter_map(|slug| slug.as_str().map(str::to_string))
+            .collect(),
+        _ => split_genres(movie.get_str("genre").unwrap_or_default())
+            .into_iter()
+            .map(slugify)
+            .collect(),
+    }
+}
+
+/// `Movie.year` is free text; the first four digits are taken as the year.
+fn year(year: Option<&str>) -> Option<i32> {
+    let digits: String = year?.chars().filter(char::is_ascii_digit).take(4).collect();
+    (digits.len() == 4).then(|| digits.parse().ok()).flatten()
+}
+
+fn cosine(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    small
+        .iter()
+        .filter_map(|(term, weight)| large.get(term).map(|other| weight * other))
+        .sum()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+// ── Corpus ────────────────────────────────────────────────────────────────────
+
+/// Features of every title not in the trash, keyed by hex id. Visibility is
+/// applied per request, so one corpus serves every viewer.
+#[derive(Default)]
+pub struct Corpus {
+    titles: HashMap<String, TitleFeatures>,
+}
+
+impl Corpus {
+    /// Builds the corpus from movie documents and their credited people,
+    /// computing IDF over all descriptions.
+    fn build(movies: Vec<Document>, mut people: HashMap<String, HashSet<String>>) -> Self {
+        let tokenized: Vec<(String, &Document, Vec<String>)> = movies
+            .iter()
+            .filter_map(|movie| {
+                let id = movie.get_object_id("_id").ok()?.to_hex();
+                let desc = movie.get_str("desc").unwrap_or_default();
+                Some((id, movie, tokenize(desc)))
+            })
+            .collect();
+
+        let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+        for (_, _, tokens) in &tokenized {
+            let unique: HashSet<&str> = tokens.iter().map(String::as_str).collect();
+            for term in unique {
+                *document_frequency.entry(term).or_default() += 1;
+            }
+        }
+        let total = tokenized.len() as f64;
+        let idf = |term: &str| {
+            let df = document_frequency.get(term).copied().unwrap_or(0) as f64;
+            ((1.0 + total) / (1.0 + df)).ln() + 1.0
+        };
+
+        let titles = tokenized
+            .iter()
+            .map(|(id, movie, tokens)| {
+                let mut counts: HashMap<String, f64> = HashMap::new();
+                for token in tokens {
+                    *counts.entry(token.clone()).or_default() += 1.0;
+                }
+                let length = tokens.len().max(1) as f64;
+                let mut terms: HashMap<String, f64> = counts
+                    .into_iter()
+                    .map(|(term, count)| {
+                        let weight = count / length * idf(&term);
+                        (term, weight)
+                    })
+                    .collect();
+                let norm = terms.values().map(|w| w * w).sum::<f64>().sqrt();
+                if norm > 0.0 {
+                    terms.values_mut().for_each(|w| *w /= norm);
+                }
+
+                let features = TitleFeatures {
+                    genres: genres(movie),
+                    people: people.remove(id).unwrap_or_default(),
+                    year: year(movie.get_str("year").ok()),
+                    is_series: movie.get_bool("is_series").unwrap_or(false),
+                    terms,
+                };
+                (id.clone(), features)
+            })
+            .collect();
+
+        Corpus { titles }
+    }
+
+    fn score(a: &TitleFeatures, b: &TitleFeatures) -> f64 {
+        let year = match (a.year, b.year) {
+            (Some(x), Some(y)) => (1.0 - f64::from((x - y).abs()) / YEAR_SPAN).max(0.0),
+            _ => 0.0,
+        };
+        let same_type = if a.is_series == b.is_series { 1.0 } else { 0.0 };
+
+        DESC_WEIGHT * cosine(&a.terms, &b.terms)
+            + GENRE_WEIGHT * jaccard(&a.genres, &b.genres)
+            + PEOPLE_WEIGHT * jaccard(&a.people, &b.people)
+            + YEAR_WEIGHT * year
+            + TYPE_WEIGHT * same_type
+    }
+
+    /// The `k` titles most similar to `source`, best first, excluding the
+    /// source itself. `None` if the source is not in the corpus yet.
+    pub fn rank(&self, source: &str, k: usize) -> Option<Vec<(String, f64)>> {
+        let source_features = self.titles.get(source)?;
+        let mut ranked: Vec<(String, f64)> = self
+            .titles
+            .iter()
+            .filter(|(id, _)| id.as_str() != source)
+            .map(|(id, features)| (id.clone(), Corpus::score(source_features, features)))
+            .collect();
+        // Ties go to the smaller id so the order is stable across calls.
+        let best_first =
+            |a: &(String, f64), b: &(String, f64)| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0));
+        if k < ranked.len() {
+            ranked.select_nth_unstable_by(k, best_first);
+            ranked.truncate(k);
+        }
+        ranked.sort_by(best_first);
+        Some(ranked)
+    }
+}
+
+/// The current corpus, swapped whole when rebuilt so readers never block on a refresh.
+#[derive(Default)]
+pub struct SimilarityIndex {
+    corpus: RwLock<Arc<Corpus>>,
+}
+
+impl SimilarityIndex {
+    pub fn snapshot(&self) -> Arc<Corpus> {
+        self.corpus
+            .read()
+            .map(|corpus| Arc::clone(&corpus))
+            .unwrap_or_default()
+    }
+
+    fn replace(&self, corpus: Corpus) {
+        if let Ok(mut current) = self.corpus.write() {
+            *current = Arc::new(corpus);
+        }
+    }
+}
+
+// ── Refresh ───────────────────────────────────────────────────────────────────
+
+/// How often the catalog is checked for changes (`SIMILAR_REFRESH_SECS`, default 60).
+fn similar_refresh_secs() -> u64 {
+    env::var("SIMILAR_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60)
+}
+
+/// Count and newest `field` of a collection.
+async fn collection_fingerprint(
+    collection: &Collection<Document>,
+    field: &str,
+) -> mongodb::error::Result<(u64, Option<DateTime>)> {
+    let count = collection.estimated_document_count().await?;
+    let latest = collection
+        .find_one(doc! {})
+        .sort(doc! { field: -1 })
+        .projection(doc! { field: 1 })
+        .await?
+        .and_then(|document| match document.get(field) {
+            Some(Bson::DateTime(at)) => Some(*at),
+            _ => None,
+        });
+    Ok((count, latest))
+}
+
+/// Changes whenever a movie is added, edited, trashed or purged, or its
+/// credits are replaced: every edit bumps `updated_at`, replaced credits are
+/// newly created, and additions or purges change the counts.
+async fn catalog_fingerprint(
+    movies: &Collection<Document>,
+    credits: &Collection<Document>,
+) -> mongodb::error::Result<[(u64, Option<DateTime>); 2]> {
+    Ok([
+        collection_fingerprint(movies, "updated_at").await?,
+        collection_fingerprint(credits, "created_at").await?,
+    ])
+}
+
+async fn load_titles(movies: &Collection<Document>) -> mongodb::error::Result<Vec<Document>> {
+    movies
+        .find(not_deleted())
+        .projection(doc! { "desc": 1, "genre": 1, "genres": 1, "year": 1, "is_series": 1 })
+        .await?
+        .try_collect()
+        .await
+}
+
+/// Spawns a background task that builds the corpus at startup and rebuilds it
+/// whenever the catalog fingerprint changes.
+pub fn spawn_similarity_refresher(
+    movie_collection: Collection<Movie>,
+    credit_collection: Collection<Credit>,
+    index: Arc<SimilarityIndex>,
+) {
+    let movies = movie_collection.clone_with_type::<Document>();
+    let credits = credit_collection.clone_with_type::<Document>();
+
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(similar_refresh_secs()));
+        let mut built_for = None;
+
+        loop {
+            interval.tick().await;
+            let fingerprint = match catalog_fingerprint(&movies, &credits).await {
+                Ok(fingerprint) => fingerprint,
+                Err(e) => {
+                    log::warn!("Similarity index check failed: {}", e);
+                    continue;
+                }
+            };
+            if built_for == Some(fingerprint) {
+                continue;
+            }
+
+            let (titles, people) =
+                match (load_titles(&movies).await, people_by_title(&credits).await) {
+                    (Ok(titles), Ok(people)) => (titles, people),
+                    (Err(e), _) | (_, Err(e)) => {
+                        log::warn!("Similarity index rebuild failed: {}", e);
+                        continue;
+                    }
+                };
+            // TF-IDF over the whole catalog is CPU-bound; keep it off the event loop.
+            match web::block(move || Corpus::build(titles, people)).await {
+                Ok(corpus) => {
+                    log::info!(
+                        "Similarity index rebuilt over {} titles",
+                        corpus.titles.len()
+                    );
+                    index.replace(corpus);
+                    built_for = Some(fingerprint);
+                }
+                Err(e) => log::warn!("Similarity index rebuild failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::oid::ObjectId;
+
+    fn title(desc: &str, genre: &str, year: &str, is_series: bool) -> Document {
+        doc! {
+            "_id": ObjectId::new(),
+            "desc": desc,
+            "genre": genre,
+            "year": year,
+            "is_series": is_series,
+        }
+    }
+
+    fn id(movie: &Document) -> String {
+        movie.get_object_id("_id").unwrap().to_hex()
+    }
+
+    #[test]
+    fn tokenizes_without_stopwords_or_short_words() {
+        assert_eq!(
+            tokenize("The heist, and an AI on Mars!"),
+            vec!["heist".to_string(), "mars".to_string()]
+        );
+    }
+
+    #[test]
+    fn reads_the_year_from_free_text() {
+        assert_eq!(year(Some("1999")), Some(1999));
+        assert_eq!(year(Some("2019–2022")), Some(2019));
+        assert_eq!(year(Some("n/a")), None);
+        assert_eq!(year(None), None);
+    }
+
+    #[test]
+    fn scores_identical_titles_highest() {
+        let a = title(
+            "A crew plans a daring bank heist",
+            "Crime, Thriller",
+            "2001",
+            false,
+        );
+        let b = a.clone();
+        let mut people = HashMap::new();
+        people.insert(id(&a), HashSet::from(["p1".to_string()]));
+        let corpus = Corpus::build(vec![a.clone()], people);
+        let features = &corpus.titles[&id(&a)];
+        let same = Corpus::score(features, features);
+        assert!((same - 1.0).abs() < 1e-9, "{same}");
+
+        let corpus = Corpus::build(vec![b.clone()], HashMap::new());
+        let without_people = Corpus::score(&corpus.titles[&id(&b)], &corpus.titles[&id(&b)]);
+        assert!((without_people - (1.0 - PEOPLE_WEIGHT)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn year_signal_fades_over_the_span() {
+        let a = title("", "", "2000", false);
+        let b = title("", "", "2005", false);
+        let c = title("", "", "2020", true);
+        let corpus = Corpus::build(vec![a.clone(), b.clone(), c.clone()], HashMap::new());
+        let (fa, fb, fc) = (
+            &corpus.titles[&id(&a)],
+            &corpus.titles[&id(&b)],
+            &corpus.titles[&id(&c)],
+        );
+        assert!((Corpus::score(fa, fb) - (YEAR_WEIGHT * 0.5 + TYPE_WEIGHT)).abs() < 1e-9);
+        assert_eq!(Corpus::score(fa, fc), 0.0);
+    }
+
+    #[test]
+    fn ranks_the_top_k_best_first_without_the_source() {
+        let source = title(
+            "Astronauts stranded on Mars fight to survive",
+            "Sci-Fi",
+            "2015",
+            false,
+        );
+        let close = title(
+            "Astronauts stranded on a moon survive",
+            "Sci-Fi",
+            "2014",
+            false,
+        );
+        let middle = title("A detective hunts a killer", "Sci-Fi", "2015", false);
+        let far = title("Cooking show about pastries", "Reality", "1990", true);
+        let corpus = Corpus::build(
+            vec![source.clone(), close.clone(), middle.clone(), far.clone()],
+            HashMap::new(),
+        );
+
+        let all = corpus.rank(&id(&source), 10).unwrap();
+        let order: Vec<&str> = all.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(order, vec![id(&close), id(&middle), id(&far)]);
+        assert!(all.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        let top = corpus.rank(&id(&source), 2).unwrap();
+        assert_eq!(top, all[..2].to_vec());
+
+        assert!(corpus.rank(&ObjectId::new().to_hex(), 10).is_none());
+    }
+}
@@ -0,0 +1,303 @@
+use crate::catalog::{and, movie_visible};
+use crate::events::{CatalogEvent, CatalogEvents};
+use crate::genres::movie_genre_filter;
+use crate::models::list::{List, ListRule, RuleSort};
+use crate::models::movie::{Movie, MovieSummary};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::Collection;
+use std::env;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+// ── Rule → query ──────────────────────────────────────────────────────────────
+
+const DEFAULT_LIMIT: u32 = 20;
+const MAX_LIMIT: u32 = 100;
+
+/// Seconds a resolved smart list is served before being re-run
+/// (`SMART_LIST_TTL_SECS`, default 300).
+pub fn smart_list_ttl_secs() -> i64 {
+    env::var("SMART_LIST_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300)
+}
+
+/// Checks a rule for contradictions before it is stored or previewed.
+pub fn validate_rule(rule: &ListRule) -> Result<(), String> {
+    if let (Some(from), Some(to)) = (rule.year_from, rule.year_to) {
+        if from > to {
+            return Err("year_from must not be after year_to.".to_string());
+        }
+    }
+    if rule.limit == Some(0) {
+        return Err("limit must be at least 1.".to_string());
+    }
+    Ok(())
+}
+
+/// Translates a rule into a `movies` filter. `Movie.year` is stored as a
+/// four-digit string, so the year range compares strings.
+pub fn rule_filter(rule: &ListRule) -> Document {
+    let mut filter = Document::new();
+    if let Some(genre) = &rule.genre {
+        filter.extend(movie_genre_filter(genre));
+    }
+    if let Some(is_series) = rule.is_series {
+        filter.insert("is_series", is_series);
+    }
+
+    let mut year = Document::new();
+    if let Some(from) = rule.year_from {
+        year.insert("$gte", format!("{:04}", from));
+    }
+    if let Some(to) = rule.year_to {
+        year.insert("$lte", format!("{:04}", to));
+    }
+    if !year.is_empty() {
+        filter.insert("year", year);
+    }
+
+    if let Some(days) = rule.added_since_days {
+        let since = DateTime::from_millis(
+            DateTime::now().timestamp_millis() - i64::from(days) * 24 * 60 * 60 * 1000,
+        );
+        filter.insert("created_at", doc! { "$gte": since });
+    }
+
+    filter
+}
+
+fn rule_sort(sort: RuleSort) -> Document {
+    match sort {
+        RuleSort::Newest => doc! { "created_at": -1, "_id": -1 },
+        RuleSort::Oldest => doc! { "created_at": 1, "_id": 1 },
+        RuleSort::Title => doc! { "title": 1 },
+        RuleSort::Year => doc! { "year": -1, "title": 1 },
+    }
+}
+
+// ── Resolution ────────────────────────────────────────────────────────────────
+
+/// Runs a rule against the catalog, returning matching movie summaries in rule order.
+/// Only titles inside their availability window are included.
+pub async fn resolve_rule(
+    movie_collection: &Collection<Movie>,
+    rule: &ListRule,
+) -> mongodb::error::Result<Vec<MovieSummary>> {
+    let limit = rule.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    movie_collection
+        .clone_with_type::<MovieSummary>()
+        .find(and(rule_filter(rule), movie_visible(DateTime::now())))
+        .sort(rule_sort(rule.sort))
+        .limit(i64::from(limit))
+        .projection(MovieSummary::projection())
+        .await?
+        .try_collect()
+        .await
+}
+
+/// The ids a rule currently resolves to, as stored in a list's `content`.
+pub async fn resolve_content(
+    movie_collection: &Collection<Movie>,
+    rule: &ListRule,
+) -> mongodb::error::Result<Vec<String>> {
+    Ok(resolve_rule(movie_collection, rule)
+        .await?
+        .into_iter()
+        .filter_map(|movie| movie.id.map(|oid| oid.to_hex()))
+        .collect())
+}
+
+/// Re-resolves every smart list whose cached `content` is missing or older
+/// than the TTL, storing the result back on the list, so static and smart
+/// lists can be read, filtered, sampled and expanded by the same queries.
+/// A list edited or invalidated while its rule runs is left for the next
+/// pass, and one list failing does not hold up the others.
+pub async fn refresh_stale_lists(
+    list_collection: &Collection<List>,
+    movie_collection: &Collection<Movie>,
+) -> mongodb::error::Result<()> {
+    let cutoff =
+        DateTime::from_millis(DateTime::now().timestamp_millis() - smart_list_ttl_secs() * 1000);
+
+    let stale: Vec<List> = list_collection
+        .find(doc! {
+            "rule": { "$ne": null },
+            "deleted_at": null,
+            "$or": [{ "resolved_at": null }, { "resolved_at": { "$lt": cutoff } }],
+        })
+        .await?
+        .try_collect()
+        .await?;
+
+    for list in stale {
+        let (Some(id), Some(rule)) = (list.id, list.rule) else {
+            continue;
+        };
+        let content = match resolve_content(movie_collection, &rule).await {
+            Ok(content) => content,
+            Err(e) => {
+                log::warn!("Failed to resolve smart list {}: {}", id, e);
+                continue;
+            }
+        };
+
+        // Only the list as it was read: same version (so the same rule) and
+        // not invalidated since.
+        let version = if list.version == 0 {
+            doc! { "$in": [0_i64, null] }
+        } else {
+            doc! { "$eq": list.version }
+        };
+        let filter = doc! { "_id": id, "version": version, "resolved_at": list.resolved_at };
+        if let Err(e) = list_collection
+            .update_one(
+                filter,
+                doc! { "$set": { "content": content, "resolved_at": DateTime::now() } },
+            )
+            .await
+        {
+            log::warn!("Failed to store smart list {}: {}", id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Marks every smart list stale so the refresher re-runs its rule.
+/// Call after catalog changes that could alter rule results.
+pub async fn invalidate_smart_lists(
+    list_collection: &Collection<List>,
+) -> mongodb::error::Result<()> {
+    list_collection
+        .update_many(
+            doc! { "rule": { "$ne": null } },
+            doc! { "$unset": { "resolved_at": "" } },
+        )
+        .await
+        .map(|_| ())
+}
+
+/// How often stale smart lists are re-resolved (`SMART_LIST_REFRESH_SECS`,
+/// default 30). Lists invalidated by catalog changes are stale for at most this long.
+fn smart_list_refresh_secs() -> u64 {
+    env::var("SMART_LIST_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30)
+}
+
+/// Spawns a background task that keeps smart lists resolved, so reads never
+/// wait on rule queries.
+pub fn spawn_smart_list_refresher(
+    list_collection: Collection<List>,
+    movie_collection: Collection<Movie>,
+) {
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(Duration::from_secs(smart_list_refresh_secs()));
+        loop {
+            interval.tick().await;
+            if let Err(e) = refresh_stale_lists(&list_collection, &movie_collection).await {
+                log::warn!("Failed to refresh smart lists: {}", e);
+            }
+        }
+    });
+}
+
+/// Spawns a listener that marks smart lists stale whenever a title goes live
+/// or expires, so rule results never outlive a licence window.
+pub fn spawn_invalidation_listener(list_collection: Collection<List>, events: &CatalogEvents) {
+    let mut receiver = events.subscribe();
+
+    actix_rt::spawn(async move {
+        loop {
+            let stale = match receiver.recv().await {
+                Ok(CatalogEvent::MovieLive(_)) | Ok(CatalogEvent::MovieExpired(_)) => true,
+                Ok(_) => false,
+                // Missed events may have included movie changes.
+                Err(RecvError::Lagged(_)) => true,
+                Err(RecvError::Closed) => break,
+            };
+            if stale {
+                if let Err(e) = invalidate_smart_lists(&list_collection).await {
+                    log::warn!("Failed to invalidate smart lists: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_rule_matches_everything() {
+        assert_eq!(rule_filter(&ListRule::default()), doc! {});
+    }
+
+    #[test]
+    fn translates_each_criterion() {
+        let rule = ListRule {
+            genre: Some("Sci-Fi".to_string()),
+            year_from: Some(1999),
+            year_to: Some(2005),
+            is_series: Some(true),
+            ..ListRule::default()
+        };
+        assert_eq!(
+            rule_filter(&rule),
+            doc! {
+                "$or": [{ "genres": "sci-fi" }, { "genre": "Sci-Fi" }],
+                "is_series": true,
+                "year": { "$gte": "1999", "$lte": "2005" },
+            }
+        );
+    }
+
+    #[test]
+    fn pads_years_to_compare_as_strings() {
+        let rule = ListRule {
+            year_to: Some(999),
+            ..ListRule::default()
+        };
+        assert_eq!(rule_filter(&rule), doc! { "year": { "$lte": "0999" } });
+    }
+
+    #[test]
+    fn added_since_counts_back_from_now() {
+        let rule = ListRule {
+            added_since_days: Some(7),
+            ..ListRule::default()
+        };
+        let before = DateTime::now().timestamp_millis();
+        let filter = rule_filter(&rule);
+        let since = filter
+            .get_document("created_at")
+            .unwrap()
+            .get_datetime("$gte")
+            .unwrap()
+            .timestamp_millis();
+        let week = 7 * 24 * 60 * 60 * 1000;
+        assert!(since >= before - week && since <= DateTime::now().timestamp_millis() - week);
+    }
+
+    #[test]
+    fn rejects_contradictory_rules() {
+        let backwards = ListRule {
+            year_from: Some(2010),
+            year_to: Some(2000),
+            ..ListRule::default()
+        };
+        assert!(validate_rule(&backwards).is_err());
+        let empty = ListRule {
+            limit: Some(0),
+            ..ListRule::default()
+        };
+        assert!(validate_rule(&empty).is_err());
+        assert!(validate_rule(&ListRule::default()).is_ok());
+    }
+}
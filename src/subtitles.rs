@@ -0,0 +1,482 @@
+use crate::models::subtitle::{AudioTrack, SubtitleTrack};
+use thiserror::Error;
+
+/// A self-contained subtitle error — no actix types here.
+/// Handlers map this to a 400 with the message as the body.
+#[derive(Error, Debug)]
+pub enum SubtitleError {
+    #[error("Subtitle file is empty")]
+    Empty,
+
+    #[error("Cue {0}: missing or malformed timing line")]
+    BadTiming(usize),
+
+    #[error("Cue {0}: end time must be after start time")]
+    EndBeforeStart(usize),
+
+    #[error("Cue {0}: starts before the previous cue")]
+    OutOfOrder(usize),
+
+    #[error("Cue {0}: timestamp exceeds 24 hours")]
+    TooLong(usize),
+
+    #[error("No cues found")]
+    NoCues,
+}
+
+/// One parsed cue, times in milliseconds.
+#[derive(Debug, Clone)]
+pub struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// WebVTT cue settings (`line:0 align:start` …); always empty for SRT.
+    pub settings: String,
+    pub text: String,
+}
+
+const MAX_TIMESTAMP_MS: u64 = 24 * 60 * 60 * 1000;
+
+// ── Encoding detection ────────────────────────────────────────────────────────
+
+/// Windows-1252 code points for bytes 0x80..=0x9F (everything else maps 1:1 to Latin-1).
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// Decodes subtitle bytes to text.
+///
+/// Honours UTF-8 and UTF-16 byte-order marks, accepts BOM-less UTF-8, and
+/// falls back to Windows-1252, the usual encoding of legacy SRT files.
+pub fn decode_text(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return String::from_utf8_lossy(rest).into_owned();
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes
+            .iter()
+            .map(|&b| match b {
+                0x80..=0x9F => CP1252_HIGH[(b - 0x80) as usize],
+                _ => b as char,
+            })
+            .collect(),
+    }
+}
+
+// ── Parsing ───────────────────────────────────────────────────────────────────
+
+/// Parses `HH:MM:SS,mmm` (SRT) or `[HH:]MM:SS.mmm` (WebVTT) into milliseconds.
+fn parse_timestamp(raw: &str) -> Option<u64> {
+    let (clock, millis) = raw.trim().split_once([',', '.'])?;
+    if millis.len() != 3 {
+        return None;
+    }
+    let millis: u64 = millis.parse().ok()?;
+
+    let parts: Vec<u64> = clock
+        .split(':')
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    let (h, m, s) = match parts.as_slice() {
+        [h, m, s] => (*h, *m, *s),
+        [m, s] => (0, *m, *s),
+        _ => return None,
+    };
+    if m > 59 || s > 59 {
+        return None;
+    }
+
+    Some(((h * 60 + m) * 60 + s) * 1000 + millis)
+}
+
+/// Parses a `start --> end [settings]` line.
+fn parse_timing(line: &str) -> Option<(u64, u64, String)> {
+    let (start, rest) = line.split_once("-->")?;
+    let mut rest = rest.split_whitespace();
+    let end = rest.next()?;
+    let settings = rest.collect::<Vec<_>>().join(" ");
+    Some((parse_timestamp(start)?, parse_timestamp(end)?, settings))
+}
+
+/// Tags SRT and WebVTT share; everything else in angle brackets is text.
+const CUE_TAGS: &[&str] = &["<i>", "</i>", "<b>", "</b>", "<u>", "</u>"];
+
+/// Strips SRT markup that WebVTT does not understand (`<font>` tags and
+/// `{\an8}`-style override codes). `<i>`, `<b>` and `<u>` pass through; any
+/// other `<` and `&` are escaped, as WebVTT cue text requires.
+fn clean_srt_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(['<', '{', '&']) {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if let Some(after) = tail.strip_prefix('&') {
+            out.push_str("&amp;");
+            rest = after;
+            continue;
+        }
+        let lower = tail.to_ascii_lowercase();
+        if let Some(tag) = CUE_TAGS.iter().find(|tag| lower.starts_with(**tag)) {
+            out.push_str(tag);
+            rest = &tail[tag.len()..];
+            continue;
+        }
+        let close = if tail.starts_with('{') { '}' } else { '>' };
+        let is_markup = tail.starts_with("{\\")
+            || lower.starts_with("<font")
+            || lower.starts_with("</font");
+        match (is_markup, tail.find(close)) {
+            (true, Some(end)) => rest = &tail[end + 1..],
+            _ => {
+                out.push_str(if tail.starts_with('<') { "&lt;" } else { &tail[..1] });
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Parses the cue blocks of an SRT or WebVTT document and sanity-checks timings.
+pub fn parse_cues(text: &str) -> Result<Vec<Cue>, SubtitleError> {
+    let normalized = text.replace("\r\n", "\n").replace('\r', "\n");
+    if normalized.trim().is_empty() {
+        return Err(SubtitleError::Empty);
+    }
+    let is_vtt = normalized.trim_start().starts_with("WEBVTT");
+
+    let mut cues: Vec<Cue> = Vec::new();
+    for block in normalized.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+        if lines.is_empty() {
+            continue;
+        }
+        if is_vtt && ["WEBVTT", "NOTE", "STYLE", "REGION"].iter().any(|k| lines[0].starts_with(k)) {
+            continue;
+        }
+
+        let number = cues.len() + 1;
+        // The first line is either the timing line or a cue identifier / SRT index.
+        let timing_at = if lines[0].contains("-->") { 0 } else { 1 };
+        let (start_ms, end_ms, settings) = lines
+            .get(timing_at)
+            .and_then(|l| parse_timing(l))
+            .ok_or(SubtitleError::BadTiming(number))?;
+
+        if end_ms <= start_ms {
+            return Err(SubtitleError::EndBeforeStart(number));
+        }
+        if end_ms > MAX_TIMESTAMP_MS {
+            return Err(SubtitleError::TooLong(number));
+        }
+        if cues.last().is_some_and(|prev| start_ms < prev.start_ms) {
+            return Err(SubtitleError::OutOfOrder(number));
+        }
+
+        let body = lines[timing_at + 1..].join("\n");
+        cues.push(Cue {
+            start_ms,
+            end_ms,
+            settings: if is_vtt { settings } else { String::new() },
+            text: if is_vtt { body } else { clean_srt_text(&body) },
+        });
+    }
+
+    if cues.is_empty() {
+        return Err(SubtitleError::NoCues);
+    }
+    Ok(cues)
+}
+
+// ── Rendering ─────────────────────────────────────────────────────────────────
+
+fn format_timestamp(ms: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// Renders cues as a WebVTT document.
+pub fn render_vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}",
+            i + 1,
+            format_timestamp(cue.start_ms),
+            format_timestamp(cue.end_ms)
+        ));
+        if !cue.settings.is_empty() {
+            out.push(' ');
+            out.push_str(&cue.settings);
+        }
+        out.push('\n');
+        out.push_str(&cue.text);
+        out.push_str("\n\n");
+    }
+    out
+}
+
+/// Duration of a cue list: the end of its last-ending cue.
+pub fn duration_ms(cues: &[Cue]) -> u64 {
+    cues.iter().map(|cue| cue.end_ms).max().unwrap_or(0)
+}
+
+/// Decodes, validates and converts an uploaded SRT or WebVTT file to WebVTT.
+/// Returns the document and its cue count.
+pub fn to_webvtt(bytes: &[u8]) -> Result<(String, usize), SubtitleError> {
+    let cues = parse_cues(&decode_text(bytes))?;
+    Ok((render_vtt(&cues), cues.len()))
+}
+
+// ── HLS playlists ─────────────────────────────────────────────────────────────
+
+/// Advertised bandwidth of the single video rendition. Players only use it to
+/// choose between variants, and a title has one.
+const VARIANT_BANDWIDTH: u64 = 5_000_000;
+
+/// Makes text safe inside a quoted playlist attribute, which may contain
+/// neither double quotes nor line breaks.
+fn quoted(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .map(|c| if c == '"' { '\'' } else { c })
+        .filter(|c| !c.is_control())
+        .collect();
+    format!("\"{}\"", text)
+}
+
+/// One-segment media playlist wrapping a WebVTT file, the form HLS players
+/// expect subtitle renditions in.
+pub fn render_subtitle_playlist(vtt_url: &str, duration_ms: u64) -> String {
+    let secs = duration_ms as f64 / 1000.0;
+    format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:{}\n#EXT-X-MEDIA-SEQUENCE:0\n\
+         #EXT-X-PLAYLIST-TYPE:VOD\n#EXTINF:{:.3},\n{}\n#EXT-X-ENDLIST\n",
+        duration_ms.div_ceil(1000).max(1),
+        secs,
+        vtt_url
+    )
+}
+
+/// Master playlist for `video_url` listing the audio tracks and subtitle
+/// renditions as `EXT-X-MEDIA` entries. `subtitles` pairs each track with
+/// the URL of its media playlist.
+pub fn render_master_playlist(
+    video_url: &str,
+    audio_tracks: &[&AudioTrack],
+    subtitles: &[(&SubtitleTrack, String)],
+) -> String {
+    let mut out = String::from("#EXTM3U\n#EXT-X-VERSION:4\n");
+
+    for track in audio_tracks {
+        let name = track.label.as_deref().unwrap_or(&track.lang);
+        out.push_str(&format!(
+            "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"audio\",NAME={},LANGUAGE={},DEFAULT={},\
+             AUTOSELECT=YES",
+            quoted(name),
+            quoted(&track.lang),
+            if track.is_default { "YES" } else { "NO" }
+        ));
+        if let Some(channels) = track.channels {
+            out.push_str(&format!(",CHANNELS=\"{}\"", channels));
+        }
+        // Without a URI the track is muxed into the video rendition.
+        if let Some(url) = &track.url {
+            out.push_str(&format!(",URI={}", quoted(url)));
+        }
+        out.push('\n');
+    }
+
+    for (track, playlist_url) in subtitles {
+        let name = track.label.as_deref().unwrap_or(&track.lang);
+        out.push_str(&format!(
+            "#EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"subs\",NAME={},LANGUAGE={},DEFAULT=NO,\
+             AUTOSELECT=YES,URI={}\n",
+            quoted(name),
+            quoted(&track.lang),
+            quoted(playlist_url)
+        ));
+    }
+
+    out.push_str(&format!(
+        "#EXT-X-STREAM-INF:BANDWIDTH={}",
+        VARIANT_BANDWIDTH
+    ));
+    if !audio_tracks.is_empty() {
+        out.push_str(",AUDIO=\"audio\"");
+    }
+    if !subtitles.is_empty() {
+        out.push_str(",SUBTITLES=\"subs\"");
+    }
+    out.push('\n');
+    out.push_str(video_url);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRT: &str = "1\r\n00:00:01,000 --> 00:00:02,500\r\n<font color=\"red\">Hello</font>\r\n\r\n\
+                       2\r\n00:00:03,000 --> 00:00:04,000\r\n{\\an8}<i>World</i>\r\n";
+
+    fn track(lang: &str, label: Option<&str>) -> SubtitleTrack {
+        SubtitleTrack {
+            lang: lang.to_string(),
+            label: label.map(str::to_string),
+            episode: None,
+            url: format!("/api/movies/m/subtitles/{}.vtt", lang),
+        }
+    }
+
+    #[test]
+    fn decodes_utf8_with_and_without_bom() {
+        assert_eq!(decode_text("olá".as_bytes()), "olá");
+        assert_eq!(decode_text(b"\xEF\xBB\xBFhi"), "hi");
+    }
+
+    #[test]
+    fn decodes_utf16_by_bom() {
+        assert_eq!(decode_text(&[0xFF, 0xFE, b'h', 0, b'i', 0]), "hi");
+        assert_eq!(decode_text(&[0xFE, 0xFF, 0, b'h', 0, b'i']), "hi");
+    }
+
+    #[test]
+    fn falls_back_to_windows_1252() {
+        // 0x93/0x94 are curly quotes and 0xE9 is é in Windows-1252.
+        assert_eq!(decode_text(b"\x93caf\xE9\x94"), "“café”");
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_timestamp("01:02:03,004"), Some(3_723_004));
+        assert_eq!(parse_timestamp("02:03.004"), Some(123_004));
+        assert_eq!(parse_timestamp("00:60:00,000"), None);
+        assert_eq!(parse_timestamp("00:00:01,5"), None);
+    }
+
+    #[test]
+    fn converts_srt_and_strips_markup() {
+        let cues = parse_cues(SRT).unwrap();
+        assert_eq!(cues.len(), 2);
+        assert_eq!((cues[0].start_ms, cues[0].end_ms), (1_000, 2_500));
+        assert_eq!(cues[0].text, "Hello");
+        assert_eq!(cues[1].text, "<i>World</i>");
+
+        let (vtt, count) = to_webvtt(SRT.as_bytes()).unwrap();
+        assert_eq!(count, 2);
+        assert!(vtt.starts_with("WEBVTT\n\n1\n00:00:01.000 --> 00:00:02.500\nHello\n"));
+    }
+
+    #[test]
+    fn escapes_text_outside_known_tags() {
+        assert_eq!(
+            clean_srt_text("<I>Tom & Jerry</I> <3 <font color=\"red\">x</font> {\\an8}a<b"),
+            "<i>Tom &amp; Jerry</i> &lt;3 x a&lt;b"
+        );
+    }
+
+    #[test]
+    fn keeps_webvtt_settings_and_skips_headers() {
+        let vtt = "WEBVTT\n\nNOTE a comment\n\nintro\n00:01.000 --> 00:02.000 line:0\nHi\n";
+        let cues = parse_cues(vtt).unwrap();
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].settings, "line:0");
+        assert_eq!(cues[0].text, "Hi");
+    }
+
+    #[test]
+    fn rejects_bad_timings() {
+        assert!(matches!(parse_cues("  \n"), Err(SubtitleError::Empty)));
+        assert!(matches!(
+            parse_cues("1\nnot a timing\nHi\n"),
+            Err(SubtitleError::BadTiming(1))
+        ));
+        assert!(matches!(
+            parse_cues("1\n00:00:02,000 --> 00:00:01,000\nHi\n"),
+            Err(SubtitleError::EndBeforeStart(1))
+        ));
+        assert!(matches!(
+            parse_cues(
+                "1\n00:00:05,000 --> 00:00:06,000\na\n\n2\n00:00:01,000 --> 00:00:02,000\nb\n"
+            ),
+            Err(SubtitleError::OutOfOrder(2))
+        ));
+        assert!(matches!(
+            parse_cues("1\n23:59:59,000 --> 24:00:00,001\nHi\n"),
+            Err(SubtitleError::TooLong(1))
+        ));
+        assert!(matches!(parse_cues("WEBVTT\n\nNOTE x\n"), Err(SubtitleError::NoCues)));
+    }
+
+    #[test]
+    fn subtitle_playlist_wraps_the_vtt() {
+        let playlist = render_subtitle_playlist("/s/en.vtt", 61_500);
+        assert!(playlist.contains("#EXT-X-TARGETDURATION:62\n"));
+        assert!(playlist.contains("#EXTINF:61.500,\n/s/en.vtt\n#EXT-X-ENDLIST\n"));
+    }
+
+    #[test]
+    fn master_playlist_lists_renditions() {
+        let audio = AudioTrack {
+            lang: "en".to_string(),
+            label: Some("English 5.1".to_string()),
+            episode: None,
+            codec: None,
+            channels: Some(6),
+            is_default: true,
+            url: None,
+        };
+        let subs = track("pt-BR", Some("Português \"forçado\""));
+        let master = render_master_playlist(
+            "https://cdn/v.m3u8",
+            &[&audio],
+            &[(&subs, "/api/movies/m/subtitles/pt-BR.m3u8".to_string())],
+        );
+
+        assert!(master.starts_with("#EXTM3U\n"));
+        assert!(master.contains(
+            "#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"audio\",NAME=\"English 5.1\",LANGUAGE=\"en\",\
+             DEFAULT=YES,AUTOSELECT=YES,CHANNELS=\"6\"\n"
+        ));
+        assert!(master.contains(
+            "TYPE=SUBTITLES,GROUP-ID=\"subs\",NAME=\"Português 'forçado'\",LANGUAGE=\"pt-BR\",\
+             DEFAULT=NO,AUTOSELECT=YES,URI=\"/api/movies/m/subtitles/pt-BR.m3u8\"\n"
+        ));
+        assert!(master.ends_with(
+            "#EXT-X-STREAM-INF:BANDWIDTH=5000000,AUDIO=\"audio\",SUBTITLES=\"subs\"\n\
+             https://cdn/v.m3u8\n"
+        ));
+    }
+
+    #[test]
+    fn master_playlist_without_tracks_has_no_groups() {
+        let master = render_master_playlist("/v.mp4", &[], &[]);
+        assert_eq!(
+            master,
+            "#EXTM3U\n#EXT-X-VERSION:4\n#EXT-X-STREAM-INF:BANDWIDTH=5000000\n/v.mp4\n"
+        );
+    }
+}
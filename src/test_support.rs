@@ -0,0 +1,18 @@
+use mongodb::bson::oid::ObjectId;
+use mongodb::{Client, Database};
+use std::env;
+
+/// A fresh, uniquely named database on the server at `TEST_MONGODB_URL`.
+/// Tests that need MongoDB are `#[ignore]`d and run with `--ignored`; they
+/// panic when the variable is unset rather than pass without a server.
+/// Callers drop the database when done.
+pub async fn test_database() -> Database {
+    let url = env::var("TEST_MONGODB_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .expect("TEST_MONGODB_URL must point at a MongoDB server to run this test");
+    let client = Client::with_uri_str(&url)
+        .await
+        .expect("TEST_MONGODB_URL must be a valid MongoDB URL");
+    client.database(&format!("test_{}", ObjectId::new().to_hex()))
+}
@@ -0,0 +1,163 @@
+use crate::catalog::not_deleted;
+use crate::locale::{fallback_chain, translated, DEFAULT_LOCALE};
+use crate::models::genre::Genre;
+use crate::models::list::{List, ListTranslation};
+use crate::models::movie::{Movie, MovieTranslation};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, oid::ObjectId};
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// ── Coverage report ───────────────────────────────────────────────────────────
+
+/// Ids listed as missing per field; the counts stay exact past it.
+const MAX_MISSING: usize = 100;
+
+/// How many items have a text to translate, how many of them a viewer of the
+/// locale sees translated (its fallbacks count, English does not), and which
+/// ones are missing.
+#[derive(Debug, Default, Serialize)]
+pub struct FieldCoverage {
+    pub total: u64,
+    pub translated: u64,
+    pub missing: Vec<String>,
+}
+
+impl FieldCoverage {
+    fn record(&mut self, id: &str, covered: bool) {
+        self.total += 1;
+        if covered {
+            self.translated += 1;
+        } else if self.missing.len() < MAX_MISSING {
+            self.missing.push(id.to_string());
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MovieCoverage {
+    pub title: FieldCoverage,
+    pub desc: FieldCoverage,
+    pub img_title: FieldCoverage,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ListCoverage {
+    pub title: FieldCoverage,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GenreCoverage {
+    pub name: FieldCoverage,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LocaleCoverage {
+    pub locale: String,
+    pub movies: MovieCoverage,
+    pub lists: ListCoverage,
+    pub genres: GenreCoverage,
+}
+
+/// The translatable text of a movie.
+#[derive(Deserialize)]
+struct MovieTexts {
+    #[serde(rename = "_id")]
+    id: ObjectId,
+    desc: Option<String>,
+    img_title: Option<String>,
+    #[serde(default)]
+    translations: BTreeMap<String, MovieTranslation>,
+}
+
+#[derive(Deserialize)]
+struct ListTexts {
+    #[serde(rename = "_id")]
+    id: ObjectId,
+    #[serde(default)]
+    translations: BTreeMap<String, ListTranslation>,
+}
+
+/// Translation coverage of movies and lists outside the trash, and of all
+/// genres, for each of `locales`; when empty, for every locale translated
+/// into anywhere.
+pub async fn coverage(
+    movie_collection: &Collection<Movie>,
+    list_collection: &Collection<List>,
+    genre_collection: &Collection<Genre>,
+    locales: Vec<String>,
+) -> mongodb::error::Result<Vec<LocaleCoverage>> {
+    let movies: Vec<MovieTexts> = movie_collection
+        .clone_with_type::<MovieTexts>()
+        .find(not_deleted())
+        .projection(doc! { "desc": 1, "img_title": 1, "translations": 1 })
+        .await?
+        .try_collect()
+        .await?;
+    let lists: Vec<ListTexts> = list_collection
+        .clone_with_type::<ListTexts>()
+        .find(not_deleted())
+        .projection(doc! { "translations": 1 })
+        .await?
+        .try_collect()
+        .await?;
+    let genres: Vec<Genre> = genre_collection.find(doc! {}).await?.try_collect().await?;
+
+    let locales: BTreeSet<String> = if locales.is_empty() {
+        movies
+            .iter()
+            .flat_map(|movie| movie.translations.keys())
+            .chain(lists.iter().flat_map(|list| list.translations.keys()))
+            .chain(genres.iter().flat_map(|genre| genre.names.keys()))
+            .filter(|tag| tag.as_str() != DEFAULT_LOCALE)
+            .cloned()
+            .collect()
+    } else {
+        locales.into_iter().collect()
+    };
+
+    Ok(locales
+        .into_iter()
+        .map(|locale| {
+            let chain = fallback_chain(&locale);
+
+            let mut movie_coverage = MovieCoverage::default();
+            for movie in &movies {
+                let id = movie.id.to_hex();
+                let has = |field: fn(&MovieTranslation) -> &Option<String>| {
+                    translated(&movie.translations, &chain, field).is_some()
+                };
+                movie_coverage.title.record(&id, has(|t| &t.title));
+                if movie.desc.is_some() {
+                    movie_coverage.desc.record(&id, has(|t| &t.desc));
+                }
+                if movie.img_title.is_some() {
+                    movie_coverage.img_title.record(&id, has(|t| &t.img_title));
+                }
+            }
+
+            let mut list_coverage = ListCoverage::default();
+            for list in &lists {
+                let covered = translated(&list.translations, &chain, |t| &t.title).is_some();
+                list_coverage.title.record(&list.id.to_hex(), covered);
+            }
+
+            let mut genre_coverage = GenreCoverage::default();
+            for genre in &genres {
+                let covered = chain
+                    .iter()
+                    .take_while(|tag| tag.as_str() != DEFAULT_LOCALE)
+                    .any(|tag| genre.names.contains_key(tag));
+                genre_coverage.name.record(&genre.slug, covered);
+            }
+
+            LocaleCoverage {
+                locale,
+                movies: movie_coverage,
+                lists: list_coverage,
+                genres: genre_coverage,
+            }
+        })
+        .collect())
+}
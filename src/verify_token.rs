@@ -1,6 +1,9 @@
+use crate::models::user::User;
 use crate::routes::auth::Claims;
-use actix_web::HttpRequest;
+use actix_web::{web, HttpRequest};
+use bson::doc;
 use jsonwebtoken::{decode, DecodingKey, Validation};
+use mongodb::Collection;
 use std::collections::HashMap;
 use std::env;
 use thiserror::Error;
@@ -40,6 +43,11 @@ pub fn get_jwt_token(req: &HttpRequest) -> Option<String> {
 // ── Verification ──────────────────────────────────────────────────────────────
 
 /// Decodes and validates the JWT, returning its claims as a flat string map.
+///
+/// Tokens do not expire on their own, so the account is looked up too: a
+/// token of a user who was moved to the trash (or purged) is refused, and
+/// `is_admin` and `role` come from the account rather than the token, so
+/// changes apply from the next request.
 pub async fn verify(
     req: HttpRequest,
 ) -> Result<HashMap<String, String>, AppError> {
@@ -54,13 +62,28 @@ pub async fn verify(
     )
     .map_err(|e| AppError::DecodeError(e.to_string()))?;
 
+    let users = req
+        .app_data::<web::Data<Collection<User>>>()
+        .ok_or(AppError::UserLookupUnavailable)?;
+    let user = users
+        .clone_with_type::<bson::Document>()
+        .find_one(doc! { "email": &token_data.claims.sub, "deleted_at": null })
+        .projection(doc! { "is_admin": 1, "role": 1 })
+        .await
+        .map_err(|e| AppError::UserLookup(e.to_string()))?
+        .ok_or(AppError::UserInactive)?;
+    let is_admin = user.get_bool("is_admin").unwrap_or(false);
+
     let mut claims_map = HashMap::new();
     claims_map.insert("sub".to_string(), token_data.claims.sub);
     claims_map.insert("exp".to_string(), token_data.claims.exp.to_string());
-    claims_map.insert(
-        "is_admin".to_string(),
-        token_data.claims.is_admin.to_string(),
-    );
+    claims_map.insert("is_admin".to_string(), is_admin.to_string());
+    if let (true, Ok(role)) = (is_admin, user.get_str("role")) {
+        claims_map.insert("role".to_string(), role.to_string());
+    }
+    if let Some(language) = token_data.claims.language {
+        claims_map.insert("language".to_string(), language);
+    }
 
     Ok(claims_map)
 }
@@ -77,4 +100,13 @@ pub enum AppError {
 
     #[error("Failed to decode token: {0}")]
     DecodeError(String),
+
+    #[error("The token's user is deleted or in the trash")]
+    UserInactive,
+
+    #[error("User collection not registered")]
+    UserLookupUnavailable,
+
+    #[error("Failed to look up the token's user: {0}")]
+    UserLookup(String),
 }
\ No newline at end of file